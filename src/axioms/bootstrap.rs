@@ -23,9 +23,62 @@ fn maj_define_standard_streams(mut state: &mut MajState) {
     state.push(stderr_sym, stderr);
 }
 
+#[inline]
+fn maj_define_eof(mut state: &mut MajState) {
+    let eof_sym = Maj::symbol(&mut state, "*eof*");
+    state.push(eof_sym, Maj::eof());
+}
+
+#[inline]
+fn maj_define_features(mut state: &mut MajState) {
+    use std::env::consts::{ ARCH, OS, FAMILY };
+    let target = include_str!(concat!(env!("OUT_DIR"), "/target.txt"));
+    let mut names = vec!["majestic", ARCH, OS, FAMILY, target];
+    if cfg!(feature = "dumb_terminal") {
+        names.push("dumb-terminal");
+    }
+    let mut features = Maj::nil();
+    for name in names.into_iter().rev() {
+        if !name.is_empty() {
+            features = Maj::cons(Maj::symbol(&mut state, name), features);
+        }
+    }
+    let features_sym = Maj::symbol(&mut state, "*features*");
+    state.push(features_sym, features);
+}
+
+#[inline]
+fn maj_define_printer_flags(mut state: &mut MajState) {
+    for name in &["*print-readably*", "*print-circle*"] {
+        let flag = Maj::symbol(&mut state, name);
+        state.declare_special(flag.clone());
+        state.push(flag, Maj::nil());
+    }
+    let margin = Maj::symbol(&mut state, "*print-right-margin*");
+    state.declare_special(margin.clone());
+    state.push(margin, Maj::integer(80));
+}
+
+#[inline]
+fn maj_define_indent_rules(mut state: &mut MajState) {
+    let rules = [
+        ("def", 1), ("defn", 2), ("defmac", 2), ("defmethod", 2),
+        ("fn", 1), ("mac", 1), ("let", 1), ("letrec", 1),
+        ("if", 1), ("cond", 0), ("while", 1), ("do", 0),
+    ];
+    for (name, args) in rules.iter() {
+        let sym = Maj::symbol(&mut state, name);
+        state.set_indent_rule(sym, *args);
+    }
+}
+
 fn maj_put_constants(mut state: &mut MajState) {
     maj_define_ulps(&mut state);
     maj_define_standard_streams(&mut state);
+    maj_define_eof(&mut state);
+    maj_define_features(&mut state);
+    maj_define_printer_flags(&mut state);
+    maj_define_indent_rules(&mut state);
 }
 
 #[inline]
@@ -544,6 +597,26 @@ fn bootstrap_with_open_stream(mut state: &mut MajState) -> Gc<Maj> {
                         maj_list!(Maj::unquote(), sym_sym))))))
 }
 
+fn bootstrap_ignore_errors(mut state: &mut MajState) -> Gc<Maj> {
+    let body_sym = Maj::symbol(&mut state, "body");
+
+    maj_list!(
+        Maj::symbol(&mut state, "defmac"),
+        Maj::symbol(&mut state, "ignore-errors"),
+        body_sym.clone(),
+        maj_list!(
+            Maj::quasiquote(),
+            maj_list!(
+                Maj::symbol(&mut state, "handler-case"),
+                maj_list!(
+                    Maj::do_sym(),
+                    maj_list!(Maj::unquote_splice(), body_sym)),
+                maj_list!(
+                    Maj::error(),
+                    Maj::nil(),
+                    Maj::nil()))))
+}
+
 fn bootstrap_repeat(mut state: &mut MajState) -> Gc<Maj> {
     let n       = Maj::symbol(&mut state, "n");
     let body    = Maj::symbol(&mut state, "body");
@@ -609,6 +682,448 @@ fn bootstrap_repeat(mut state: &mut MajState) -> Gc<Maj> {
                                 
 }
 
+#[inline]
+fn bootstrap_defsyntax(mut state: &mut MajState) -> Gc<Maj> {
+    let label_sym = Maj::symbol(&mut state, "label");
+    let literals_sym = Maj::symbol(&mut state, "literals");
+    let rules_sym = Maj::symbol(&mut state, "rules");
+    maj_list!(
+        Maj::symbol(&mut state, "defmac"),
+        Maj::symbol(&mut state, "defsyntax"),
+        maj_dotted_list!(
+            label_sym.clone(),
+            literals_sym.clone(),
+            rules_sym.clone()),
+        maj_list!(
+            Maj::quasiquote(),
+            maj_list!(
+                Maj::symbol(&mut state, "def"),
+                maj_list!(Maj::unquote(), label_sym),
+                maj_list!(
+                    Maj::syntax_rules(),
+                    maj_list!(Maj::unquote(), literals_sym),
+                    maj_list!(Maj::unquote_splice(), rules_sym)))))
+}
+
+#[inline]
+fn bootstrap_declaim(mut state: &mut MajState) -> Gc<Maj> {
+    let declarations_sym = Maj::symbol(&mut state, "declarations");
+    let d_sym = Maj::symbol(&mut state, "d");
+    maj_list!(
+        Maj::symbol(&mut state, "defmac"),
+        Maj::symbol(&mut state, "declaim"),
+        declarations_sym.clone(),
+        maj_list!(
+            Maj::quasiquote(),
+            maj_list!(
+                Maj::symbol(&mut state, "proclaim"),
+                maj_list!(
+                    Maj::unquote_splice(),
+                    maj_list!(
+                        Maj::symbol(&mut state, "map"),
+                        maj_list!(
+                            Maj::fn_sym(),
+                            maj_list!(d_sym.clone()),
+                            maj_list!(
+                                Maj::symbol(&mut state, "list"),
+                                maj_list!(Maj::quote(), Maj::quote()),
+                                d_sym)),
+                        declarations_sym)))))
+}
+
+#[inline]
+fn bootstrap_defgeneric(mut state: &mut MajState) -> Gc<Maj> {
+    let name = Maj::symbol(&mut state, "name");
+    let lambda_list = Maj::symbol(&mut state, "lambda-list");
+    let body = Maj::symbol(&mut state, "body");
+    maj_list!(
+        Maj::symbol(&mut state, "defmac"),
+        Maj::symbol(&mut state, "defgeneric"),
+        maj_dotted_list!(name.clone(), lambda_list.clone(),
+                         Maj::optional(), body.clone()),
+        maj_list!(
+            Maj::quasiquote(),
+            maj_list!(
+                Maj::do_sym(),
+                maj_list!(
+                    Maj::symbol(&mut state, "def"),
+                    maj_list!(Maj::unquote(), name.clone()),
+                    maj_list!(
+                        Maj::symbol(&mut state, "make-generic"),
+                        maj_list!(Maj::quote(),
+                                  maj_list!(Maj::unquote(), name.clone())),
+                        maj_list!(Maj::quote(),
+                                  maj_list!(Maj::unquote(),
+                                            lambda_list.clone())))),
+                maj_list!(
+                    Maj::unquote_splice(),
+                    maj_list!(
+                        Maj::symbol(&mut state, "when"),
+                        body.clone(),
+                        maj_list!(
+                            Maj::symbol(&mut state, "list"),
+                            maj_list!(
+                                Maj::quasiquote(),
+                                maj_list!(
+                                    Maj::symbol(&mut state, "defmethod"),
+                                    maj_list!(Maj::unquote(), name.clone()),
+                                    maj_list!(Maj::unquote(), lambda_list),
+                                    maj_list!(Maj::unquote_splice(),
+                                              body)))))),
+                maj_list!(Maj::quote(),
+                          maj_list!(Maj::unquote(), name)))))
+}
+
+#[inline]
+fn bootstrap_defrecord(mut state: &mut MajState) -> Gc<Maj> {
+    let name = Maj::symbol(&mut state, "name");
+    let fields = Maj::symbol(&mut state, "fields");
+    let field = Maj::symbol(&mut state, "field");
+    let x = Maj::symbol(&mut state, "x");
+    let defn = Maj::symbol(&mut state, "defn");
+    let intern = Maj::symbol(&mut state, "intern");
+    let format = Maj::symbol(&mut state, "format");
+    maj_list!(
+        Maj::symbol(&mut state, "defmac"),
+        Maj::symbol(&mut state, "defrecord"),
+        maj_dotted_list!(name.clone(), Maj::optional(), fields.clone()),
+        maj_list!(
+            Maj::quasiquote(),
+            maj_list!(
+                Maj::do_sym(),
+                maj_list!(
+                    defn.clone(),
+                    maj_list!(
+                        Maj::unquote(),
+                        maj_list!(
+                            intern.clone(),
+                            maj_list!(format.clone(),
+                                      Maj::string("make-{}"),
+                                      name.clone()))),
+                    maj_list!(Maj::unquote(), fields.clone()),
+                    maj_list!(
+                        Maj::symbol(&mut state, "make-record"),
+                        maj_list!(Maj::quote(),
+                                  maj_list!(Maj::unquote(), name.clone())),
+                        maj_list!(Maj::quote(),
+                                  maj_list!(Maj::unquote(), fields.clone())),
+                        maj_list!(Maj::unquote_splice(), fields.clone()))),
+                maj_list!(
+                    defn.clone(),
+                    maj_list!(
+                        Maj::unquote(),
+                        maj_list!(
+                            intern.clone(),
+                            maj_list!(format.clone(),
+                                      Maj::string("{}-p"),
+                                      name.clone()))),
+                    maj_list!(x.clone()),
+                    maj_list!(
+                        Maj::symbol(&mut state, "and"),
+                        maj_list!(Maj::symbol(&mut state, "recordp"),
+                                  x.clone()),
+                        maj_list!(
+                            Maj::symbol(&mut state, "eq"),
+                            maj_list!(Maj::symbol(&mut state, "type"),
+                                      x.clone()),
+                            maj_list!(Maj::quote(),
+                                      maj_list!(Maj::unquote(),
+                                                name.clone()))))),
+                maj_list!(
+                    Maj::unquote_splice(),
+                    maj_list!(
+                        Maj::symbol(&mut state, "map"),
+                        maj_list!(
+                            Maj::fn_sym(),
+                            maj_list!(field.clone()),
+                            maj_list!(
+                                Maj::quasiquote(),
+                                maj_list!(
+                                    defn,
+                                    maj_list!(
+                                        Maj::unquote(),
+                                        maj_list!(
+                                            intern,
+                                            maj_list!(format,
+                                                      Maj::string("{}-{}"),
+                                                      name.clone(),
+                                                      field.clone()))),
+                                    maj_list!(x.clone()),
+                                    maj_list!(
+                                        Maj::symbol(&mut state, "record-get"),
+                                        x,
+                                        maj_list!(
+                                            Maj::quote(),
+                                            maj_list!(Maj::unquote(),
+                                                      field)))))),
+                        fields)),
+                maj_list!(Maj::quote(),
+                          maj_list!(Maj::unquote(), name)))))
+}
+
+#[inline]
+fn bootstrap_print_object(mut state: &mut MajState) -> Gc<Maj> {
+    maj_list!(
+        Maj::symbol(&mut state, "defgeneric"),
+        Maj::symbol(&mut state, "print-object"),
+        maj_list!(Maj::symbol(&mut state, "object")))
+}
+
+#[inline]
+fn bootstrap_generator(mut state: &mut MajState) -> Gc<Maj> {
+    let body = Maj::symbol(&mut state, "body");
+    maj_list!(
+        Maj::symbol(&mut state, "defmac"),
+        Maj::symbol(&mut state, "generator"),
+        body.clone(),
+        maj_list!(
+            Maj::quasiquote(),
+            maj_list!(
+                Maj::symbol(&mut state, "make-coroutine"),
+                maj_dotted_list!(
+                    Maj::fn_sym(),
+                    Maj::nil(),
+                    maj_list!(Maj::unquote_splice(), body)))))
+}
+
+#[inline]
+fn bootstrap_break_continue(mut state: &mut MajState) -> Vec<Gc<Maj>> {
+    let value = Maj::symbol(&mut state, "value");
+    let throw = Maj::symbol(&mut state, "throw");
+    let defmac = Maj::symbol(&mut state, "defmac");
+    let break_sym = Maj::symbol(&mut state, "break");
+    let continue_sym = Maj::symbol(&mut state, "continue");
+    vec![
+        maj_list!(
+            defmac.clone(),
+            break_sym.clone(),
+            maj_list!(Maj::optional(), value.clone()),
+            maj_list!(
+                Maj::quasiquote(),
+                maj_list!(
+                    throw.clone(),
+                    maj_list!(Maj::quote(), break_sym),
+                    maj_list!(Maj::unquote(), value)))),
+        maj_list!(
+            defmac,
+            continue_sym.clone(),
+            Maj::nil(),
+            maj_list!(
+                Maj::quote(),
+                maj_list!(
+                    throw,
+                    maj_list!(Maj::quote(), continue_sym),
+                    Maj::nil()))),
+    ]
+}
+
+#[inline]
+fn bootstrap_dotimes(mut state: &mut MajState) -> Gc<Maj> {
+    let spec = Maj::symbol(&mut state, "spec");
+    let body = Maj::symbol(&mut state, "body");
+    let var = Maj::symbol(&mut state, "var");
+    let count = Maj::symbol(&mut state, "count");
+    maj_list!(
+        Maj::symbol(&mut state, "defmac"),
+        Maj::symbol(&mut state, "dotimes"),
+        maj_dotted_list!(spec.clone(), body.clone()),
+        maj_list!(
+            Maj::symbol(&mut state, "let"),
+            maj_list!(
+                maj_list!(var.clone(),
+                          maj_list!(Maj::symbol(&mut state, "first"),
+                                    spec.clone())),
+                maj_list!(count.clone(),
+                          maj_list!(Maj::symbol(&mut state, "gensym")))),
+            maj_list!(
+                Maj::quasiquote(),
+                maj_list!(
+                    Maj::symbol(&mut state, "let"),
+                    maj_list!(
+                        maj_list!(
+                            maj_list!(Maj::unquote(), count.clone()),
+                            maj_list!(
+                                Maj::unquote(),
+                                maj_list!(Maj::symbol(&mut state, "second"),
+                                          spec.clone()))),
+                        maj_list!(
+                            maj_list!(Maj::unquote(), var.clone()),
+                            Maj::integer(0))),
+                    maj_list!(
+                        Maj::symbol(&mut state, "catch"),
+                        maj_list!(Maj::quote(),
+                                  Maj::symbol(&mut state, "break")),
+                        maj_list!(
+                            Maj::symbol(&mut state, "while"),
+                            maj_list!(
+                                Maj::symbol(&mut state, "<"),
+                                maj_list!(Maj::unquote(), var.clone()),
+                                maj_list!(Maj::unquote(), count)),
+                            maj_list!(
+                                Maj::symbol(&mut state, "catch"),
+                                maj_list!(Maj::quote(),
+                                          Maj::symbol(&mut state, "continue")),
+                                maj_list!(Maj::unquote_splice(), body)),
+                            maj_list!(
+                                Maj::symbol(&mut state, "set"),
+                                maj_list!(Maj::unquote(), var.clone()),
+                                maj_list!(
+                                    Maj::symbol(&mut state, "1+"),
+                                    maj_list!(Maj::unquote(), var)))),
+                        maj_list!(
+                            Maj::unquote(),
+                            maj_list!(Maj::symbol(&mut state, "third"),
+                                      spec)))))))
+}
+
+#[inline]
+fn bootstrap_dolist(mut state: &mut MajState) -> Gc<Maj> {
+    let spec = Maj::symbol(&mut state, "spec");
+    let body = Maj::symbol(&mut state, "body");
+    let var = Maj::symbol(&mut state, "var");
+    let iter = Maj::symbol(&mut state, "iter");
+    let set = Maj::symbol(&mut state, "set");
+    maj_list!(
+        Maj::symbol(&mut state, "defmac"),
+        Maj::symbol(&mut state, "dolist"),
+        maj_dotted_list!(spec.clone(), body.clone()),
+        maj_list!(
+            Maj::symbol(&mut state, "let"),
+            maj_list!(
+                maj_list!(var.clone(),
+                          maj_list!(Maj::symbol(&mut state, "first"),
+                                    spec.clone())),
+                maj_list!(iter.clone(),
+                          maj_list!(Maj::symbol(&mut state, "gensym")))),
+            maj_list!(
+                Maj::quasiquote(),
+                maj_list!(
+                    Maj::symbol(&mut state, "let"),
+                    maj_list!(
+                        maj_list!(
+                            maj_list!(Maj::unquote(), iter.clone()),
+                            maj_list!(
+                                Maj::unquote(),
+                                maj_list!(Maj::symbol(&mut state, "second"),
+                                          spec.clone()))),
+                        maj_list!(
+                            maj_list!(Maj::unquote(), var.clone()),
+                            Maj::nil())),
+                    maj_list!(
+                        Maj::symbol(&mut state, "catch"),
+                        maj_list!(Maj::quote(),
+                                  Maj::symbol(&mut state, "break")),
+                        maj_list!(
+                            Maj::symbol(&mut state, "while"),
+                            maj_list!(Maj::unquote(), iter.clone()),
+                            maj_list!(
+                                set.clone(),
+                                maj_list!(Maj::unquote(), var.clone()),
+                                maj_list!(
+                                    Maj::symbol(&mut state, "car"),
+                                    maj_list!(Maj::unquote(), iter.clone()))),
+                            maj_list!(
+                                set,
+                                maj_list!(Maj::unquote(), iter.clone()),
+                                maj_list!(
+                                    Maj::symbol(&mut state, "cdr"),
+                                    maj_list!(Maj::unquote(), iter))),
+                            maj_list!(
+                                Maj::symbol(&mut state, "catch"),
+                                maj_list!(Maj::quote(),
+                                          Maj::symbol(&mut state, "continue")),
+                                maj_list!(Maj::unquote_splice(), body))),
+                        maj_list!(
+                            Maj::unquote(),
+                            maj_list!(Maj::symbol(&mut state, "third"),
+                                      spec)))))))
+}
+
+#[inline]
+fn bootstrap_dovec(mut state: &mut MajState) -> Gc<Maj> {
+    let spec = Maj::symbol(&mut state, "spec");
+    let body = Maj::symbol(&mut state, "body");
+    let var = Maj::symbol(&mut state, "var");
+    let vec = Maj::symbol(&mut state, "vec");
+    let index = Maj::symbol(&mut state, "index");
+    let gensym = Maj::symbol(&mut state, "gensym");
+    let set = Maj::symbol(&mut state, "set");
+    maj_list!(
+        Maj::symbol(&mut state, "defmac"),
+        Maj::symbol(&mut state, "dovec"),
+        maj_dotted_list!(spec.clone(), body.clone()),
+        maj_list!(
+            Maj::symbol(&mut state, "let"),
+            maj_list!(
+                maj_list!(var.clone(),
+                          maj_list!(Maj::symbol(&mut state, "first"),
+                                    spec.clone())),
+                maj_list!(vec.clone(), maj_list!(gensym.clone())),
+                maj_list!(index.clone(), maj_list!(gensym))),
+            maj_list!(
+                Maj::quasiquote(),
+                maj_list!(
+                    Maj::symbol(&mut state, "let"),
+                    maj_list!(
+                        maj_list!(
+                            maj_list!(Maj::unquote(), vec.clone()),
+                            maj_list!(
+                                Maj::unquote(),
+                                maj_list!(Maj::symbol(&mut state, "second"),
+                                          spec.clone()))),
+                        maj_list!(
+                            maj_list!(Maj::unquote(), index.clone()),
+                            Maj::integer(0)),
+                        maj_list!(
+                            maj_list!(Maj::unquote(), var.clone()),
+                            Maj::nil())),
+                    maj_list!(
+                        Maj::symbol(&mut state, "catch"),
+                        maj_list!(Maj::quote(),
+                                  Maj::symbol(&mut state, "break")),
+                        maj_list!(
+                            Maj::symbol(&mut state, "while"),
+                            maj_list!(
+                                Maj::symbol(&mut state, "<"),
+                                maj_list!(Maj::unquote(), index.clone()),
+                                maj_list!(
+                                    Maj::symbol(&mut state, "vec-length"),
+                                    maj_list!(Maj::unquote(), vec.clone()))),
+                            maj_list!(
+                                set.clone(),
+                                maj_list!(Maj::unquote(), var),
+                                maj_list!(
+                                    Maj::symbol(&mut state, "vec-at"),
+                                    maj_list!(Maj::unquote(), index.clone()),
+                                    maj_list!(Maj::unquote(), vec))),
+                            maj_list!(
+                                set,
+                                maj_list!(Maj::unquote(), index.clone()),
+                                maj_list!(
+                                    Maj::symbol(&mut state, "1+"),
+                                    maj_list!(Maj::unquote(), index))),
+                            maj_list!(
+                                Maj::symbol(&mut state, "catch"),
+                                maj_list!(Maj::quote(),
+                                          Maj::symbol(&mut state, "continue")),
+                                maj_list!(Maj::unquote_splice(), body))),
+                        maj_list!(
+                            Maj::unquote(),
+                            maj_list!(Maj::symbol(&mut state, "third"),
+                                      spec)))))))
+}
+
+#[inline]
+fn bootstrap_loop(mut state: &mut MajState) -> Gc<Maj> {
+    let clauses = Maj::symbol(&mut state, "clauses");
+    maj_list!(
+        Maj::symbol(&mut state, "defmac"),
+        Maj::symbol(&mut state, "loop"),
+        clauses.clone(),
+        maj_list!(Maj::symbol(&mut state, "loop-expand"),
+                  clauses))
+}
+
 #[inline]
 fn bootstrap_car_cdr(mut state: &mut MajState) -> Vec<Gc<Maj>> {
     let def  = Maj::symbol(&mut state, "def");
@@ -1024,7 +1539,17 @@ pub fn maj_gen_bootstrap(mut state: &mut MajState) {
         bootstrap_unless(&mut state),
         bootstrap_until(&mut state),
         bootstrap_with_open_stream(&mut state),
+        bootstrap_ignore_errors(&mut state),
         bootstrap_repeat(&mut state),
+        bootstrap_defsyntax(&mut state),
+        bootstrap_declaim(&mut state),
+        bootstrap_defgeneric(&mut state),
+        bootstrap_defrecord(&mut state),
+        bootstrap_generator(&mut state),
+        bootstrap_dotimes(&mut state),
+        bootstrap_dolist(&mut state),
+        bootstrap_dovec(&mut state),
+        bootstrap_loop(&mut state),
         bootstrap_map(&mut state),
         bootstrap_mapc(&mut state),
         bootstrap_vectorequal(&mut state),
@@ -1033,8 +1558,10 @@ pub fn maj_gen_bootstrap(mut state: &mut MajState) {
         bootstrap_assp(&mut state),
         bootstrap_assoc(&mut state),
         bootstrap_member(&mut state),
+        bootstrap_print_object(&mut state),
     ]);
     expressions.append(&mut bootstrap_car_cdr(&mut state));
+    expressions.append(&mut bootstrap_break_continue(&mut state));
     expressions.append(&mut bootstrap_one_plusless(&mut state));
     for expression in expressions.iter() {
         let e = maj_eval(&mut state,
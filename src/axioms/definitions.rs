@@ -25,6 +25,23 @@ pub enum MajRawSym {
     Fraction       = 18,
     Complex        = 19,
     Vector         = 20,
+    Condition      = 21,
+    UnboundVar     = 22,
+    ArityError     = 23,
+    TypeError      = 24,
+    IoError        = 25,
+    Continuation   = 26,
+    Optional       = 27,
+    Key            = 28,
+    MatchError     = 29,
+    SyntaxRules    = 30,
+    Record         = 31,
+    Generic        = 32,
+    NextMethod     = 33,
+    Coroutine      = 34,
+    Global         = 35,
+    Eof            = 36,
+    Reader         = 37,
 }
 
 pub fn maj_gen_symbols(mut state: &mut MajState) {
@@ -50,4 +67,22 @@ pub fn maj_gen_symbols(mut state: &mut MajState) {
     let _ = Maj::symbol(&mut state, "fraction");       // 18
     let _ = Maj::symbol(&mut state, "complex");        // 19
     let _ = Maj::symbol(&mut state, "vector");         // 20
+    let _ = Maj::symbol(&mut state, "condition");      // 21
+    let _ = Maj::symbol(&mut state, "unbound-variable"); // 22
+    let _ = Maj::symbol(&mut state, "arity-error");    // 23
+    let _ = Maj::symbol(&mut state, "type-error");     // 24
+    let _ = Maj::symbol(&mut state, "io-error");       // 25
+    let _ = Maj::symbol(&mut state, "continuation");   // 26
+    let _ = Maj::symbol(&mut state, "&optional");      // 27
+    let _ = Maj::symbol(&mut state, "&key");           // 28
+    let _ = Maj::symbol(&mut state, "match-error");    // 29
+    let _ = Maj::symbol(&mut state, "syntax-rules");   // 30
+    let _ = Maj::symbol(&mut state, "record");         // 31
+    let _ = Maj::symbol(&mut state, "generic");        // 32
+    let _ = Maj::symbol(&mut state, "next-method");    // 33
+    let _ = Maj::symbol(&mut state, "coroutine");      // 34
+    let _ = Maj::symbol(&mut state, "global");         // 35
+    // Uninterned, so that the reader can never produce it
+    let _ = Maj::gensym(&mut state);                   // 36
+    let _ = Maj::symbol(&mut state, "reader");         // 37
 }
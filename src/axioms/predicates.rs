@@ -5,7 +5,7 @@ use crate::core::MajState;
 use super::primitives::{
     maj_car,
     maj_cdr,
-    maj_err
+    maj_type_err
 };
 
 pub fn maj_symbolp(x: Gc<Maj>) -> Gc<Maj> {
@@ -60,12 +60,12 @@ pub fn maj_char_equals(x: Gc<Maj>, y: Gc<Maj>) -> Gc<Maj> {
                 Maj::nil()
             }
         } else {
-            maj_err(Maj::string("{} is not a character"),
-                    maj_list!(y))
+            maj_type_err(Maj::string("{} is not a character"),
+                         maj_list!(y))
         }
     } else {
-        maj_err(Maj::string("{} is not a character"),
-                maj_list!(x))
+        maj_type_err(Maj::string("{} is not a character"),
+                     maj_list!(x))
     }
 }
 
@@ -167,13 +167,23 @@ pub fn maj_proper_list_p(x: Gc<Maj>) -> Gc<Maj> {
         return Maj::nil();
     }
 
-    let mut itr = x.clone();
+    let mut itr    = x.clone();
+    let mut slow   = x;
+    let mut length = 0;
     while !maj_nilp(itr.clone()).to_bool()  {
         if let Maj::Cons { car: _, cdr } = &*itr.clone() {
             itr = cdr.clone();
         } else {
             return Maj::nil();
         }
+        length += 1;
+        // Slow iterator only meets the other one on cycles
+        if length % 2 == 0 {
+            slow = maj_cdr(slow);
+            if Gc::ptr_eq(&itr, &slow) {
+                return Maj::nil();
+            }
+        }
     }
     Maj::t()
 }
@@ -218,7 +228,10 @@ pub fn maj_closurep(x: Gc<Maj>) -> Gc<Maj> {
 
 pub fn maj_functionp(x: Gc<Maj>) -> Gc<Maj> {
     if maj_primitivep(x.clone()).to_bool()
-        || maj_closurep(x).to_bool()
+        || maj_closurep(x.clone()).to_bool()
+        || maj_continuationp(x.clone()).to_bool()
+        || maj_genericp(x.clone()).to_bool()
+        || maj_next_method_p(x).to_bool()
     {
         return Maj::t();
     }
@@ -245,6 +258,85 @@ pub fn maj_errorp(x: Gc<Maj>) -> Gc<Maj> {
     Maj::nil()
 }
 
+pub fn maj_conditionp(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_literalp(x.clone()).to_bool() {
+        let sym = maj_car(maj_cdr(x));
+        if maj_eq(sym, Maj::condition()).to_bool() {
+            return Maj::t();
+        }
+    }
+    Maj::nil()
+}
+
+pub fn maj_continuationp(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_literalp(x.clone()).to_bool() {
+        let sym = maj_car(maj_cdr(x));
+        if maj_eq(sym, Maj::continuation()).to_bool() {
+            return Maj::t();
+        }
+    }
+    Maj::nil()
+}
+
+pub fn maj_recordp(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_literalp(x.clone()).to_bool() {
+        let sym = maj_car(maj_cdr(x));
+        if maj_eq(sym, Maj::record()).to_bool() {
+            return Maj::t();
+        }
+    }
+    Maj::nil()
+}
+
+pub fn maj_genericp(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_literalp(x.clone()).to_bool() {
+        let sym = maj_car(maj_cdr(x));
+        if maj_eq(sym, Maj::generic()).to_bool() {
+            return Maj::t();
+        }
+    }
+    Maj::nil()
+}
+
+pub fn maj_next_method_p(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_literalp(x.clone()).to_bool() {
+        let sym = maj_car(maj_cdr(x));
+        if maj_eq(sym, Maj::next_method()).to_bool() {
+            return Maj::t();
+        }
+    }
+    Maj::nil()
+}
+
+pub fn maj_coroutinep(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_literalp(x.clone()).to_bool() {
+        let sym = maj_car(maj_cdr(x));
+        if maj_eq(sym, Maj::coroutine()).to_bool() {
+            return Maj::t();
+        }
+    }
+    Maj::nil()
+}
+
+pub fn maj_readerp(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_literalp(x.clone()).to_bool() {
+        let sym = maj_car(maj_cdr(x));
+        if maj_eq(sym, Maj::reader()).to_bool() {
+            return Maj::t();
+        }
+    }
+    Maj::nil()
+}
+
+pub fn maj_keywordp(state: &MajState, x: Gc<Maj>) -> Gc<Maj> {
+    if let Maj::Sym(idx) = *x {
+        if state.keywordp(&idx) {
+            return Maj::t();
+        }
+    }
+    Maj::nil()
+}
+
 pub fn maj_zerop(
     mut state: &mut MajState,
     env: Gc<Maj>,
@@ -352,6 +444,34 @@ pub fn maj_gen_predicates(state: &mut MajState) {
             maj_destructure_args!(args, first);
             maj_errorp(first)
         }),
+        ("conditionp", MajPrimArgs::Required(1), |_, args, _| {
+            maj_destructure_args!(args, first);
+            maj_conditionp(first)
+        }),
+        ("continuationp", MajPrimArgs::Required(1), |_, args, _| {
+            maj_destructure_args!(args, first);
+            maj_continuationp(first)
+        }),
+        ("recordp", MajPrimArgs::Required(1), |_, args, _| {
+            maj_destructure_args!(args, first);
+            maj_recordp(first)
+        }),
+        ("genericp", MajPrimArgs::Required(1), |_, args, _| {
+            maj_destructure_args!(args, first);
+            maj_genericp(first)
+        }),
+        ("coroutinep", MajPrimArgs::Required(1), |_, args, _| {
+            maj_destructure_args!(args, first);
+            maj_coroutinep(first)
+        }),
+        ("readerp", MajPrimArgs::Required(1), |_, args, _| {
+            maj_destructure_args!(args, first);
+            maj_readerp(first)
+        }),
+        ("keywordp", MajPrimArgs::Required(1), |state, args, _| {
+            maj_destructure_args!(args, first);
+            maj_keywordp(state, first)
+        }),
         ("zerop", MajPrimArgs::Required(1),
          |mut state, args, env| {
              maj_destructure_args!(args, first);
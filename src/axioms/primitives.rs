@@ -42,8 +42,8 @@ pub fn maj_car(x: Gc<Maj>) -> Gc<Maj> {
         },
         _ => {}
     }
-    maj_err(Maj::string("{} is not a cons cell"),
-            maj_list!(x))
+    maj_type_err(Maj::string("{} is not a cons cell"),
+                 maj_list!(x))
 }
 
 pub fn maj_cdr(x: Gc<Maj>) -> Gc<Maj> {
@@ -58,8 +58,8 @@ pub fn maj_cdr(x: Gc<Maj>) -> Gc<Maj> {
         },
         _ => {}
     }
-    maj_err(Maj::string("{} is not a cons cell"),
-            maj_list!(x))
+    maj_type_err(Maj::string("{} is not a cons cell"),
+                 maj_list!(x))
 }
 
 pub fn maj_copy(x: Gc<Maj>) -> Gc<Maj> {
@@ -67,7 +67,7 @@ pub fn maj_copy(x: Gc<Maj>) -> Gc<Maj> {
         Maj::Cons { car, cdr } => {
             Maj::cons(car.clone(), cdr.clone())
         }
-        _ => maj_err(
+        _ => maj_type_err(
             Maj::string("{} is not a cons cell"),
             maj_list!(x))
     }
@@ -77,28 +77,47 @@ pub fn maj_length(x: Gc<Maj>) -> Gc<Maj> {
     if maj_nilp(x.clone()).to_bool() {
         Maj::integer(0)
     } else if !maj_consp(x.clone()).to_bool() {
-        maj_err(
+        maj_type_err(
             Maj::string("{} is not a proper list"),
             maj_list!(x.clone()))
     } else {
         let mut itr    = x.clone();
+        let mut slow   = x.clone();
         let mut length = 0;
         while maj_consp(itr.clone()).to_bool() {
             length += 1;
             itr = maj_cdr(itr);
+            // Slow iterator only meets the other one on cycles
+            if length % 2 == 0 {
+                slow = maj_cdr(slow);
+                if Gc::ptr_eq(&itr, &slow) {
+                    return maj_type_err(
+                        Maj::string("{} is a circular list"),
+                        maj_list!(x));
+                }
+            }
         }
         Maj::integer(length)
     }
 }
 
-fn maj_depth_helper(x: Gc<Maj>) -> i64 {
+fn maj_depth_helper(
+    x: Gc<Maj>,
+    path: &mut std::collections::HashSet<usize>
+) -> Option<i64> {
     use std::cmp;
     if !maj_consp(x.clone()).to_bool() {
-        0
-    } else {
-        1 + cmp::max(maj_depth_helper(maj_car(x.clone())),
-                     maj_depth_helper(maj_cdr(x)))
+        return Some(0);
+    }
+    // Reaching a cell which is already being measured means a cycle
+    let key = &*x as *const Maj as usize;
+    if !path.insert(key) {
+        return None;
     }
+    let depth = cmp::max(maj_depth_helper(maj_car(x.clone()), path)?,
+                         maj_depth_helper(maj_cdr(x), path)?);
+    path.remove(&key);
+    Some(1 + depth)
 }
 
 pub fn maj_depth(x: Gc<Maj>) -> Gc<Maj> {
@@ -109,12 +128,23 @@ pub fn maj_depth(x: Gc<Maj>) -> Gc<Maj> {
         maj_err(Maj::string(
             "{} is an atom"), maj_list!(x))
     } else {
-        Maj::integer(maj_depth_helper(x))
+        use std::collections::HashSet;
+        match maj_depth_helper(x.clone(), &mut HashSet::new()) {
+            Some(depth) => Maj::integer(depth),
+            None => maj_type_err(
+                Maj::string("{} is a circular structure"),
+                maj_list!(x)),
+        }
     }
 }
 
 pub fn maj_type(mut state: &mut MajState, x: Gc<Maj>) -> Gc<Maj> {
     use crate::core::types::MajNumber;
+    use crate::axioms::predicates::maj_recordp;
+    // Records have user-defined types
+    if maj_recordp(x.clone()).to_bool() {
+        return maj_car(maj_cdr(maj_cdr(x)));
+    }
     Maj::symbol(
         &mut state,
         match &*x {
@@ -148,8 +178,8 @@ pub fn maj_intern(mut state: &mut MajState, x: Gc<Maj>) -> Gc<Maj> {
             }
         },
         None => {
-            maj_err(Maj::string("{} is not a string"),
-                    maj_list!(x))
+            maj_type_err(Maj::string("{} is not a string"),
+                         maj_list!(x))
         },
     }
 }
@@ -159,25 +189,24 @@ pub fn maj_name(state: &MajState, x: Gc<Maj>) -> Gc<Maj> {
     if let Maj::Sym(_) = &*x.clone() {
         Maj::string(&maj_format(&state, x))
     } else {
-        maj_err(Maj::string("{} is not a symbol"),
-                maj_list!(x.clone()))
+        maj_type_err(Maj::string("{} is not a symbol"),
+                     maj_list!(x.clone()))
     }
 }
 
+fn maj_option_p(state: &mut MajState, x: Gc<Maj>, name: &str) -> bool {
+    maj_eq(x.clone(), Maj::symbol(state, name)).to_bool()
+        || maj_eq(x, Maj::symbol(state, &format!(":{}", name))).to_bool()
+}
+
 pub fn maj_get_environment(
     mut state: &mut MajState,
     type_sym: Gc<Maj>,
     env: Gc<Maj>
 ) -> Gc<Maj> {
-    if maj_eq(type_sym.clone(),
-              Maj::symbol(&mut state, "lexical"))
-        .to_bool()
-    {
+    if maj_option_p(&mut state, type_sym.clone(), "lexical") {
         env
-    } else if maj_eq(type_sym.clone(),
-                     Maj::symbol(&mut state, "global"))
-        .to_bool()
-    {
+    } else if maj_option_p(&mut state, type_sym.clone(), "global") {
         state.get_global_env()
     } else {
         maj_err(
@@ -186,6 +215,41 @@ pub fn maj_get_environment(
     }
 }
 
+pub fn maj_eval_prim(mut state: &mut MajState,
+                     form: Gc<Maj>,
+                     rest: Gc<Maj>) -> Gc<Maj> {
+    use crate::evaluator::maj_eval;
+    use crate::evaluator::evaluation::maj_expand_all;
+    if !maj_nilp(maj_cdr(rest.clone())).to_bool() {
+        return maj_arity_err(
+            Maj::string("Too many arguments to eval"),
+            Maj::nil());
+    }
+    let env = maj_car(rest);
+    let env = if maj_eq(env.clone(), state.get_global_env()).to_bool() {
+        Maj::nil()
+    } else if maj_environment_p(env.clone()) {
+        env
+    } else {
+        return maj_type_err(
+            Maj::string("{} is not an environment"),
+            maj_list!(env));
+    };
+    let form = maj_expand_all(&mut state, form, env.clone());
+    maj_eval(&mut state, form, env)
+}
+
+fn maj_environment_p(env: Gc<Maj>) -> bool {
+    let mut iter = env;
+    while maj_consp(iter.clone()).to_bool() {
+        if !maj_consp(maj_car(iter.clone())).to_bool() {
+            return false;
+        }
+        iter = maj_cdr(iter);
+    }
+    maj_nilp(iter).to_bool()
+}
+
 pub fn maj_coin() -> Gc<Maj> {
     use rand::random;
 
@@ -200,7 +264,7 @@ pub fn maj_sys(com: Gc<Maj>, args: Gc<Maj>) -> Gc<Maj> {
     use std::process::Command;
 
     if !maj_stringp(com.clone()).to_bool() {
-        return maj_err(
+        return maj_type_err(
             Maj::string("{} is not a string"),
             maj_list!(com));
     }
@@ -211,7 +275,7 @@ pub fn maj_sys(com: Gc<Maj>, args: Gc<Maj>) -> Gc<Maj> {
     while !maj_nilp(itr.clone()).to_bool() {
         if let Maj::Cons { car, cdr } = &*itr.clone() {
             if !maj_stringp(car.clone()).to_bool() {
-                return maj_err(
+                return maj_type_err(
                     Maj::string("{} is not a string"),
                     maj_list!(car.clone()));
             } else {
@@ -230,7 +294,7 @@ pub fn maj_sys(com: Gc<Maj>, args: Gc<Maj>) -> Gc<Maj> {
 
     match result {
         Some(code) => Maj::integer(code as i64),
-        None => maj_err(
+        None => maj_io_err(
             Maj::string("Error executing command {} {}"),
             maj_list!(com, args)),
     }
@@ -297,11 +361,22 @@ pub fn maj_format_prim(
         }
         Maj::string(buffer.as_ref())
     } else {
-        maj_err(Maj::string("{} is not a string"),
-                maj_list!(fmt))
+        maj_type_err(Maj::string("{} is not a string"),
+                     maj_list!(fmt))
     }
 }
 
+pub fn maj_format_objects(
+    mut state: &mut MajState,
+    fmt: Gc<Maj>,
+    rest: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::printing::maj_with_print_objects;
+    maj_with_print_objects(&mut state, rest.clone(),
+                           |state| maj_format_prim(state, fmt, rest))
+        .unwrap_or_else(|err| err)
+}
+
 pub fn maj_err(fmt: Gc<Maj>, rest: Gc<Maj>) -> Gc<Maj> {
     use crate::maj_dotted_list;
 
@@ -314,6 +389,307 @@ pub fn maj_err(fmt: Gc<Maj>, rest: Gc<Maj>) -> Gc<Maj> {
     maj_dotted_list!(Maj::lit(), Maj::error(), fmt, rest)
 }
 
+pub fn maj_typed_err(kind: Gc<Maj>, fmt: Gc<Maj>, rest: Gc<Maj>) -> Gc<Maj> {
+    use crate::maj_dotted_list;
+
+    if !maj_stringp(fmt.clone()).to_bool() {
+        panic!("Cannot throw error: {} is not a string", fmt);
+    } else if !maj_proper_list_p(rest.clone()).to_bool() {
+        panic!("Cannot throw error: {} is not a proper list", rest);
+    }
+
+    maj_dotted_list!(Maj::lit(), Maj::error(), kind, fmt, rest)
+}
+
+pub fn maj_error(kind: Gc<Maj>, fmt: Gc<Maj>, rest: Gc<Maj>) -> Gc<Maj> {
+    use super::predicates::maj_symbolp;
+    if !maj_symbolp(kind.clone()).to_bool() {
+        maj_type_err(Maj::string("{} is not a symbol"),
+                     maj_list!(kind))
+    } else if !maj_stringp(fmt.clone()).to_bool() {
+        maj_type_err(Maj::string("{} is not a string"),
+                     maj_list!(fmt))
+    } else if !maj_proper_list_p(rest.clone()).to_bool() {
+        maj_type_err(Maj::string("{} is not a proper list"),
+                     maj_list!(rest))
+    } else {
+        maj_typed_err(kind, fmt, rest)
+    }
+}
+
+pub fn maj_unbound_err(fmt: Gc<Maj>, rest: Gc<Maj>) -> Gc<Maj> {
+    maj_typed_err(Maj::unbound_variable(), fmt, rest)
+}
+
+pub fn maj_arity_err(fmt: Gc<Maj>, rest: Gc<Maj>) -> Gc<Maj> {
+    maj_typed_err(Maj::arity_error(), fmt, rest)
+}
+
+pub fn maj_type_err(fmt: Gc<Maj>, rest: Gc<Maj>) -> Gc<Maj> {
+    maj_typed_err(Maj::type_error(), fmt, rest)
+}
+
+pub fn maj_io_err(fmt: Gc<Maj>, rest: Gc<Maj>) -> Gc<Maj> {
+    maj_typed_err(Maj::io_error(), fmt, rest)
+}
+
+pub fn maj_match_err(fmt: Gc<Maj>, rest: Gc<Maj>) -> Gc<Maj> {
+    maj_typed_err(Maj::match_error(), fmt, rest)
+}
+
+pub fn maj_error_with_context(
+    err: Gc<Maj>,
+    form: Gc<Maj>,
+    stack: Gc<Maj>,
+    location: Gc<Maj>,
+    backtrace: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::maj_dotted_list;
+    maj_dotted_list!(Maj::lit(), Maj::error(),
+                     maj_list!(maj_condition_type(err.clone()),
+                               form, stack, location, backtrace),
+                     maj_condition_format(err.clone()),
+                     maj_condition_irritants(err))
+}
+
+pub fn maj_error_with_message(
+    err: Gc<Maj>,
+    fmt: Gc<Maj>,
+    rest: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::maj_dotted_list;
+    maj_dotted_list!(Maj::lit(), Maj::error(),
+                     maj_car(maj_cdr(maj_cdr(err))),
+                     fmt, rest)
+}
+
+fn maj_condition_header(x: Gc<Maj>) -> Gc<Maj> {
+    maj_car(maj_cdr(maj_cdr(x)))
+}
+
+fn maj_condition_typedp(x: Gc<Maj>) -> bool {
+    !maj_stringp(maj_condition_header(x)).to_bool()
+}
+
+pub fn maj_condition_contextp(x: Gc<Maj>) -> bool {
+    maj_consp(maj_condition_header(x)).to_bool()
+}
+
+pub fn maj_condition_type(x: Gc<Maj>) -> Gc<Maj> {
+    let header = maj_condition_header(x.clone());
+    if !maj_condition_typedp(x.clone()) {
+        Maj::error()
+    } else if maj_condition_contextp(x) {
+        maj_car(header)
+    } else {
+        header
+    }
+}
+
+pub fn maj_condition_form(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_condition_contextp(x.clone()) {
+        maj_car(maj_cdr(maj_condition_header(x)))
+    } else {
+        Maj::nil()
+    }
+}
+
+pub fn maj_condition_stack(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_condition_contextp(x.clone()) {
+        maj_car(maj_cdr(maj_cdr(maj_condition_header(x))))
+    } else {
+        Maj::nil()
+    }
+}
+
+pub fn maj_condition_location(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_condition_contextp(x.clone()) {
+        maj_car(maj_cdr(maj_cdr(maj_cdr(maj_condition_header(x)))))
+    } else {
+        Maj::nil()
+    }
+}
+
+pub fn maj_condition_backtrace(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_condition_contextp(x.clone()) {
+        maj_car(maj_cdr(maj_cdr(maj_cdr(maj_cdr(
+            maj_condition_header(x))))))
+    } else {
+        Maj::nil()
+    }
+}
+
+pub fn maj_condition_format(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_condition_typedp(x.clone()) {
+        maj_car(maj_cdr(maj_cdr(maj_cdr(x))))
+    } else {
+        maj_condition_header(x)
+    }
+}
+
+pub fn maj_condition_irritants(x: Gc<Maj>) -> Gc<Maj> {
+    if maj_condition_typedp(x.clone()) {
+        maj_cdr(maj_cdr(maj_cdr(maj_cdr(x))))
+    } else {
+        maj_cdr(maj_cdr(maj_cdr(x)))
+    }
+}
+
+fn maj_condition_accessor(
+    x: Gc<Maj>,
+    accessor: fn(Gc<Maj>) -> Gc<Maj>
+) -> Gc<Maj> {
+    use super::predicates::maj_conditionp;
+    if maj_conditionp(x.clone()).to_bool() {
+        accessor(x)
+    } else {
+        maj_type_err(Maj::string("{} is not a condition"),
+                     maj_list!(x))
+    }
+}
+
+pub fn maj_error_type(x: Gc<Maj>) -> Gc<Maj> {
+    maj_condition_accessor(x, maj_condition_type)
+}
+
+pub fn maj_error_irritants(x: Gc<Maj>) -> Gc<Maj> {
+    maj_condition_accessor(x, maj_condition_irritants)
+}
+
+pub fn maj_error_form(x: Gc<Maj>) -> Gc<Maj> {
+    maj_condition_accessor(x, maj_condition_form)
+}
+
+pub fn maj_error_stack(x: Gc<Maj>) -> Gc<Maj> {
+    maj_condition_accessor(x, maj_condition_stack)
+}
+
+pub fn maj_error_location(x: Gc<Maj>) -> Gc<Maj> {
+    maj_condition_accessor(x, maj_condition_location)
+}
+
+pub fn maj_backtrace(state: &MajState, args: Gc<Maj>) -> Gc<Maj> {
+    if maj_nilp(args.clone()).to_bool() {
+        // Skip the frame of this very call
+        maj_cdr(state.backtrace())
+    } else if !maj_nilp(maj_cdr(args.clone())).to_bool() {
+        maj_arity_err(
+            Maj::string("Too many arguments for backtrace: {}"),
+            maj_list!(args))
+    } else {
+        maj_condition_accessor(maj_car(args),
+                               maj_condition_backtrace)
+    }
+}
+
+pub fn maj_error_message(state: &MajState, x: Gc<Maj>) -> Gc<Maj> {
+    use super::predicates::maj_conditionp;
+    if maj_conditionp(x.clone()).to_bool() {
+        maj_format_prim(&state,
+                        maj_condition_format(x.clone()),
+                        maj_condition_irritants(x))
+    } else {
+        maj_type_err(Maj::string("{} is not a condition"),
+                     maj_list!(x))
+    }
+}
+
+pub fn maj_error_to_condition(err: Gc<Maj>) -> Gc<Maj> {
+    Maj::cons(Maj::lit(),
+              Maj::cons(Maj::condition(),
+                        maj_cdr(maj_cdr(err))))
+}
+
+pub fn maj_signal(x: Gc<Maj>, rest: Gc<Maj>) -> Gc<Maj> {
+    use super::predicates::maj_conditionp;
+    if maj_conditionp(x.clone()).to_bool()
+        && maj_nilp(rest.clone()).to_bool()
+    {
+        Maj::cons(Maj::lit(),
+                  Maj::cons(Maj::error(),
+                            maj_cdr(maj_cdr(x))))
+    } else {
+        maj_error(x, maj_car(rest.clone()), maj_cdr(rest))
+    }
+}
+
+pub fn maj_throw(mut state: &mut MajState, tag: Gc<Maj>, value: Gc<Maj>) -> Gc<Maj> {
+    maj_typed_err(Maj::symbol(&mut state, "throw"),
+                  Maj::string("No catch for tag {}"),
+                  maj_list!(tag, value))
+}
+
+pub fn maj_call_cc(
+    mut state: &mut MajState,
+    f: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::evaluator::maj_apply;
+    let tag = maj_gensym(&mut state);
+    let k = maj_list!(Maj::lit(), Maj::continuation(), tag.clone());
+    state.push_continuation(tag.clone());
+    let result = maj_apply(&mut state, f, maj_list!(k), env);
+    state.pop_continuation();
+    let throw = Maj::symbol(&mut state, "throw");
+    if maj_errorp(result.clone()).to_bool()
+        && maj_eq(maj_condition_type(result.clone()), throw).to_bool()
+    {
+        // (lit error throw fmt tag value)
+        let irritants = maj_condition_irritants(result.clone());
+        if maj_eq(maj_car(irritants.clone()), tag).to_bool() {
+            return maj_car(maj_cdr(irritants));
+        }
+    }
+    result
+}
+
+pub fn maj_make_coroutine(mut state: &mut MajState, f: Gc<Maj>) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_closurep;
+    if !maj_closurep(f.clone()).to_bool() {
+        return maj_type_err(Maj::string("{} is not a closure"),
+                            maj_list!(f));
+    }
+    let tag = maj_gensym(&mut state);
+    let coroutine = maj_list!(Maj::lit(), Maj::coroutine(), tag);
+    state.add_coroutine(coroutine.clone(), f);
+    coroutine
+}
+
+pub fn maj_resume_prim(
+    mut state: &mut MajState,
+    coroutine: Gc<Maj>,
+    args: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::evaluator::evaluation::{ maj_resume, MajResume };
+    match maj_resume(&mut state, coroutine, args) {
+        MajResume::Yield(value) | MajResume::Return(value) => value,
+    }
+}
+
+pub fn maj_coroutine_status(
+    mut state: &mut MajState,
+    coroutine: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::core::state::MajCoroutineStatus;
+    use crate::axioms::predicates::maj_coroutinep;
+    if !maj_coroutinep(coroutine.clone()).to_bool() {
+        return maj_type_err(Maj::string("{} is not a coroutine"),
+                            maj_list!(coroutine));
+    }
+    let status = match state.coroutine_status(coroutine) {
+        Some(MajCoroutineStatus::Suspended) => "suspended",
+        Some(MajCoroutineStatus::Running)   => "running",
+        None                                => "dead",
+    };
+    Maj::symbol(&mut state, status)
+}
+
+pub fn maj_values(state: &mut MajState, rest: Gc<Maj>) -> Gc<Maj> {
+    let primary = maj_car(rest.clone());
+    state.set_values(primary.clone(), rest);
+    primary
+}
+
 pub fn maj_warn(mut state: &mut MajState, fmt: Gc<Maj>,
                 rest: Gc<Maj>, env: Gc<Maj>
 ) -> Gc<Maj> {
@@ -374,7 +750,7 @@ pub fn maj_append(rest: Gc<Maj>) -> Gc<Maj> {
 
 pub fn maj_last(x: Gc<Maj>) -> Gc<Maj> {
     if !maj_consp(x.clone()).to_bool() {
-        return maj_err(
+        return maj_type_err(
             Maj::string("{} is not a cons"),
             maj_list!(x));
     }
@@ -392,7 +768,7 @@ pub fn maj_last(x: Gc<Maj>) -> Gc<Maj> {
 pub fn maj_reverse(x: Gc<Maj>) -> Gc<Maj> {
     use crate::axioms::predicates::maj_atomp;
     if !maj_consp(x.clone()).to_bool() {
-        return maj_err(
+        return maj_type_err(
             Maj::string("{} is not a cons"),
             maj_list!(x));
     }
@@ -404,7 +780,7 @@ pub fn maj_reverse(x: Gc<Maj>) -> Gc<Maj> {
         itr = maj_cdr(itr);
         let is_atom = maj_atomp(itr.clone()).to_bool();
         if is_atom && !maj_nilp(itr.clone()).to_bool() {
-            return maj_err(
+            return maj_type_err(
                 Maj::string("Not a proper list: {}"),
                 maj_list!(x));
         }
@@ -419,14 +795,14 @@ pub fn maj_nthcdr(n: Gc<Maj>, lst: Gc<Maj>) -> Gc<Maj> {
     }
 
     if !maj_integerp(n.clone()).to_bool() {
-        return maj_err(Maj::string("{} is not an integer"),
-                       maj_list!(n))
+        return maj_type_err(Maj::string("{} is not an integer"),
+                            maj_list!(n))
     }
 
     let mut num = n.to_integer().unwrap();
     if num < 0 {
-        return maj_err(Maj::string("{} is not a valid index"),
-                       maj_list!(n));
+        return maj_type_err(Maj::string("{} is not a valid index"),
+                            maj_list!(n));
     }
 
     let mut iter = lst.clone();
@@ -434,8 +810,8 @@ pub fn maj_nthcdr(n: Gc<Maj>, lst: Gc<Maj>) -> Gc<Maj> {
         if !maj_consp(iter.clone()).to_bool() &&
             !maj_nilp(iter.clone()).to_bool()
         {
-            return maj_err(Maj::string("{} is not a list"),
-                           maj_list!(iter));
+            return maj_type_err(Maj::string("{} is not a list"),
+                                maj_list!(iter));
         }
         if num <= 0 { break; }
         num -= 1;
@@ -461,12 +837,487 @@ pub fn maj_macroexpand_1(mut state: &mut MajState,
     if !maj_consp(expr.clone()).to_bool() {
         return (expr, true);
     }
-
-    let mac = maj_car(expr.clone());
-    let args = maj_cdr(expr.clone());
-
-    let mac = state.lookup(env.clone(), mac);
-    expand_macro(&mut state, mac, args, env)
+
+    let mac = maj_car(expr.clone());
+    let args = maj_cdr(expr.clone());
+
+    let mac = state.lookup(env.clone(), mac);
+    expand_macro(&mut state, mac, args, env)
+}
+
+pub fn maj_macroexpand(mut state: &mut MajState,
+                       expr: Gc<Maj>,
+                       env: Gc<Maj>) -> Gc<Maj> {
+    use crate::evaluator::application::expand_macro;
+    use super::predicates::{ maj_symbolp, maj_macrop };
+
+    let mut expr = expr;
+    loop {
+        if !maj_consp(expr.clone()).to_bool() {
+            return expr;
+        }
+
+        let head = maj_car(expr.clone());
+        if !maj_symbolp(head.clone()).to_bool() {
+            return expr;
+        }
+
+        let mac = state.lookup(env.clone(), head);
+        if !maj_macrop(mac.clone()).to_bool() {
+            return expr;
+        }
+
+        let (result, worked) =
+            expand_macro(&mut state, mac, maj_cdr(expr), env.clone());
+        if !worked {
+            return result;
+        }
+        expr = result;
+    }
+}
+
+#[inline]
+pub fn maj_macroexpand_all(mut state: &mut MajState,
+                           expr: Gc<Maj>,
+                           env: Gc<Maj>) -> Gc<Maj> {
+    use crate::evaluator::evaluation::maj_expand_all;
+    let compiling = state.set_compiling(false);
+    let result = maj_expand_all(&mut state, expr, env);
+    state.set_compiling(compiling);
+    result
+}
+
+struct MajLoop {
+    bindings: Vec<Gc<Maj>>,
+    body: Vec<Gc<Maj>>,
+    accumulator: Option<(String, Gc<Maj>)>,
+    first: Option<Gc<Maj>>,
+}
+
+pub fn maj_loop_expand(mut state: &mut MajState,
+                       clauses: Gc<Maj>) -> Gc<Maj> {
+    if !maj_proper_list_p(clauses.clone()).to_bool() {
+        return maj_err(
+            Maj::string("Invalid loop clauses {}"),
+            maj_list!(clauses));
+    }
+
+    let mut items = Vec::new();
+    let mut iter = clauses;
+    while !maj_nilp(iter.clone()).to_bool() {
+        items.push(maj_car(iter.clone()));
+        iter = maj_cdr(iter);
+    }
+
+    let mut parsed = MajLoop {
+        bindings: Vec::new(),
+        body: Vec::new(),
+        accumulator: None,
+        first: None,
+    };
+
+    if items.is_empty() || maj_consp(items[0].clone()).to_bool() {
+        // (loop . forms)
+        parsed.body = items;
+    } else {
+        let mut pos = 0;
+        while pos < items.len() {
+            let result = maj_loop_clause(&mut state, &items,
+                                         &mut pos, &mut parsed);
+            if maj_errorp(result.clone()).to_bool() {
+                return result;
+            }
+        }
+    }
+
+    let break_tag = maj_list!(Maj::quote(),
+                              Maj::symbol(&mut state, "break"));
+    let continue_tag = maj_list!(Maj::quote(),
+                                 Maj::symbol(&mut state, "continue"));
+    let set = Maj::symbol(&mut state, "set");
+
+    let mut bindings = parsed.bindings;
+    let mut result = None;
+    if let Some((kind, acc)) = parsed.accumulator {
+        let (init, final_value) = match kind.as_str() {
+            "collect" =>
+                (Maj::nil(),
+                 maj_list!(Maj::symbol(&mut state, "reverse"),
+                           acc.clone())),
+            "sum" => (Maj::integer(0), acc.clone()),
+            _ => (Maj::nil(), acc.clone()),
+        };
+        bindings.push(maj_list!(acc, init));
+        result = Some(final_value);
+    }
+
+    let mut iteration =
+        Maj::cons(Maj::symbol(&mut state, "catch"),
+                  Maj::cons(continue_tag, maj_loop_list(parsed.body)));
+    if let Some(first) = parsed.first {
+        bindings.push(maj_list!(first.clone(), Maj::t()));
+        iteration = maj_list!(Maj::do_sym(),
+                              iteration,
+                              maj_list!(set, first, Maj::nil()));
+    }
+
+    let lp = maj_list!(
+        Maj::symbol(&mut state, "catch"),
+        break_tag,
+        maj_list!(Maj::symbol(&mut state, "while"),
+                  Maj::t(),
+                  iteration));
+
+    let mut forms = vec![lp];
+    if let Some(result) = result {
+        forms.push(result);
+    }
+
+    if bindings.is_empty() {
+        Maj::cons(Maj::do_sym(), maj_loop_list(forms))
+    } else {
+        Maj::cons(Maj::symbol(&mut state, "let*"),
+                  Maj::cons(maj_loop_list(bindings),
+                            maj_loop_list(forms)))
+    }
+}
+
+fn maj_loop_list(items: Vec<Gc<Maj>>) -> Gc<Maj> {
+    items.into_iter().rev()
+        .fold(Maj::nil(), |acc, item| Maj::cons(item, acc))
+}
+
+fn maj_loop_keyword(state: &MajState, x: Gc<Maj>) -> Option<String> {
+    use super::predicates::maj_symbolp;
+    if !maj_symbolp(x.clone()).to_bool() {
+        return None;
+    }
+    Some(x.symbol_name(state))
+}
+
+fn maj_loop_next(items: &[Gc<Maj>], pos: &mut usize,
+                 clause: &str) -> Result<Gc<Maj>, Gc<Maj>> {
+    if *pos >= items.len() {
+        return Err(maj_err(
+            Maj::string("Missing argument for loop clause {}"),
+            maj_list!(Maj::string(clause))));
+    }
+    let item = items[*pos].clone();
+    *pos += 1;
+    Ok(item)
+}
+
+fn maj_loop_break(state: &mut MajState) -> Gc<Maj> {
+    maj_list!(Maj::symbol(state, "throw"),
+              maj_list!(Maj::quote(), Maj::symbol(state, "break")),
+              Maj::nil())
+}
+
+fn maj_loop_clause(mut state: &mut MajState,
+                   items: &[Gc<Maj>],
+                   pos: &mut usize,
+                   parsed: &mut MajLoop) -> Gc<Maj> {
+    let clause = items[*pos].clone();
+    let keyword = maj_loop_keyword(&state, clause.clone());
+    *pos += 1;
+    let result = match keyword.as_deref() {
+        Some("for") => maj_loop_for(&mut state, items, pos, parsed),
+        Some("repeat") => maj_loop_next(items, pos, "repeat").map(|n| {
+            let counter = Maj::gensym(&mut state);
+            parsed.bindings.push(maj_list!(counter.clone(), n));
+            maj_list!(
+                Maj::symbol(&mut state, "if"),
+                maj_list!(Maj::symbol(&mut state, ">"),
+                          counter.clone(),
+                          Maj::integer(0)),
+                maj_list!(Maj::symbol(&mut state, "set"),
+                          counter.clone(),
+                          maj_list!(Maj::symbol(&mut state, "1-"),
+                                    counter)),
+                maj_loop_break(&mut state))
+        }),
+        Some("while") => maj_loop_next(items, pos, "while").map(|test| {
+            maj_list!(Maj::symbol(&mut state, "if"),
+                      test,
+                      Maj::nil(),
+                      maj_loop_break(&mut state))
+        }),
+        Some("until") => maj_loop_next(items, pos, "until").map(|test| {
+            maj_list!(Maj::symbol(&mut state, "if"),
+                      test,
+                      maj_loop_break(&mut state),
+                      Maj::nil())
+        }),
+        _ => {
+            *pos -= 1;
+            maj_loop_action(&mut state, items, pos, parsed)
+        },
+    };
+    match result {
+        Ok(form) => {
+            parsed.body.push(form);
+            Maj::nil()
+        },
+        Err(e) => e,
+    }
+}
+
+fn maj_loop_for(mut state: &mut MajState,
+                items: &[Gc<Maj>],
+                pos: &mut usize,
+                parsed: &mut MajLoop) -> Result<Gc<Maj>, Gc<Maj>> {
+    use super::predicates::maj_symbolp;
+    let var = maj_loop_next(items, pos, "for")?;
+    if !maj_symbolp(var.clone()).to_bool() {
+        return Err(maj_err(
+            Maj::string("{} is not a valid loop variable"),
+            maj_list!(var)));
+    }
+    let kind = maj_loop_next(items, pos, "for")?;
+    let kind = maj_loop_keyword(&state, kind.clone())
+        .ok_or_else(|| maj_err(
+            Maj::string("Invalid loop clause {}"),
+            maj_list!(kind)))?;
+    let set = Maj::symbol(&mut state, "set");
+    let if_sym = Maj::symbol(&mut state, "if");
+    match kind.as_str() {
+        "in" => {
+            let list = maj_loop_next(items, pos, "in")?;
+            let iter = Maj::gensym(&mut state);
+            parsed.bindings.push(maj_list!(iter.clone(), list));
+            parsed.bindings.push(maj_list!(var.clone(), Maj::nil()));
+            Ok(maj_list!(
+                if_sym,
+                iter.clone(),
+                maj_list!(
+                    Maj::do_sym(),
+                    maj_list!(set.clone(), var,
+                              maj_list!(Maj::symbol(&mut state, "car"),
+                                        iter.clone())),
+                    maj_list!(set, iter.clone(),
+                              maj_list!(Maj::symbol(&mut state, "cdr"),
+                                        iter))),
+                maj_loop_break(&mut state)))
+        },
+        "across" => {
+            let vec = maj_loop_next(items, pos, "across")?;
+            let vsym = Maj::gensym(&mut state);
+            let index = Maj::gensym(&mut state);
+            parsed.bindings.push(maj_list!(vsym.clone(), vec));
+            parsed.bindings.push(maj_list!(index.clone(), Maj::integer(0)));
+            parsed.bindings.push(maj_list!(var.clone(), Maj::nil()));
+            Ok(maj_list!(
+                if_sym,
+                maj_list!(Maj::symbol(&mut state, "<"),
+                          index.clone(),
+                          maj_list!(Maj::symbol(&mut state, "vec-length"),
+                                    vsym.clone())),
+                maj_list!(
+                    Maj::do_sym(),
+                    maj_list!(set.clone(), var,
+                              maj_list!(Maj::symbol(&mut state, "vec-at"),
+                                        index.clone(),
+                                        vsym)),
+                    maj_list!(set, index.clone(),
+                              maj_list!(Maj::symbol(&mut state, "1+"),
+                                        index))),
+                maj_loop_break(&mut state)))
+        },
+        "from" => {
+            let start = maj_loop_next(items, pos, "from")?;
+            parsed.bindings.push(maj_list!(var.clone(), start));
+            let mut test = None;
+            let mut step = Maj::integer(1);
+            let mut downwards = false;
+            while *pos < items.len() {
+                let keyword = maj_loop_keyword(&mut state,
+                                               items[*pos].clone());
+                let (cmp, down) = match keyword.as_deref() {
+                    Some("to") => (">", false),
+                    Some("below") => (">=", false),
+                    Some("downto") => ("<", true),
+                    Some("above") => ("<=", true),
+                    Some("by") => {
+                        *pos += 1;
+                        step = maj_loop_next(items, pos, "by")?;
+                        continue;
+                    },
+                    _ => break,
+                };
+                *pos += 1;
+                let end_value = maj_loop_next(items, pos, "from")?;
+                let end = Maj::gensym(&mut state);
+                parsed.bindings.push(maj_list!(end.clone(), end_value));
+                test = Some(maj_list!(Maj::symbol(&mut state, cmp),
+                                      var.clone(), end));
+                downwards = down;
+            }
+            let step_sym = Maj::gensym(&mut state);
+            parsed.bindings.push(maj_list!(step_sym.clone(), step));
+            let first = parsed.first.clone()
+                .unwrap_or_else(|| Maj::gensym(&mut state));
+            parsed.first = Some(first.clone());
+            let op = Maj::symbol(&mut state,
+                                 if downwards { "-" } else { "+" });
+            let advance = maj_list!(
+                if_sym.clone(),
+                first,
+                Maj::nil(),
+                maj_list!(set, var.clone(),
+                          maj_list!(op, var, step_sym)));
+            Ok(match test {
+                Some(test) => maj_list!(
+                    Maj::do_sym(),
+                    advance,
+                    maj_list!(if_sym, test,
+                              maj_loop_break(&mut state),
+                              Maj::nil())),
+                None => advance,
+            })
+        },
+        _ => Err(maj_err(
+            Maj::string("Invalid loop clause {}"),
+            maj_list!(Maj::symbol(&mut state, &kind)))),
+    }
+}
+
+fn maj_loop_action(mut state: &mut MajState,
+                   items: &[Gc<Maj>],
+                   pos: &mut usize,
+                   parsed: &mut MajLoop) -> Result<Gc<Maj>, Gc<Maj>> {
+    let clause = items[*pos].clone();
+    let keyword = maj_loop_keyword(&state, clause.clone())
+        .ok_or_else(|| maj_err(
+            Maj::string("Invalid loop clause {}"),
+            maj_list!(clause.clone())))?;
+    *pos += 1;
+    let set = Maj::symbol(&mut state, "set");
+    let if_sym = Maj::symbol(&mut state, "if");
+    match keyword.as_str() {
+        "do" => {
+            let mut forms = Vec::new();
+            while *pos < items.len()
+                && maj_consp(items[*pos].clone()).to_bool()
+            {
+                forms.push(items[*pos].clone());
+                *pos += 1;
+            }
+            if forms.is_empty() {
+                return Err(maj_err(
+                    Maj::string("Missing argument for loop clause {}"),
+                    maj_list!(Maj::string("do"))));
+            }
+            Ok(Maj::cons(Maj::do_sym(), maj_loop_list(forms)))
+        },
+        "when" | "unless" => {
+            let test = maj_loop_next(items, pos, &keyword)?;
+            if *pos >= items.len() {
+                return Err(maj_err(
+                    Maj::string("Missing argument for loop clause {}"),
+                    maj_list!(Maj::string(&keyword))));
+            }
+            let action = maj_loop_action(&mut state, items, pos, parsed)?;
+            Ok(if keyword == "when" {
+                maj_list!(if_sym, test, action, Maj::nil())
+            } else {
+                maj_list!(if_sym, test, Maj::nil(), action)
+            })
+        },
+        "collect" | "sum" | "maximize" | "minimize" => {
+            let value = maj_loop_next(items, pos, &keyword)?;
+            let acc = match parsed.accumulator.clone() {
+                Some((kind, acc)) => {
+                    if kind != keyword {
+                        return Err(maj_err(
+                            Maj::string("Cannot mix {} and {} in loop"),
+                            maj_list!(Maj::symbol(&mut state, &kind),
+                                      Maj::symbol(&mut state, &keyword))));
+                    }
+                    acc
+                },
+                None => {
+                    let acc = Maj::gensym(&mut state);
+                    parsed.accumulator = Some((keyword.clone(), acc.clone()));
+                    acc
+                },
+            };
+            Ok(match keyword.as_str() {
+                "collect" =>
+                    maj_list!(set, acc.clone(),
+                              maj_list!(Maj::symbol(&mut state, "cons"),
+                                        value, acc)),
+                "sum" =>
+                    maj_list!(set, acc.clone(),
+                              maj_list!(Maj::symbol(&mut state, "+"),
+                                        acc, value)),
+                _ => {
+                    let tmp = Maj::gensym(&mut state);
+                    let cmp = if keyword == "maximize" { ">" } else { "<" };
+                    parsed.bindings.push(maj_list!(tmp.clone(), Maj::nil()));
+                    maj_list!(
+                        Maj::do_sym(),
+                        maj_list!(set.clone(), tmp.clone(), value),
+                        maj_list!(
+                            if_sym,
+                            maj_list!(
+                                Maj::symbol(&mut state, "or"),
+                                maj_list!(Maj::symbol(&mut state, "nilp"),
+                                          acc.clone()),
+                                maj_list!(Maj::symbol(&mut state, cmp),
+                                          tmp.clone(), acc.clone())),
+                            maj_list!(set, acc, tmp),
+                            Maj::nil()))
+                },
+            })
+        },
+        _ => Err(maj_err(
+            Maj::string("Invalid loop clause {}"),
+            maj_list!(clause))),
+    }
+}
+
+pub fn maj_proclaim(mut state: &mut MajState,
+                    declarations: Gc<Maj>) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_symbolp;
+    let inline = Maj::symbol(&mut state, "inline");
+    let notinline = Maj::symbol(&mut state, "notinline");
+    let mut pending = Vec::new();
+    let mut iter = declarations;
+    while !maj_nilp(iter.clone()).to_bool() {
+        let declaration = maj_car(iter.clone());
+        if !maj_proper_list_p(declaration.clone()).to_bool() {
+            return maj_type_err(
+                Maj::string("{} is not a valid declaration"),
+                maj_list!(declaration));
+        }
+        let kind = maj_car(declaration.clone());
+        let is_inline =
+            if maj_eq(kind.clone(), inline.clone()).to_bool() {
+                true
+            } else if maj_eq(kind.clone(), notinline.clone()).to_bool() {
+                false
+            } else {
+                return maj_err(
+                    Maj::string("Unknown declaration {}"),
+                    maj_list!(kind));
+            };
+        let mut syms = maj_cdr(declaration);
+        while !maj_nilp(syms.clone()).to_bool() {
+            let sym = maj_car(syms.clone());
+            if !maj_symbolp(sym.clone()).to_bool() {
+                return maj_type_err(
+                    Maj::string("{} is not a symbol"),
+                    maj_list!(sym));
+            }
+            pending.push((sym, is_inline));
+            syms = maj_cdr(syms);
+        }
+        iter = maj_cdr(iter);
+    }
+    for (sym, is_inline) in pending {
+        state.declare_inline(sym, is_inline);
+    }
+    Maj::nil()
 }
 
 #[inline]
@@ -488,13 +1339,13 @@ pub fn maj_number_coerce(
     use crate::axioms::predicates::maj_symbolp;
 
     if !maj_numberp(number.clone()).to_bool() {
-        return maj_err(Maj::string("{} is not a number"),
-                       maj_list!(number));
+        return maj_type_err(Maj::string("{} is not a number"),
+                            maj_list!(number));
     }
 
     if !maj_symbolp(subtype.clone()).to_bool() {
-        return maj_err(Maj::string("{} is not a symbol"),
-                       maj_list!(subtype));
+        return maj_type_err(Maj::string("{} is not a symbol"),
+                            maj_list!(subtype));
     }
 
 
@@ -518,7 +1369,7 @@ pub fn maj_number_coerce(
             Some(MajRawSym::Complex) => {
                 Maj::complex(number.clone(), Maj::float(0.0))
             },
-            _ => maj_err(
+            _ => maj_type_err(
                 Maj::string("{} is not a number subtype"),
                 maj_list!(subtype)),
         },
@@ -544,7 +1395,7 @@ pub fn maj_number_coerce(
             Some(MajRawSym::Complex) => {
                 Maj::complex(number.clone(), Maj::float(0.0))
             },
-            _ => maj_err(
+            _ => maj_type_err(
                 Maj::string("{} is not a number subtype"),
                 maj_list!(subtype)),
         },
@@ -563,7 +1414,7 @@ pub fn maj_number_coerce(
             Some(MajRawSym::Complex) => {
                 Maj::complex(number.clone(), Maj::float(0.0))
             },
-            _ => maj_err(
+            _ => maj_type_err(
                 Maj::string("{} is not a number subtype"),
                 maj_list!(subtype)),
         },
@@ -575,7 +1426,7 @@ pub fn maj_number_coerce(
                 maj_number_coerce(&mut state, subtype, realpart)
             },
             Some(MajRawSym::Complex) => number,
-            _ => maj_err(
+            _ => maj_type_err(
                 Maj::string("{} is not a number subtype"),
                 maj_list!(subtype)),
         },
@@ -595,13 +1446,13 @@ pub fn maj_real_part(x: Gc<Maj>) -> Gc<Maj> {
                     let real = &*real.clone();
                     Gc::new(Maj::Number(real.clone()))
                 },
-                _ => maj_err(Maj::string(
+                _ => maj_type_err(Maj::string(
                     "{} is not a complex number"),
                     maj_list!(x)),
             }
         },
-        _ => maj_err(Maj::string("{} is not a number"),
-                     maj_list!(x)),
+        _ => maj_type_err(Maj::string("{} is not a number"),
+                          maj_list!(x)),
     }
 }
 
@@ -617,38 +1468,38 @@ pub fn maj_imag_part(x: Gc<Maj>) -> Gc<Maj> {
                     let imag = &*imag.clone();
                     Gc::new(Maj::Number(imag.clone()))
                 },
-                _ => maj_err(Maj::string(
+                _ => maj_type_err(Maj::string(
                     "{} is not a complex number"),
                     maj_list!(x)),
             }
         },
-        _ => maj_err(Maj::string("{} is not a number"),
-                     maj_list!(x)),
+        _ => maj_type_err(Maj::string("{} is not a number"),
+                          maj_list!(x)),
     }
 }
 
 pub fn maj_numer(x: Gc<Maj>) -> Gc<Maj> {
     if !maj_numberp(x.clone()).to_bool() {
-        maj_err(Maj::string("{} is not a number"),
-                maj_list!(x))
+        maj_type_err(Maj::string("{} is not a number"),
+                     maj_list!(x))
     } else {
         match x.clone().to_fraction() {
             Some((numer, _)) => Maj::integer(numer),
-            None => maj_err(Maj::string("{} is not a fraction"),
-                            maj_list!(x)),
+            None => maj_type_err(Maj::string("{} is not a fraction"),
+                                 maj_list!(x)),
         }
     }
 }
 
 pub fn maj_denom(x: Gc<Maj>) -> Gc<Maj> {
     if !maj_numberp(x.clone()).to_bool() {
-        maj_err(Maj::string("{} is not a number"),
-                maj_list!(x))
+        maj_type_err(Maj::string("{} is not a number"),
+                     maj_list!(x))
     } else {
         match x.clone().to_fraction() {
             Some((_, denom)) => Maj::integer(denom),
-            None => maj_err(Maj::string("{} is not a fraction"),
-                            maj_list!(x)),
+            None => maj_type_err(Maj::string("{} is not a fraction"),
+                                 maj_list!(x)),
         }
     }
 }
@@ -659,12 +1510,12 @@ pub fn maj_richest_number_type(
     y: Gc<Maj>
 ) -> Gc<Maj> {
     if !maj_numberp(x.clone()).to_bool() {
-        return maj_err(Maj::string("{} is not a number"),
-                       maj_list!(x));
+        return maj_type_err(Maj::string("{} is not a number"),
+                            maj_list!(x));
     }
     if !maj_numberp(y.clone()).to_bool() {
-        return maj_err(Maj::string("{} is not a number"),
-                       maj_list!(y));
+        return maj_type_err(Maj::string("{} is not a number"),
+                            maj_list!(y));
     }
 
     let x_type = maj_type(&mut state, x.clone());
@@ -767,12 +1618,12 @@ fn maj_arithm_coercion_helper(
     y: Gc<Maj>
 ) -> Result<(Gc<Maj>, Gc<Maj>, MajRawSym), Gc<Maj>> {
     if !maj_numberp(x.clone()).to_bool() {
-        return Err(maj_err(
+        return Err(maj_type_err(
             Maj::string("{} is not a number"),
             maj_list!(x)));
     }
     if !maj_numberp(y.clone()).to_bool() {
-        return Err(maj_err(
+        return Err(maj_type_err(
             Maj::string("{} is not a number"),
             maj_list!(y)));
     }
@@ -996,11 +1847,11 @@ pub fn maj_arithm_floateq(
     use core::f64;
     use float_cmp::approx_eq;
     if !maj_floatp(x.clone()).to_bool() {
-        return maj_err(Maj::string("{} is not a float number"),
-                       maj_list!(x));
+        return maj_type_err(Maj::string("{} is not a float number"),
+                            maj_list!(x));
     } else if !maj_floatp(y.clone()).to_bool() {
-        return maj_err(Maj::string("{} is not a float number"),
-                       maj_list!(y));
+        return maj_type_err(Maj::string("{} is not a float number"),
+                            maj_list!(y));
     }
 
     let ulps = Maj::symbol(&mut state, "*ulps*");
@@ -1402,8 +2253,8 @@ pub fn maj_arithm_plus(
         1 => {
             maj_destructure_args!(rest, x);
             if !maj_numberp(x.clone()).to_bool() {
-                maj_err(Maj::string("{} is not a number"),
-                        maj_list!(x))
+                maj_type_err(Maj::string("{} is not a number"),
+                             maj_list!(x))
             } else {
                 maj_conjugate(&mut state, env, x)
             }
@@ -1411,11 +2262,11 @@ pub fn maj_arithm_plus(
         _ => {
             maj_destructure_args!(rest, x, r1, y, rest);
             if !maj_numberp(x.clone()).to_bool() {
-                maj_err(Maj::string("{} is not a number"),
-                        maj_list!(x))
+                maj_type_err(Maj::string("{} is not a number"),
+                             maj_list!(x))
             } else if !maj_numberp(y.clone()).to_bool() {
-                maj_err(Maj::string("{} is not a number"),
-                        maj_list!(y))
+                maj_type_err(Maj::string("{} is not a number"),
+                             maj_list!(y))
             } else {
                 maj_arithm_dispatch(
                     &mut state, Maj::nil(),
@@ -1440,8 +2291,8 @@ pub fn maj_arithm_minus(
         1 => {
             maj_destructure_args!(rest, x);
             if !maj_numberp(x.clone()).to_bool() {
-                maj_err(Maj::string("{} is not a number"),
-                        maj_list!(x))
+                maj_type_err(Maj::string("{} is not a number"),
+                             maj_list!(x))
             } else {
                 maj_negate(&mut state, env, x)
             }
@@ -1449,11 +2300,11 @@ pub fn maj_arithm_minus(
         _ => {
             maj_destructure_args!(rest, x, r1, y, rest);
             if !maj_numberp(x.clone()).to_bool() {
-                maj_err(Maj::string("{} is not a number"),
-                        maj_list!(x))
+                maj_type_err(Maj::string("{} is not a number"),
+                             maj_list!(x))
             } else if !maj_numberp(y.clone()).to_bool() {
-                maj_err(Maj::string("{} is not a number"),
-                        maj_list!(y))
+                maj_type_err(Maj::string("{} is not a number"),
+                             maj_list!(y))
             } else {
                 maj_arithm_dispatch(
                     &mut state, Maj::nil(),
@@ -1478,8 +2329,8 @@ pub fn maj_arithm_times(
         1 => {
             maj_destructure_args!(rest, x);
             if !maj_numberp(x.clone()).to_bool() {
-                maj_err(Maj::string("{} is not a number"),
-                        maj_list!(x))
+                maj_type_err(Maj::string("{} is not a number"),
+                             maj_list!(x))
             } else {
                 maj_signum(&mut state, env, x)
             }
@@ -1487,11 +2338,11 @@ pub fn maj_arithm_times(
         _ => {
             maj_destructure_args!(rest, x, r1, y, rest);
             if !maj_numberp(x.clone()).to_bool() {
-                maj_err(Maj::string("{} is not a number"),
-                        maj_list!(x))
+                maj_type_err(Maj::string("{} is not a number"),
+                             maj_list!(x))
             } else if !maj_numberp(y.clone()).to_bool() {
-                maj_err(Maj::string("{} is not a number"),
-                        maj_list!(y))
+                maj_type_err(Maj::string("{} is not a number"),
+                             maj_list!(y))
             } else {
                 maj_arithm_dispatch(
                     &mut state, Maj::nil(),
@@ -1516,8 +2367,8 @@ pub fn maj_arithm_divide(
         1 => {
             maj_destructure_args!(rest, x);
             if !maj_numberp(x.clone()).to_bool() {
-                maj_err(Maj::string("{} is not a number"),
-                        maj_list!(x))
+                maj_type_err(Maj::string("{} is not a number"),
+                             maj_list!(x))
             } else {
                 maj_reciprocal(&mut state, env, x)
             }
@@ -1525,11 +2376,11 @@ pub fn maj_arithm_divide(
         _ => {
             maj_destructure_args!(rest, x, r1, y, rest);
             if !maj_numberp(x.clone()).to_bool() {
-                maj_err(Maj::string("{} is not a number"),
-                        maj_list!(x))
+                maj_type_err(Maj::string("{} is not a number"),
+                             maj_list!(x))
             } else if !maj_numberp(y.clone()).to_bool() {
-                maj_err(Maj::string("{} is not a number"),
-                        maj_list!(y))
+                maj_type_err(Maj::string("{} is not a number"),
+                             maj_list!(y))
             } else {
                 maj_arithm_dispatch(
                     &mut state, Maj::nil(),
@@ -1557,7 +2408,7 @@ fn get_raw_stream(
         }
 
         if maj_nilp(state.stat_stream(mstream.handle)).to_bool() {
-            Err(maj_err(
+            Err(maj_io_err(
                 Maj::string("The stream {} is closed"),
                 maj_list!(stream)))
         }
@@ -1578,20 +2429,20 @@ fn get_raw_stream(
             Ok(file)
         }
         else {
-            Err(maj_err(
+            Err(maj_type_err(
                 Maj::string("{} is not an {} stream"),
                 maj_list!(
                     stream,
                 Maj::string(
                     if expected_dir ==
-                        MajStreamDirection::In {
-                            "input"
-                        } else {
-                            "output"
-                        }))))
+                             MajStreamDirection::In {
+                                 "input"
+                             } else {
+                                 "output"
+                             }))))
         }
     } else {
-        Err(maj_err(
+        Err(maj_type_err(
             Maj::string("{} is not a stream"),
             maj_list!(stream)))
     }
@@ -1634,21 +2485,19 @@ pub fn maj_open_stream(
 ) -> Gc<Maj> {
     let path_opt = path.stringify();
     if path_opt.is_none() {
-        return maj_err(
+        return maj_type_err(
             Maj::string("{} is not a string"),
             maj_list!(path));
     }
 
     let direction =
-        if maj_eq(Maj::symbol(&mut state, "in"),
-                  dir.clone()).to_bool() {
+        if maj_option_p(&mut state, dir.clone(), "in") {
             MajStreamDirection::In
-        } else if maj_eq(Maj::symbol(&mut state, "out"),
-                         dir.clone()).to_bool() {
+        } else if maj_option_p(&mut state, dir.clone(), "out") {
             MajStreamDirection::Out
         } else {
-            return maj_err(
-                Maj::string("{} should be one of symbols 'in or 'out"),
+            return maj_type_err(
+                Maj::string("{} should be one of :in or :out"),
                 maj_list!(dir));
         };
 
@@ -1657,7 +2506,7 @@ pub fn maj_open_stream(
 
     match stream {
         Some(obj) => obj,
-        None => maj_err(
+        None => maj_io_err(
             Maj::string("Cannot open stream to path {}"),
             maj_list!(path)),
     }
@@ -1683,17 +2532,71 @@ pub fn maj_stat(mut state: &mut MajState, x: Gc<Maj>) -> Gc<Maj> {
             Maj::symbol(&mut state, "open")
         }
     } else {
-        maj_err(
+        maj_type_err(
             Maj::string("Not a stream: {}"),
             maj_list!(x))
     }
 }
 
+fn maj_reader_char(mut state: &mut MajState,
+                   reader: Gc<Maj>,
+                   advance: bool
+) -> Gc<Maj> {
+    use crate::reader::parser::MajReaderInput;
+    let c = match state.reader_input(reader.clone()) {
+        Some(MajReaderInput::Text(source)) => if advance {
+            source.next()
+        } else {
+            source.peek()
+        },
+        Some(MajReaderInput::Stream(stream)) => {
+            let stream = stream.clone();
+            return if advance {
+                maj_read_char(&mut state, stream)
+            } else {
+                maj_peek_char(&mut state, stream)
+            };
+        },
+        None => return maj_err(
+            Maj::string("{} was used outside of its dispatch macro"),
+            maj_list!(reader)),
+    };
+    match c {
+        Ok(Some(c)) => Maj::character(c),
+        Ok(None) => Maj::symbol(&mut state, "eof"),
+        Err(err) => err,
+    }
+}
+
+fn maj_read_utf8<R: std::io::Read>(reader: &mut R)
+                                   -> std::io::Result<Option<(char, i64)>> {
+    let mut buffer = [0; 4];
+    if reader.read(&mut buffer[..1])? == 0 {
+        return Ok(None);
+    }
+    // The lead byte tells how many continuation bytes follow.
+    let length = match buffer[0] {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _           => 1,
+    };
+    reader.read_exact(&mut buffer[1..length])?;
+    let c = std::str::from_utf8(&buffer[..length])
+        .ok()
+        .and_then(|s| s.chars().next())
+        .unwrap_or(std::char::REPLACEMENT_CHARACTER);
+    Ok(Some((c, length as i64)))
+}
+
 pub fn maj_read_char(mut state: &mut MajState,
                      stream: Gc<Maj>
 ) -> Gc<Maj> {
-    use std::io::Read;
-    let mut buffer = [0; 1];
+    use crate::axioms::predicates::maj_readerp;
+    if maj_readerp(stream.clone()).to_bool() {
+        return maj_reader_char(&mut state, stream, true);
+    }
     if stdstreamp(stream.clone()) {
         if stdstreamdirp(stream.clone(), MajStreamDirection::In) {
             let peekopt = state.pop_stdin_peeked();
@@ -1703,12 +2606,15 @@ pub fn maj_read_char(mut state: &mut MajState,
                 },
                 None => {
                     use std::io;
-                    match io::stdin().read(&mut buffer) {
-                        Ok(_) => {
-                            return Maj::character(buffer[0] as char);
+                    match maj_read_utf8(&mut io::stdin()) {
+                        Ok(None) => {
+                            return Maj::symbol(&mut state, "eof");
+                        },
+                        Ok(Some((c, _))) => {
+                            return Maj::character(c);
                         },
                         Err(_) => {
-                            return maj_err(
+                            return maj_io_err(
                                 Maj::string(
                                     "Could not read from stream *stdin*"),
                                 Maj::nil());
@@ -1717,7 +2623,7 @@ pub fn maj_read_char(mut state: &mut MajState,
                 },
             }
         } else {
-            return maj_err(
+            return maj_type_err(
                 Maj::string("{} is not an input stream"),
                 maj_list!(stream));
         }
@@ -1727,14 +2633,17 @@ pub fn maj_read_char(mut state: &mut MajState,
                          stream.clone(),
                          MajStreamDirection::In) {
         Ok(mut file) => {
-            match file.read(&mut buffer) {
-                Ok(_) => {
-                    Maj::character(buffer[0] as char)
+            match maj_read_utf8(&mut file) {
+                Ok(Some((c, _))) => {
+                    Maj::character(c)
+                },
+                Ok(None) => {
+                    Maj::symbol(&mut state, "eof")
                 },
                 Err(_) => {
-                    maj_err(
+                    maj_io_err(
                         Maj::string(
-                            "Could not read from stream {}"),
+                               "Could not read from stream {}"),
                         maj_list!(stream))
                 }
             }
@@ -1746,8 +2655,11 @@ pub fn maj_read_char(mut state: &mut MajState,
 pub fn maj_peek_char(mut state: &mut MajState,
                      stream: Gc<Maj>
 ) -> Gc<Maj> {
-    use std::io::{ Read, Seek, SeekFrom };
-    let mut buffer = [0; 1];
+    use std::io::{ Seek, SeekFrom };
+    use crate::axioms::predicates::maj_readerp;
+    if maj_readerp(stream.clone()).to_bool() {
+        return maj_reader_char(&mut state, stream, false);
+    }
     if stdstreamp(stream.clone()) {
         if stdstreamdirp(stream.clone(), MajStreamDirection::In) {
             let peekopt = state.pop_stdin_peeked();
@@ -1758,13 +2670,16 @@ pub fn maj_peek_char(mut state: &mut MajState,
                 },
                 None => {
                     use std::io;
-                    match io::stdin().read(&mut buffer) {
-                        Ok(_) => {
-                            state.push_stdin_peeked(buffer[0] as char);
-                            return Maj::character(buffer[0] as char);
+                    match maj_read_utf8(&mut io::stdin()) {
+                        Ok(None) => {
+                            return Maj::symbol(&mut state, "eof");
+                        },
+                        Ok(Some((c, _))) => {
+                            state.push_stdin_peeked(c);
+                            return Maj::character(c);
                         },
                         Err(_) => {
-                            return maj_err(
+                            return maj_io_err(
                                 Maj::string(
                                     "Could not read from stream *stdin*"),
                                 Maj::nil());
@@ -1773,7 +2688,7 @@ pub fn maj_peek_char(mut state: &mut MajState,
                 },
             }
         } else {
-            return maj_err(
+            return maj_type_err(
                 Maj::string("{} is not an input stream"),
                 maj_list!(stream));
         }
@@ -1783,19 +2698,22 @@ pub fn maj_peek_char(mut state: &mut MajState,
                          stream.clone(),
                          MajStreamDirection::In) {
         Ok(mut file) => {
-            match file.read(&mut buffer) {
-                Ok(_) => {
+            match maj_read_utf8(&mut file) {
+                Ok(Some((c, length))) => {
                     // Since a character was read,
                     // seek back to a point before it.
                     // If unable, this should fail anyway
-                    file.seek(SeekFrom::Current(-1))
+                    file.seek(SeekFrom::Current(-length))
                         .unwrap();
-                    Maj::character(buffer[0] as char)
+                    Maj::character(c)
+                },
+                Ok(None) => {
+                    Maj::symbol(&mut state, "eof")
                 },
                 Err(_) => {
-                    maj_err(
+                    maj_io_err(
                         Maj::string(
-                            "Could not read from stream {}"),
+                               "Could not read from stream {}"),
                         maj_list!(stream))
                 }
             }
@@ -1811,7 +2729,7 @@ pub fn maj_write_char(mut state: &mut MajState,
     use std::io::Write;
     use crate::axioms::predicates::maj_charp;
     if !maj_charp(c.clone()).to_bool() {
-        return maj_err(
+        return maj_type_err(
             Maj::string("{} is not a character"),
             maj_list!(c));
     }
@@ -1828,7 +2746,7 @@ pub fn maj_write_char(mut state: &mut MajState,
             };
             return Maj::nil();
         } else {
-            return maj_err(
+            return maj_type_err(
                 Maj::string("{} is not an output stream"),
                 maj_list!(stream));
         }
@@ -1844,9 +2762,9 @@ pub fn maj_write_char(mut state: &mut MajState,
                     Maj::nil()
                 },
                 Err(_) => {
-                    maj_err(
+                    maj_io_err(
                         Maj::string(
-                            "Could not write to stream {}"),
+                               "Could not write to stream {}"),
                         maj_list!(stream))
                 }
             }
@@ -1861,7 +2779,7 @@ pub fn maj_write_string(mut state: &mut MajState,
 ) -> Gc<Maj> {
     use std::io::Write;
     if !maj_stringp(strn.clone()).to_bool() {
-        return maj_err(
+        return maj_type_err(
             Maj::string("{} is not a string"),
             maj_list!(strn));
     }
@@ -1878,7 +2796,7 @@ pub fn maj_write_string(mut state: &mut MajState,
             };
             return Maj::nil();
         } else {
-            return maj_err(
+            return maj_type_err(
                 Maj::string("{} is not an output stream"),
                 maj_list!(stream));
         }
@@ -1894,9 +2812,9 @@ pub fn maj_write_string(mut state: &mut MajState,
                     Maj::nil()
                 },
                 Err(_) => {
-                    maj_err(
+                    maj_io_err(
                         Maj::string(
-                            "Could not write to stream {}"),
+                               "Could not write to stream {}"),
                         maj_list!(stream))
                 }
             }
@@ -1909,9 +2827,172 @@ pub fn maj_write(mut state: &mut MajState,
                  x: Gc<Maj>,
                  stream: Gc<Maj>
 ) -> Gc<Maj> {
-    use crate::printing::maj_format;
-    let string = Maj::string(&maj_format(&state, x));
-    maj_write_string(&mut state, string, stream)
+    use crate::printing::{ maj_format, maj_with_print_objects };
+    match maj_with_print_objects(&mut state, x.clone(),
+                                 |state| maj_format(state, x)) {
+        Ok(string) => {
+            let string = Maj::string(&string);
+            maj_write_string(&mut state, string, stream)
+        },
+        Err(err) => err,
+    }
+}
+
+pub fn maj_write_readably(mut state: &mut MajState,
+                          x: Gc<Maj>,
+                          stream: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::printing::maj_format_readably;
+    match maj_format_readably(&state, x) {
+        Ok(string) => {
+            let string = Maj::string(&string);
+            maj_write_string(&mut state, string, stream)
+        },
+        Err(err) => err,
+    }
+}
+
+pub fn maj_print_readably_p(mut state: &mut MajState, env: Gc<Maj>) -> bool {
+    let flag = Maj::symbol(&mut state, "*print-readably*");
+    let flag = state.lookup(env, flag);
+    !maj_nilp(flag.clone()).to_bool() && !maj_errorp(flag).to_bool()
+}
+
+use crate::reader::tokenizer::{
+    MajCharSource,
+    MajStringSource,
+    MajLexer,
+    MajReadError
+};
+use crate::reader::parser::{
+    MajReaderInput,
+    maj_read_datum
+};
+
+fn maj_read_err(origin: Option<Gc<Maj>>, err: MajReadError) -> Gc<Maj> {
+    let msg = match err {
+        MajReadError::Source(err) => return err,
+        MajReadError::Lexical(_, msg) | MajReadError::Syntax(msg) => msg,
+    };
+    match origin {
+        Some(stream) =>
+            maj_err(Maj::string("While reading from {}: {}"),
+                    maj_list!(stream, Maj::string(msg))),
+        None =>
+            maj_err(Maj::string("While reading from string: {}"),
+                    maj_list!(Maj::string(msg))),
+    }
+}
+
+fn maj_read_result(
+    result: Result<Option<Gc<Maj>>, MajReadError>,
+    origin: Option<Gc<Maj>>
+) -> Gc<Maj> {
+    match result {
+        Ok(Some(datum)) => datum,
+        Ok(None) => Maj::eof(),
+        Err(err) => maj_read_err(origin, err),
+    }
+}
+
+pub fn maj_read(mut state: &mut MajState, stream: Gc<Maj>) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_readerp;
+    let mut input = MajReaderInput::Stream(stream.clone());
+    let mut lexer = MajLexer::new();
+    let result = match maj_read_datum(&mut state, &mut lexer,
+                                      &mut input, None) {
+        Ok(None) if maj_readerp(stream.clone()).to_bool() => Err(
+            MajReadError::Syntax(
+                "Unexpected EOF after dispatch macro character")),
+        result => result,
+    };
+    maj_read_result(result, Some(stream))
+}
+
+pub fn maj_read_from_string(mut state: &mut MajState,
+                            string: Gc<Maj>,
+                            rest: Gc<Maj>) -> Gc<Maj> {
+    let text = match string.stringify() {
+        Some(text) => text,
+        None => return maj_type_err(
+            Maj::string("{} is not a string"),
+            maj_list!(string)),
+    };
+    if !maj_nilp(maj_cdr(rest.clone())).to_bool() {
+        return maj_arity_err(
+            Maj::string("Too many arguments to read-from-string"),
+            Maj::nil());
+    }
+    let length = text.chars().count() as i64;
+    let start = if maj_nilp(rest.clone()).to_bool() {
+        0
+    } else {
+        let start = maj_car(rest);
+        match start.to_integer() {
+            Some(n) if n >= 0 && n <= length => n,
+            _ => return maj_type_err(
+                Maj::string("{} is not a valid index for {}"),
+                maj_list!(start, string)),
+        }
+    };
+    let source = MajStringSource::new(&text, start as usize);
+    let mut input = MajReaderInput::Text(source);
+    let mut lexer = MajLexer::new();
+    let result = maj_read_datum(&mut state, &mut lexer, &mut input, None);
+    let datum = maj_read_result(result, None);
+    if maj_errorp(datum.clone()).to_bool() {
+        return datum;
+    }
+    let position = Maj::integer(input.position() as i64);
+    maj_values(&mut state, maj_list!(datum, position))
+}
+
+pub fn maj_read_all(mut state: &mut MajState, source: Gc<Maj>) -> Gc<Maj> {
+    let (mut input, origin) = match source.stringify() {
+        Some(text) =>
+            (MajReaderInput::Text(MajStringSource::new(&text, 0)), None),
+        None =>
+            (MajReaderInput::Stream(source.clone()), Some(source.clone())),
+    };
+    let mut lexer = MajLexer::new();
+    let mut data = Vec::new();
+    loop {
+        match maj_read_datum(&mut state, &mut lexer, &mut input, None) {
+            Ok(Some(datum)) => data.push(datum),
+            Ok(None) => break,
+            Err(err) => return maj_read_err(origin, err),
+        }
+    }
+    data.into_iter().rev()
+        .fold(Maj::nil(), |acc, datum| Maj::cons(datum, acc))
+}
+
+pub fn maj_set_dispatch_macro(state: &mut MajState,
+                              c: Gc<Maj>,
+                              function: Gc<Maj>) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_functionp;
+    use crate::reader::tokenizer::maj_dispatch_token_p;
+    let chr = match c.to_char() {
+        Some(chr) => chr,
+        None => return maj_type_err(
+            Maj::string("{} is not a character"),
+            maj_list!(c)),
+    };
+    if maj_dispatch_token_p(&format!("#{}", chr)).is_none() {
+        return maj_type_err(
+            Maj::string("{} cannot be used as a dispatch macro character"),
+            maj_list!(c));
+    }
+    if maj_nilp(function.clone()).to_bool() {
+        state.remove_dispatch_macro(chr);
+    } else if maj_functionp(function.clone()).to_bool() {
+        state.set_dispatch_macro(chr, function.clone());
+    } else {
+        return maj_type_err(
+            Maj::string("{} is not a function"),
+            maj_list!(function));
+    }
+    function
 }
 
 pub fn maj_terpri(mut state: &mut MajState,
@@ -1938,14 +3019,21 @@ pub fn maj_display(mut state: &mut MajState,
         return stdout;
     }
 
-    maj_write(&mut state, x, stdout)
+    // Strings and characters are shown as they are
+    if maj_stringp(x.clone()).to_bool() {
+        maj_write_string(&mut state, x, stdout)
+    } else if x.to_char().is_some() {
+        maj_write_char(&mut state, x, stdout)
+    } else {
+        maj_write(&mut state, x, stdout)
+    }
 }
 
 pub fn maj_pretty_display(mut state: &mut MajState,
                           x: Gc<Maj>,
                           env: Gc<Maj>
 ) -> Gc<Maj> {
-    use crate::printing::maj_pretty_format;
+    use crate::printing::{ maj_pretty_format, maj_with_print_objects };
     // Lookup dynamically bound stdout
     let stdout = Maj::symbol(&mut state, "*stdout*");
     let stdout = state.lookup(env.clone(), stdout);
@@ -1953,9 +3041,38 @@ pub fn maj_pretty_display(mut state: &mut MajState,
         return stdout;
     }
 
-    let string = maj_pretty_format(&state, x);
-    let string = Maj::string(&string);
-    maj_write_string(&mut state, string, stdout)
+    match maj_with_print_objects(&mut state, x.clone(),
+                                 |state| maj_pretty_format(state, x)) {
+        Ok(string) => {
+            let string = Maj::string(&string);
+            maj_write_string(&mut state, string, stdout)
+        },
+        Err(err) => err,
+    }
+}
+
+pub fn maj_set_pretty_indent(state: &mut MajState,
+                             sym: Gc<Maj>,
+                             args: Gc<Maj>) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_symbolp;
+    if !maj_symbolp(sym.clone()).to_bool() {
+        return maj_type_err(
+            Maj::string("{} is not a symbol"),
+            maj_list!(sym));
+    }
+    if maj_nilp(args.clone()).to_bool() {
+        state.remove_indent_rule(sym);
+        return args;
+    }
+    match args.to_integer() {
+        Some(num) if num >= 0 => {
+            state.set_indent_rule(sym, num as usize);
+            args
+        },
+        _ => maj_type_err(
+            Maj::string("{} is not a valid number of arguments"),
+            maj_list!(args)),
+    }
 }
 
 pub fn maj_print(
@@ -1971,7 +3088,7 @@ pub fn maj_print(
         return stdout;
     }
 
-    let formatted = maj_format_prim(&state, fmt, rest);
+    let formatted = maj_format_objects(&mut state, fmt, rest);
     if maj_errorp(formatted.clone()).to_bool() {
         formatted
     } else {
@@ -1987,52 +3104,64 @@ pub fn maj_load(
     env: Gc<Maj>,
     path: Gc<Maj>
 ) -> Gc<Maj> {
-    use crate::reader::parser::maj_parse;
-    use crate::reader::tokenizer::maj_tokenize_file;
+    use crate::reader::tokenizer::maj_read_file;
     use crate::evaluator::maj_eval;
-    match path.clone().stringify() {
-        Some(pathstr) => {
-            let mut buffer = String::new();
-            match maj_tokenize_file(&pathstr, &mut buffer) {
-                Ok(tokens) => {
-                    match maj_parse(&mut state, tokens.clone()) {
-                        Ok(expressions) => {
-                            // TODO: Iterate over forms and yield errors
-                            // depending on them
-                            let results = maj_eval(&mut state, Maj::cons(
-                                Maj::do_sym(), expressions), env);
-                            if maj_errorp(results.clone()).to_bool() {
-                                maj_err(
-                                    Maj::string(
-                                        "On evaluation of file {}: {}"),
-                                    maj_list!(path, results))
-                            } else {
-                                results
-                            }
-                        },
-                        Err(msg) => {
-                            maj_err(
-                                Maj::string("While parsing file {}: {}"),
-                                maj_list!(path, Maj::string(msg)))
-                        },
-                    }
-                },
-                Err((line, msg)) => {
-                    if line != 0 {
-                        maj_err(
-                            Maj::string("While reading file {}:{}: {}"),
-                            maj_list!(path, Maj::integer(line as i64),
-                                      Maj::string(msg)))
-                    } else {
-                        maj_err(
-                            Maj::string("While reading file {}: {}"),
-                            maj_list!(path, Maj::string(msg)))
-                    }
-                },
-            }
-        },
-        None => maj_err(Maj::string("{} is not a string"),
-                        maj_list!(path)),
+    use crate::evaluator::evaluation::maj_expand_all;
+    let pathstr = match path.clone().stringify() {
+        Some(pathstr) => pathstr,
+        None => return maj_type_err(Maj::string("{} is not a string"),
+                                    maj_list!(path)),
+    };
+    let text = match maj_read_file(&pathstr) {
+        Ok(text) => text,
+        Err(msg) => return maj_io_err(
+            Maj::string("While reading file {}: {}"),
+            maj_list!(path, Maj::string(msg))),
+    };
+    let mut input = MajReaderInput::Text(MajStringSource::new(&text, 0));
+    let mut lexer = MajLexer::new();
+    let mut results = Maj::nil();
+    loop {
+        let form = match maj_read_datum(&mut state, &mut lexer,
+                                        &mut input, Some(&pathstr)) {
+            Ok(Some(form)) => form,
+            Ok(None) => break,
+            Err(MajReadError::Lexical(line, msg)) => return maj_err(
+                Maj::string("While reading file {}:{}: {}"),
+                maj_list!(path, Maj::integer(line), Maj::string(msg))),
+            Err(MajReadError::Syntax(msg)) => return maj_err(
+                Maj::string("While parsing file {}: {}"),
+                maj_list!(path, Maj::string(msg))),
+            Err(MajReadError::Source(err)) => return err,
+        };
+        // Expand and compile each form just before evaluating it,
+        // so that macros and functions defined on previous forms
+        // are taken into account
+        let form = maj_expand_all(&mut state, form, env.clone());
+        results = maj_eval(&mut state, form, env.clone());
+        if maj_errorp(results.clone()).to_bool() {
+            break;
+        }
+    }
+    let throw = Maj::symbol(&mut state, "throw");
+    if maj_errorp(results.clone()).to_bool()
+        && !maj_eq(maj_condition_type(results.clone()), throw).to_bool()
+    {
+        // Keep type and context of original error
+        let location = maj_condition_location(results.clone());
+        if maj_nilp(location.clone()).to_bool() {
+            maj_error_with_message(
+                results.clone(),
+                Maj::string("On evaluation of file {}: {}"),
+                maj_list!(path, results))
+        } else {
+            maj_error_with_message(
+                results.clone(),
+                Maj::string("On evaluation of file {}:{}:{}: {}"),
+                maj_append(maj_list!(location, maj_list!(results))))
+        }
+    } else {
+        results
     }
 }
 
@@ -2040,7 +3169,7 @@ pub fn maj_vec_type(mut state: &mut MajState,
                     vec: Gc<Maj>) -> Gc<Maj> {
     use crate::core::types::MajVector;
     if !maj_vectorp(vec.clone()).to_bool() {
-        return maj_err(
+        return maj_type_err(
             Maj::string("{} is not a vector"),
             maj_list!(vec));
     }
@@ -2071,7 +3200,7 @@ pub fn maj_vec_insert(mut state: &mut MajState,
     use crate::core::types::MajVector;
 
     if !maj_vectorp(vec.clone()).to_bool() {
-        return maj_err(
+        return maj_type_err(
             Maj::string("{} is not a vector"),
             maj_list!(vec));
     }
@@ -2092,7 +3221,7 @@ pub fn maj_vec_insert(mut state: &mut MajState,
                 x as usize
             },
             None => {
-                return maj_err(
+                return maj_type_err(
                     Maj::string("{} is not an integer"),
                     maj_list!(pos));
             },
@@ -2100,7 +3229,7 @@ pub fn maj_vec_insert(mut state: &mut MajState,
 
     if !maj_eq(vectype.clone(), any).to_bool()
         && !maj_eq(xtype.clone(), vectype.clone()).to_bool() {
-        return maj_err(
+        return maj_type_err(
             Maj::string(
                 "{} has type {}, which is incompatible with insertion on vector of type {}"),
             maj_list!(x, xtype, vectype));
@@ -2188,7 +3317,7 @@ pub fn maj_vec_coerce(mut state: &mut MajState,
                       vec: Gc<Maj>) -> Gc<Maj> {
     use crate::core::types::MajVector;
     if !maj_vectorp(vec.clone()).to_bool() {
-        return maj_err(
+        return maj_type_err(
             Maj::string("{} is not a vector"),
             maj_list!(vec));
     }
@@ -2353,7 +3482,7 @@ pub fn maj_vec_length(vec: Gc<Maj>) -> Gc<Maj> {
                 MajVector::Any(v) => v.borrow().len(),
             } as i64)
     } else {
-        maj_err(
+        maj_type_err(
             Maj::string("{} is not a vector"),
             maj_list!(vec))
     }
@@ -2389,7 +3518,7 @@ pub fn maj_vec_pop(vec: Gc<Maj>) -> Gc<Maj> {
             }
         }
     } else {
-        maj_err(
+        maj_type_err(
             Maj::string("{} is not a vector"),
             maj_list!(vec))
     }
@@ -2432,7 +3561,7 @@ pub fn maj_vec_deq(vec: Gc<Maj>) -> Gc<Maj> {
             }
         }
     } else {
-        maj_err(
+        maj_type_err(
             Maj::string("{} is not a vector"),
             maj_list!(vec))
     }
@@ -2443,7 +3572,7 @@ pub fn maj_vec_at(x: Gc<Maj>, vec: Gc<Maj>) -> Gc<Maj> {
     use crate::axioms::predicates::maj_integerp;
 
     if !maj_integerp(x.clone()).to_bool() {
-        return maj_err(
+        return maj_type_err(
             Maj::string("{} is not an integer"),
             maj_list!(x));
     }
@@ -2486,7 +3615,7 @@ pub fn maj_vec_at(x: Gc<Maj>, vec: Gc<Maj>) -> Gc<Maj> {
             }
         }
     } else {
-        maj_err(
+        maj_type_err(
             Maj::string("{} is not a vector"),
             maj_list!(vec))
     }
@@ -2519,7 +3648,7 @@ pub fn maj_vec_set(
                 x as usize
             },
             None => {
-                return maj_err(
+                return maj_type_err(
                     Maj::string("{} is not an integer"),
                     maj_list!(pos));
             },
@@ -2529,7 +3658,7 @@ pub fn maj_vec_set(
         match vv {
             MajVector::Integer(v) => {
                 if !maj_integerp(x.clone()).to_bool() {
-                    return maj_err(
+                    return maj_type_err(
                         Maj::string(
                             "{} is not type-compatible with vector {}"),
                         maj_list!(x.clone(), vec.clone()));
@@ -2547,7 +3676,7 @@ pub fn maj_vec_set(
             },
             MajVector::Float(v) => {
                 if!maj_floatp(x.clone()).to_bool() {
-                    return maj_err(
+                    return maj_type_err(
                         Maj::string(
                             "{} is not type-compatible with vector {}"),
                         maj_list!(x.clone(), vec.clone()));
@@ -2565,9 +3694,9 @@ pub fn maj_vec_set(
             },
             MajVector::Char(s) => {
                 if!maj_charp(x.clone()).to_bool() {
-                    maj_err(
+                    maj_type_err(
                         Maj::string(
-                            "{} is not type-compatible with vector {}"),
+                                 "{} is not type-compatible with vector {}"),
                         maj_list!(x.clone(), vec.clone()));
                 }
                 let c = x.clone().to_char().unwrap();
@@ -2594,7 +3723,7 @@ pub fn maj_vec_set(
             },
         }
     } else {
-        maj_err(
+        maj_type_err(
             Maj::string("{} is not a vector"),
             maj_list!(vec))
     }
@@ -2614,7 +3743,7 @@ pub fn maj_vec_remove(pos: Gc<Maj>, vec: Gc<Maj>) -> Gc<Maj> {
                 x as usize
             },
             None => {
-                return maj_err(
+                return maj_type_err(
                     Maj::string("{} is not an integer"),
                     maj_list!(pos));
             },
@@ -2670,9 +3799,170 @@ pub fn maj_vec_remove(pos: Gc<Maj>, vec: Gc<Maj>) -> Gc<Maj> {
             },
         }
     } else {
-        maj_err(Maj::string("{} is not a vector"),
-                maj_list!(vec))
+        maj_type_err(Maj::string("{} is not a vector"),
+                     maj_list!(vec))
+    }
+}
+
+pub fn maj_make_record(
+    type_sym: Gc<Maj>,
+    fields: Gc<Maj>,
+    values: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_symbolp;
+    if !maj_symbolp(type_sym.clone()).to_bool()
+        || maj_nilp(type_sym.clone()).to_bool()
+    {
+        return maj_type_err(Maj::string("{} is not a valid record type"),
+                            maj_list!(type_sym));
+    }
+    if !maj_proper_list_p(fields.clone()).to_bool() {
+        return maj_type_err(Maj::string("{} is not a list of fields"),
+                            maj_list!(fields));
+    }
+
+    let mut new_values = Vec::new();
+    let mut field_itr = fields.clone();
+    let mut value_itr = values.clone();
+    while !maj_nilp(field_itr.clone()).to_bool() {
+        let field = maj_car(field_itr.clone());
+        if !maj_symbolp(field.clone()).to_bool() {
+            return maj_type_err(Maj::string("{} is not a symbol"),
+                                maj_list!(field));
+        }
+        if maj_nilp(value_itr.clone()).to_bool() {
+            return maj_arity_err(
+                Maj::string("Too few values for record {}"),
+                maj_list!(type_sym));
+        }
+        new_values.push(maj_car(value_itr.clone()));
+        field_itr = maj_cdr(field_itr);
+        value_itr = maj_cdr(value_itr);
+    }
+    if !maj_nilp(value_itr).to_bool() {
+        return maj_arity_err(
+            Maj::string("Too many values for record {}"),
+            maj_list!(type_sym));
+    }
+
+    // Values are copied, since records are mutable
+    let mut record = Maj::nil();
+    for value in new_values.iter().rev() {
+        record = Maj::cons(value.clone(), record);
+    }
+    use crate::maj_dotted_list;
+    maj_dotted_list!(Maj::lit(), Maj::record(), type_sym, fields, record)
+}
+
+fn maj_record_cell(record: Gc<Maj>, field: Gc<Maj>) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_recordp;
+    if !maj_recordp(record.clone()).to_bool() {
+        return maj_type_err(Maj::string("{} is not a record"),
+                            maj_list!(record));
+    }
+    // (lit record type fields . values)
+    let mut fields = maj_car(maj_cdr(maj_cdr(maj_cdr(record.clone()))));
+    let mut values = maj_cdr(maj_cdr(maj_cdr(maj_cdr(record.clone()))));
+    while !maj_nilp(fields.clone()).to_bool() {
+        if maj_eq(maj_car(fields.clone()), field.clone()).to_bool() {
+            return values;
+        }
+        fields = maj_cdr(fields);
+        values = maj_cdr(values);
+    }
+    maj_err(Maj::string("{} has no field {}"),
+            maj_list!(maj_car(maj_cdr(maj_cdr(record))), field))
+}
+
+pub fn maj_record_get(record: Gc<Maj>, field: Gc<Maj>) -> Gc<Maj> {
+    let cell = maj_record_cell(record, field);
+    if maj_errorp(cell.clone()).to_bool() {
+        cell
+    } else {
+        maj_car(cell)
+    }
+}
+
+pub fn maj_record_set(
+    record: Gc<Maj>,
+    field: Gc<Maj>,
+    value: Gc<Maj>
+) -> Gc<Maj> {
+    let cell = maj_record_cell(record, field);
+    if maj_errorp(cell.clone()).to_bool() {
+        return cell;
+    }
+    let new_cell = Maj::cons(value.clone(), maj_cdr(cell.clone()));
+    unsafe {
+        let rawcell = Gc::into_raw(cell);
+        std::ptr::copy_nonoverlapping(
+            Gc::into_raw(new_cell),
+            rawcell as *mut Maj, 1);
+    }
+    value
+}
+
+pub fn maj_make_generic(
+    state: &mut MajState,
+    name: Gc<Maj>,
+    lambda_list: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_symbolp;
+    if !maj_symbolp(name.clone()).to_bool()
+        || maj_nilp(name.clone()).to_bool()
+    {
+        return maj_type_err(Maj::string("{} is not a symbol"),
+                            maj_list!(name));
+    }
+    let required = maj_required_params(lambda_list.clone());
+    if maj_errorp(required.clone()).to_bool() {
+        return required;
+    }
+    let required = maj_length(required).to_integer().unwrap() as usize;
+    if let Err(previous) = state.define_generic(name.clone(), required) {
+        return maj_err(
+            Maj::string("Generic {} has methods for {} arguments, not {}"),
+            maj_list!(name,
+                      Maj::integer(previous as i64),
+                      Maj::integer(required as i64)));
+    }
+    maj_list!(Maj::lit(), Maj::generic(), name)
+}
+
+pub fn maj_required_params(lambda_list: Gc<Maj>) -> Gc<Maj> {
+    use crate::axioms::predicates::{ maj_consp, maj_symbolp };
+    let mut params = Vec::new();
+    let mut itr = lambda_list;
+    while maj_consp(itr.clone()).to_bool() {
+        let param = maj_car(itr.clone());
+        if maj_eq(param.clone(), Maj::ampersand()).to_bool()
+            || maj_eq(param.clone(), Maj::optional()).to_bool()
+            || maj_eq(param.clone(), Maj::key()).to_bool()
+        {
+            break;
+        }
+        let valid =
+            if maj_symbolp(param.clone()).to_bool() {
+                !maj_nilp(param.clone()).to_bool()
+            } else {
+                maj_proper_list_p(param.clone()).to_bool()
+                    && maj_length(param.clone()).to_integer().unwrap() == 2
+                    && maj_symbolp(maj_car(param.clone())).to_bool()
+                    && maj_symbolp(maj_car(maj_cdr(param.clone())))
+                    .to_bool()
+            };
+        if !valid {
+            return maj_err(Maj::string("Invalid parameter {}"),
+                           maj_list!(param));
+        }
+        params.push(param);
+        itr = maj_cdr(itr);
     }
+    let mut list = Maj::nil();
+    for param in params.iter().rev() {
+        list = Maj::cons(param.clone(), list);
+    }
+    list
 }
 
 pub fn maj_gc() -> Gc<Maj> {
@@ -2688,13 +3978,9 @@ fn maj_print_env(
 ) -> Gc<Maj> {
     use crate::printing::maj_format_env;
     let is_global =
-        maj_eq(maj_car(args.clone()),
-               Maj::symbol(&mut state, "global"))
-        .to_bool();
+        maj_option_p(&mut state, maj_car(args.clone()), "global");
     let is_lexical =
-        maj_eq(maj_car(args.clone()),
-               Maj::symbol(&mut state, "lexical"))
-        .to_bool();
+        maj_option_p(&mut state, maj_car(args.clone()), "lexical");
 
     if is_global {
         println!("{}", state);
@@ -2770,19 +4056,81 @@ pub fn maj_gen_primitives(state: &mut MajState) {
             maj_destructure_args!(args, first);
             maj_get_environment(&mut state, first, env)
         }),
+        ("eval", MajPrimArgs::Variadic(1), |mut state, args, _| {
+            maj_destructure_args!(args, first, rest);
+            maj_eval_prim(&mut state, first, rest)
+        }),
         ("coin", MajPrimArgs::None, |_, _, _| maj_coin()),
         ("sys", MajPrimArgs::Variadic(1), |_, args, _| {
             maj_destructure_args!(args, first, rest);
             maj_sys(first, rest)
         }),
-        ("format", MajPrimArgs::Variadic(1), |state, args, _| {
+        ("format", MajPrimArgs::Variadic(1), |mut state, args, _| {
             maj_destructure_args!(args, first, rest);
-            maj_format_prim(&state, first, rest)
+            maj_format_objects(&mut state, first, rest)
         }),
         ("err", MajPrimArgs::Variadic(1), |_, args, _| {
             maj_destructure_args!(args, first, rest);
             maj_err(first, rest)
         }),
+        ("error", MajPrimArgs::Variadic(2), |_, args, _| {
+            maj_destructure_args!(args, first, rest, second, rest2);
+            maj_error(first, second, rest2)
+        }),
+        ("signal", MajPrimArgs::Variadic(1), |_, args, _| {
+            maj_destructure_args!(args, first, rest);
+            maj_signal(first, rest)
+        }),
+        ("throw", MajPrimArgs::Required(2), |mut state, args, _| {
+            maj_destructure_args!(args, first, rest, second);
+            maj_throw(&mut state, first, second)
+        }),
+        ("call/cc", MajPrimArgs::Required(1), |mut state, args, env| {
+            maj_destructure_args!(args, first);
+            maj_call_cc(&mut state, first, env)
+        }),
+        ("values", MajPrimArgs::Variadic(0),
+         |mut state, args, _| maj_values(&mut state, args)),
+        ("make-coroutine", MajPrimArgs::Required(1), |mut state, args, _| {
+            maj_destructure_args!(args, first);
+            maj_make_coroutine(&mut state, first)
+        }),
+        ("resume", MajPrimArgs::Variadic(1), |mut state, args, _| {
+            maj_destructure_args!(args, first, rest);
+            maj_resume_prim(&mut state, first, rest)
+        }),
+        ("coroutine-status", MajPrimArgs::Required(1),
+         |mut state, args, _| {
+             maj_destructure_args!(args, first);
+             maj_coroutine_status(&mut state, first)
+         }),
+        ("error-type", MajPrimArgs::Required(1), |_, args, _| {
+            maj_destructure_args!(args, first);
+            maj_error_type(first)
+        }),
+        ("error-message", MajPrimArgs::Required(1), |state, args, _| {
+            maj_destructure_args!(args, first);
+            maj_error_message(&state, first)
+        }),
+        ("error-irritants", MajPrimArgs::Required(1), |_, args, _| {
+            maj_destructure_args!(args, first);
+            maj_error_irritants(first)
+        }),
+        ("error-form", MajPrimArgs::Required(1), |_, args, _| {
+            maj_destructure_args!(args, first);
+            maj_error_form(first)
+        }),
+        ("error-stack", MajPrimArgs::Required(1), |_, args, _| {
+            maj_destructure_args!(args, first);
+            maj_error_stack(first)
+        }),
+        ("error-location", MajPrimArgs::Required(1), |_, args, _| {
+            maj_destructure_args!(args, first);
+            maj_error_location(first)
+        }),
+        ("backtrace", MajPrimArgs::Variadic(0), |state, args, _| {
+            maj_backtrace(&state, args)
+        }),
         ("warn", MajPrimArgs::Variadic(1), |mut state, args, env| {
             maj_destructure_args!(args, first, rest);
             maj_warn(&mut state, first, rest, env)
@@ -2814,6 +4162,23 @@ pub fn maj_gen_primitives(state: &mut MajState) {
                  maj_macroexpand_1(&mut state, first, env);
              result
          }),
+        ("macroexpand", MajPrimArgs::Required(1),
+         |mut state, args, env| {
+             maj_destructure_args!(args, first);
+             maj_macroexpand(&mut state, first, env)
+         }),
+        ("macroexpand-all", MajPrimArgs::Required(1),
+         |mut state, args, env| {
+             maj_destructure_args!(args, first);
+             maj_macroexpand_all(&mut state, first, env)
+         }),
+        ("loop-expand", MajPrimArgs::Required(1),
+         |mut state, args, _| {
+             maj_destructure_args!(args, first);
+             maj_loop_expand(&mut state, first)
+         }),
+        ("proclaim", MajPrimArgs::Variadic(0),
+         |mut state, args, _| maj_proclaim(&mut state, args)),
         ("not", MajPrimArgs::Required(1), |_, args, _| {
             maj_destructure_args!(args, first);
             maj_not(first)
@@ -2925,6 +4290,24 @@ pub fn maj_gen_primitives(state: &mut MajState) {
             maj_destructure_args!(args, first);
             maj_peek_char(&mut state, first)
         }),
+        ("read", MajPrimArgs::Required(1), |mut state, args, _| {
+            maj_destructure_args!(args, first);
+            maj_read(&mut state, first)
+        }),
+        ("read-from-string", MajPrimArgs::Variadic(1),
+         |mut state, args, _| {
+             maj_destructure_args!(args, first, rest);
+             maj_read_from_string(&mut state, first, rest)
+         }),
+        ("read-all", MajPrimArgs::Required(1), |mut state, args, _| {
+            maj_destructure_args!(args, first);
+            maj_read_all(&mut state, first)
+        }),
+        ("set-dispatch-macro", MajPrimArgs::Required(2),
+         |mut state, args, _| {
+             maj_destructure_args!(args, first, rest, second);
+             maj_set_dispatch_macro(&mut state, first, second)
+         }),
         ("write-char", MajPrimArgs::Required(2), |mut state, args, _| {
             maj_destructure_args!(args, first, rest, second);
             maj_write_char(&mut state, first, second)
@@ -2933,9 +4316,13 @@ pub fn maj_gen_primitives(state: &mut MajState) {
             maj_destructure_args!(args, first, rest, second);
             maj_write_string(&mut state, first, second)
         }),
-        ("write", MajPrimArgs::Required(2), |mut state, args, _| {
+        ("write", MajPrimArgs::Required(2), |mut state, args, env| {
             maj_destructure_args!(args, first, rest, second);
-            maj_write(&mut state, first, second)
+            if maj_print_readably_p(&mut state, env) {
+                maj_write_readably(&mut state, first, second)
+            } else {
+                maj_write(&mut state, first, second)
+            }
         }),
         ("terpri", MajPrimArgs::None, |mut state, _, env| {
             maj_terpri(&mut state, env)
@@ -2949,6 +4336,11 @@ pub fn maj_gen_primitives(state: &mut MajState) {
              maj_destructure_args!(args, first);
              maj_pretty_display(&mut state, first, env)
          }),
+        ("set-pretty-indent", MajPrimArgs::Required(2),
+         |mut state, args, _| {
+             maj_destructure_args!(args, first, rest, second);
+             maj_set_pretty_indent(&mut state, first, second)
+         }),
         ("print", MajPrimArgs::Variadic(1), |mut state, args, env| {
             maj_destructure_args!(args, first, rest);
             maj_print(&mut state, first, rest, env)
@@ -3005,6 +4397,28 @@ pub fn maj_gen_primitives(state: &mut MajState) {
             maj_vec_remove(first, second)
         }),
 
+        // Record functions
+        ("make-record", MajPrimArgs::Variadic(2), |_, args, _| {
+            maj_destructure_args!(args, first, rest, second, rest2);
+            maj_make_record(first, second, rest2)
+        }),
+        ("record-get", MajPrimArgs::Required(2), |_, args, _| {
+            maj_destructure_args!(args, first, rest, second);
+            maj_record_get(first, second)
+        }),
+        ("record-set", MajPrimArgs::Required(3), |_, args, _| {
+            maj_destructure_args!(args, first, rest, second,
+                                  sndrst, third);
+            maj_record_set(first, second, third)
+        }),
+
+        // Generic functions
+        ("make-generic", MajPrimArgs::Required(2),
+         |mut state, args, _| {
+             maj_destructure_args!(args, first, rest, second);
+             maj_make_generic(&mut state, first, second)
+         }),
+
         // Non-standard functions
         ("gc", MajPrimArgs::None, |_, _, _| maj_gc()),
         ("print-env", MajPrimArgs::Required(1), maj_print_env),
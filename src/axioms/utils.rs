@@ -38,10 +38,11 @@ pub fn simplify_frac(x: Gc<Maj>) -> Result<Gc<Maj>, Gc<Maj>> {
         maj_numer,
         maj_denom,
         maj_err,
+        maj_type_err,
     };
     if !maj_fractionp(x.clone()).to_bool() {
-        Err(maj_err(Maj::string("{} is not a fraction"),
-                    maj_list!(x)))
+        Err(maj_type_err(Maj::string("{} is not a fraction"),
+                         maj_list!(x)))
     } else {
         let numer = maj_numer(x.clone()).to_integer().unwrap();
         let denom = maj_denom(x.clone()).to_integer().unwrap();
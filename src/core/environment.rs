@@ -22,7 +22,7 @@ pub fn maj_env_push(env: Gc<Maj>, sym: Gc<Maj>, val: Gc<Maj>) -> Gc<Maj> {
 }
 
 pub fn maj_env_assoc(env: Gc<Maj>, sym: Gc<Maj>) -> Gc<Maj> {
-    use crate::axioms::primitives::maj_err;
+    use crate::axioms::primitives::maj_unbound_err;
     use crate::maj_list;
     let mut itr = env.clone();
     while !maj_nilp(itr.clone()).to_bool() {
@@ -42,7 +42,7 @@ pub fn maj_env_assoc(env: Gc<Maj>, sym: Gc<Maj>) -> Gc<Maj> {
             panic!("Environment is not an alist");
         }
     }
-    maj_err(
+    maj_unbound_err(
         Maj::string("{} is unbound"),
         maj_list!(sym))
 }
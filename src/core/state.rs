@@ -1,9 +1,13 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::collections::VecDeque;
+use std::cell::{ Cell, RefCell };
 use std::fs::File;
 use gc::Gc;
 use super::Maj;
 use crate::axioms::{ MajPrimFn, MajPrimArgs };
+use crate::evaluator::evaluation::MajCoroutineFrame;
+use crate::reader::parser::MajReaderInput;
 use bimap::BiMap;
 
 use std::fmt;
@@ -17,7 +21,32 @@ pub struct MajState {
     streams:      Vec<MajInternalStream>,
     free_streams: VecDeque<usize>,
     stdin_peeked: Option<char>,
-    global_env:   Gc<Maj>
+    global_env:   Gc<Maj>,
+    call_stack:   Vec<MajFrame>,
+    locations:    MajWeakTable<Gc<Maj>>,
+    continuations: Vec<Gc<Maj>>,
+    specials:     HashSet<u64>,
+    dynamic_env:  Gc<Maj>,
+    values:       Option<(Gc<Maj>, Gc<Maj>)>,
+    expanded:     MajWeakTable<()>,
+    compiling:    bool,
+    inlines:      HashMap<u64, bool>,
+    redefined:    HashSet<u64>,
+    sources:      MajWeakTable<Gc<Maj>>,
+    generics:     HashMap<u64, MajGeneric>,
+    print_objects: HashMap<usize, (Gc<Maj>, String)>,
+    coroutines:   MajWeakTable<MajCoroutine>,
+    readtable:    HashMap<char, Gc<Maj>>,
+    read_labels:  HashMap<usize, Gc<Maj>>,
+    readers:      Vec<(Gc<Maj>, MajReaderInput)>,
+    read_objects: Vec<Gc<Maj>>,
+    indent_rules: HashMap<u64, usize>
+}
+
+struct MajFrame {
+    form:     Gc<Maj>,
+    function: Gc<Maj>,
+    args:     Gc<Maj>,
 }
 
 impl MajState {
@@ -31,7 +60,26 @@ impl MajState {
                 streams:      Vec::new(),
                 free_streams: VecDeque::new(),
                 stdin_peeked: None,
-                global_env:   Maj::nil()
+                global_env:   Maj::nil(),
+                call_stack:   Vec::new(),
+                locations:    MajWeakTable::new(),
+                continuations: Vec::new(),
+                specials:     HashSet::new(),
+                dynamic_env:  Maj::nil(),
+                values:       None,
+                expanded:     MajWeakTable::new(),
+                compiling:    true,
+                inlines:      HashMap::new(),
+                redefined:    HashSet::new(),
+                sources:      MajWeakTable::new(),
+                generics:     HashMap::new(),
+                print_objects: HashMap::new(),
+                coroutines:   MajWeakTable::new(),
+                readtable:    HashMap::new(),
+                read_labels:  HashMap::new(),
+                readers:      Vec::new(),
+                read_objects: Vec::new(),
+                indent_rules: HashMap::new()
             };
         majestic_initialize(&mut state);
         state
@@ -55,9 +103,7 @@ impl MajState {
 impl MajState {
     pub fn gen_random_symbol(&mut self) -> u64 {
         let new_sym = self.last_sym;
-        let sym_name = format!(":G{}", new_sym);
         self.last_sym += 1;
-        self.symbols.insert(new_sym, sym_name.to_string());
         new_sym
     }
 }
@@ -66,7 +112,22 @@ impl MajState {
     pub fn symbol_name(&self, sym: &u64) -> String {
         match self.symbols.get_by_left(sym) {
             Some(string) => string.clone(),
-            None => format!("~uninterned##{}", sym)
+            None => format!("#:G{}", sym)
+        }
+    }
+}
+
+impl MajState {
+    pub fn find_symbol(&self, name: &str) -> Option<u64> {
+        self.symbols.get_by_right(&name.to_string()).cloned()
+    }
+}
+
+impl MajState {
+    pub fn keywordp(&self, sym: &u64) -> bool {
+        match self.symbols.get_by_left(sym) {
+            Some(name) => name.len() > 1 && name.starts_with(':'),
+            None => false,
         }
     }
 }
@@ -231,18 +292,18 @@ impl MajState {
 
 impl MajState {
     pub fn close_stream(&mut self, stream: Gc<Maj>) -> Gc<Maj> {
-        use crate::axioms::primitives::maj_err;
+        use crate::axioms::primitives::{ maj_io_err, maj_type_err };
         use crate::maj_list;
         if let Maj::Stream(mstream) = &*stream.clone() {
             if mstream.is_internal() {
-                return maj_err(
+                return maj_io_err(
                     Maj::string("Cannot close standard streams"),
                     Maj::nil());
             }
 
             let index = mstream.handle;
             if self.streams.len() <= index {
-                maj_err(
+                maj_io_err(
                     Maj::string("Invalid stream {}"),
                     maj_list!(stream))
             } else {
@@ -255,7 +316,7 @@ impl MajState {
                 }
             }
         }  else {
-            maj_err(
+            maj_type_err(
                 Maj::string("Not a stream: {}"),
                 maj_list!(stream))
         }
@@ -264,9 +325,9 @@ impl MajState {
 
 impl MajState {
     pub fn stat_stream(&mut self, which: usize) -> Gc<Maj> {
-        use crate::axioms::primitives::maj_err;
+        use crate::axioms::primitives::maj_io_err;
         if self.streams.len() <= which {
-            maj_err(
+            maj_io_err(
                 Maj::string("Invalid stream"),
                 Maj::nil())
         } else {
@@ -308,6 +369,533 @@ impl MajState {
     }
 }
 
+impl MajState {
+    pub fn push_frame(
+        &mut self,
+        form: Gc<Maj>,
+        function: Gc<Maj>,
+        args: Gc<Maj>
+    ) {
+        self.call_stack.push(MajFrame { form, function, args });
+    }
+
+    pub fn pop_frame(&mut self) {
+        let _ = self.call_stack.pop();
+    }
+}
+
+impl MajState {
+    pub fn call_stack(&self) -> Gc<Maj> {
+        let mut list = Maj::nil();
+        for frame in self.call_stack.iter() {
+            list = Maj::cons(self.source_form(frame.form.clone()), list);
+        }
+        list
+    }
+}
+
+impl MajState {
+    pub fn backtrace(&self) -> Gc<Maj> {
+        use crate::axioms::predicates::maj_symbolp;
+        use crate::axioms::primitives::maj_car;
+        use crate::maj_list;
+        let mut list = Maj::nil();
+        for frame in self.call_stack.iter() {
+            let head = maj_car(self.source_form(frame.form.clone()));
+            let function =
+                if maj_symbolp(head.clone()).to_bool() {
+                    head
+                } else {
+                    frame.function.clone()
+                };
+            list = Maj::cons(
+                maj_list!(function,
+                          frame.args.clone(),
+                          self.location(frame.form.clone())),
+                list);
+        }
+        list
+    }
+}
+
+thread_local! {
+    // Live objects used as weak keys, and the ticket of each one
+    static MAJ_WEAK_KEYS: RefCell<(HashMap<usize, u64>, u64)> =
+        RefCell::new((HashMap::new(), 0));
+    // Keys released while the table above was borrowed
+    static MAJ_WEAK_PENDING: Cell<Vec<usize>> = Cell::new(Vec::new());
+}
+
+fn maj_weak_key(obj: &Gc<Maj>) -> usize {
+    &**obj as *const Maj as usize
+}
+
+fn maj_weak_keys<F, R>(f: F) -> R
+where F: FnOnce(&mut (HashMap<usize, u64>, u64)) -> R {
+    MAJ_WEAK_KEYS.with(|keys| {
+        let mut keys = keys.borrow_mut();
+        for key in MAJ_WEAK_PENDING.with(Cell::take) {
+            keys.0.remove(&key);
+        }
+        f(&mut keys)
+    })
+}
+
+fn maj_weak_ticket(key: usize) -> Option<u64> {
+    maj_weak_keys(|keys| keys.0.get(&key).copied())
+}
+
+fn maj_weak_register(key: usize) -> u64 {
+    maj_weak_keys(|keys| {
+        if let Some(ticket) = keys.0.get(&key) {
+            return *ticket;
+        }
+        keys.1 += 1;
+        let ticket = keys.1;
+        keys.0.insert(key, ticket);
+        ticket
+    })
+}
+
+pub fn maj_weak_release(obj: &Maj) {
+    let key = obj as *const Maj as usize;
+    // The thread may be exiting, or the collector may run mid-borrow;
+    // in the latter case the key is queued until the next access
+    let _ = MAJ_WEAK_KEYS.try_with(|keys| {
+        match keys.try_borrow_mut() {
+            Ok(mut keys) => {
+                keys.0.remove(&key);
+            },
+            Err(_) => {
+                let _ = MAJ_WEAK_PENDING.try_with(|pending| {
+                    let mut queued = pending.take();
+                    queued.push(key);
+                    pending.set(queued);
+                });
+            },
+        }
+    });
+}
+
+pub struct MajWeakTable<T> {
+    entries: HashMap<usize, (u64, T)>,
+    live:    usize,
+}
+
+impl<T> MajWeakTable<T> {
+    pub fn new() -> MajWeakTable<T> {
+        MajWeakTable {
+            entries: HashMap::new(),
+            live:    0,
+        }
+    }
+
+    pub fn insert(&mut self, obj: &Gc<Maj>, value: T) {
+        if self.entries.len() >= 2 * self.live.max(64) {
+            self.entries.retain(|key, (ticket, _)| {
+                maj_weak_ticket(*key) == Some(*ticket)
+            });
+            self.live = self.entries.len();
+        }
+        let key = maj_weak_key(obj);
+        let ticket = maj_weak_register(key);
+        self.entries.insert(key, (ticket, value));
+    }
+
+    pub fn get(&self, obj: &Gc<Maj>) -> Option<&T> {
+        let key = maj_weak_key(obj);
+        match self.entries.get(&key) {
+            Some((ticket, value))
+                if maj_weak_ticket(key) == Some(*ticket) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, obj: &Gc<Maj>) -> Option<&mut T> {
+        let key = maj_weak_key(obj);
+        match self.entries.get_mut(&key) {
+            Some((ticket, value))
+                if maj_weak_ticket(key) == Some(*ticket) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn remove(&mut self, obj: &Gc<Maj>) {
+        let _ = self.entries.remove(&maj_weak_key(obj));
+    }
+
+    pub fn live(&self) -> usize {
+        self.entries.iter()
+            .filter(|(key, (ticket, _))| {
+                maj_weak_ticket(**key) == Some(*ticket)
+            })
+            .count()
+    }
+}
+
+impl MajState {
+    pub fn set_location(&mut self, form: Gc<Maj>, location: Gc<Maj>) {
+        self.locations.insert(&form, location);
+    }
+}
+
+impl MajState {
+    pub fn location(&self, form: Gc<Maj>) -> Gc<Maj> {
+        match self.locations.get(&form) {
+            Some(location) => location.clone(),
+            None => Maj::nil(),
+        }
+    }
+}
+
+impl MajState {
+    pub fn push_continuation(&mut self, tag: Gc<Maj>) {
+        self.continuations.push(tag);
+    }
+
+    pub fn pop_continuation(&mut self) {
+        let _ = self.continuations.pop();
+    }
+}
+
+impl MajState {
+    pub fn continuation_activep(&self, tag: Gc<Maj>) -> bool {
+        use crate::axioms::predicates::maj_eq;
+        self.continuations
+            .iter()
+            .any(|active| maj_eq(active.clone(), tag.clone()).to_bool())
+    }
+}
+
+impl MajState {
+    pub fn declare_special(&mut self, sym: Gc<Maj>) {
+        if let Maj::Sym(num) = *sym {
+            self.specials.insert(num);
+        }
+    }
+
+    pub fn specialp(&self, sym: Gc<Maj>) -> bool {
+        match *sym {
+            Maj::Sym(num) => self.specials.contains(&num),
+            _             => false,
+        }
+    }
+}
+
+impl MajState {
+    pub fn get_dynamic_env(&self) -> Gc<Maj> {
+        self.dynamic_env.clone()
+    }
+
+    pub fn set_dynamic_env(&mut self, env: Gc<Maj>) {
+        self.dynamic_env = env;
+    }
+}
+
+impl MajState {
+    pub fn set_values(&mut self, primary: Gc<Maj>, values: Gc<Maj>) {
+        self.values = Some((primary, values));
+    }
+
+    pub fn clear_values(&mut self) {
+        self.values = None;
+    }
+}
+
+impl MajState {
+    pub fn take_values(&mut self, result: Gc<Maj>) -> Gc<Maj> {
+        use crate::maj_list;
+        match self.values.take() {
+            Some((primary, values)) if Gc::ptr_eq(&primary, &result) => values,
+            _ => maj_list!(result),
+        }
+    }
+}
+
+impl MajState {
+    pub fn mark_expanded(&mut self, body: Gc<Maj>) {
+        self.expanded.insert(&body, ());
+    }
+
+    pub fn expandedp(&self, body: Gc<Maj>) -> bool {
+        self.expanded.get(&body).is_some()
+    }
+}
+
+impl MajState {
+    pub fn set_compiling(&mut self, compiling: bool) -> bool {
+        let previous = self.compiling;
+        self.compiling = compiling;
+        previous
+    }
+
+    pub fn compilingp(&self) -> bool {
+        self.compiling
+    }
+}
+
+impl MajState {
+    pub fn declare_inline(&mut self, sym: Gc<Maj>, inline: bool) {
+        if let Maj::Sym(num) = *sym {
+            self.inlines.insert(num, inline);
+        }
+    }
+
+    pub fn inline_declaration(&self, sym: Gc<Maj>) -> Option<bool> {
+        match *sym {
+            Maj::Sym(num) => self.inlines.get(&num).copied(),
+            _             => None,
+        }
+    }
+}
+
+impl MajState {
+    pub fn set_source(&mut self, form: Gc<Maj>, source: Gc<Maj>) {
+        self.sources.insert(&form, source);
+    }
+
+    pub fn source_form(&self, form: Gc<Maj>) -> Gc<Maj> {
+        match self.sources.get(&form) {
+            Some(source) => source.clone(),
+            None => form,
+        }
+    }
+}
+
+impl MajState {
+    pub fn mark_redefined(&mut self, sym: Gc<Maj>) {
+        if let Maj::Sym(num) = *sym {
+            if self.primitives.contains_key(&num) {
+                self.redefined.insert(num);
+            }
+        }
+    }
+
+    pub fn redefinedp(&self, sym: Gc<Maj>) -> bool {
+        match *sym {
+            Maj::Sym(num) => self.redefined.contains(&num),
+            _             => false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MajMethod {
+    pub specializers: Vec<Gc<Maj>>,
+    pub function:     Gc<Maj>,
+}
+
+pub struct MajGeneric {
+    required: usize,
+    methods:  Vec<MajMethod>,
+}
+
+impl MajState {
+    pub fn define_generic(
+        &mut self,
+        name: Gc<Maj>,
+        required: usize
+    ) -> Result<(), usize> {
+        if let Maj::Sym(num) = *name {
+            match self.generics.get(&num) {
+                Some(generic) if generic.required == required => {},
+                Some(generic) if !generic.methods.is_empty() => {
+                    return Err(generic.required);
+                },
+                _ => {
+                    self.generics.insert(num, MajGeneric {
+                        required,
+                        methods: Vec::new(),
+                    });
+                },
+            }
+        }
+        Ok(())
+    }
+
+    pub fn generic_required(&self, name: Gc<Maj>) -> Option<usize> {
+        match *name {
+            Maj::Sym(num) => self.generics.get(&num).map(|g| g.required),
+            _             => None,
+        }
+    }
+}
+
+impl MajState {
+    pub fn add_method(&mut self, name: Gc<Maj>, method: MajMethod) {
+        use crate::axioms::predicates::maj_eq;
+        if let Maj::Sym(num) = *name {
+            if let Some(generic) = self.generics.get_mut(&num) {
+                generic.methods.retain(|m| {
+                    m.specializers.len() != method.specializers.len()
+                        || m.specializers.iter()
+                        .zip(method.specializers.iter())
+                        .any(|(a, b)| !maj_eq(a.clone(), b.clone())
+                             .to_bool())
+                });
+                generic.methods.push(method);
+            }
+        }
+    }
+
+    pub fn generic_methods(&self, name: Gc<Maj>) -> Vec<MajMethod> {
+        match *name {
+            Maj::Sym(num) => match self.generics.get(&num) {
+                Some(generic) => generic.methods.clone(),
+                None => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl MajState {
+    pub fn swap_print_objects(
+        &mut self,
+        table: HashMap<usize, (Gc<Maj>, String)>
+    ) -> HashMap<usize, (Gc<Maj>, String)> {
+        std::mem::replace(&mut self.print_objects, table)
+    }
+
+    pub fn set_print_object(&mut self, obj: Gc<Maj>, repr: String) {
+        let key = &*obj as *const Maj as usize;
+        self.print_objects.insert(key, (obj, repr));
+    }
+
+    pub fn print_object(&self, obj: Gc<Maj>) -> Option<String> {
+        let key = &*obj as *const Maj as usize;
+        self.print_objects.get(&key).map(|(_, repr)| repr.clone())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MajCoroutineStatus {
+    Suspended,
+    Running,
+}
+
+pub struct MajCoroutine {
+    pub function: Gc<Maj>,
+    pub status:   MajCoroutineStatus,
+    pub started:  bool,
+    pub frames:   Vec<MajCoroutineFrame>,
+}
+
+impl MajState {
+    pub fn add_coroutine(&mut self, coroutine: Gc<Maj>, function: Gc<Maj>) {
+        self.coroutines.insert(&coroutine, MajCoroutine {
+            function,
+            status:  MajCoroutineStatus::Suspended,
+            started: false,
+            frames:  Vec::new(),
+        });
+    }
+
+    pub fn coroutine_status(
+        &self,
+        coroutine: Gc<Maj>
+    ) -> Option<MajCoroutineStatus> {
+        self.coroutines.get(&coroutine).map(|c| c.status)
+    }
+
+    pub fn coroutine_mut(
+        &mut self,
+        coroutine: Gc<Maj>
+    ) -> Option<&mut MajCoroutine> {
+        self.coroutines.get_mut(&coroutine)
+    }
+
+    pub fn remove_coroutine(&mut self, coroutine: Gc<Maj>) {
+        self.coroutines.remove(&coroutine);
+    }
+
+    pub fn live_coroutines(&self) -> usize {
+        self.coroutines.live()
+    }
+}
+
+impl MajState {
+    pub fn set_dispatch_macro(&mut self, c: char, function: Gc<Maj>) {
+        self.readtable.insert(c, function);
+    }
+
+    pub fn remove_dispatch_macro(&mut self, c: char) {
+        self.readtable.remove(&c);
+    }
+
+    pub fn dispatch_macro(&self, c: char) -> Option<Gc<Maj>> {
+        self.readtable.get(&c).cloned()
+    }
+
+    pub fn dispatch_chars(&self) -> Vec<char> {
+        self.readtable.keys().cloned().collect()
+    }
+}
+
+impl MajState {
+    pub fn swap_read_labels(&mut self, labels: HashMap<usize, Gc<Maj>>)
+                            -> HashMap<usize, Gc<Maj>> {
+        std::mem::replace(&mut self.read_labels, labels)
+    }
+
+    pub fn set_read_label(&mut self, label: usize, obj: Gc<Maj>) {
+        self.read_labels.insert(label, obj);
+    }
+
+    pub fn read_label(&self, label: usize) -> Option<Gc<Maj>> {
+        self.read_labels.get(&label).cloned()
+    }
+}
+
+impl MajState {
+    pub fn push_reader(&mut self, reader: Gc<Maj>, input: MajReaderInput) {
+        self.readers.push((reader, input));
+    }
+
+    pub fn pop_reader(&mut self) -> Option<MajReaderInput> {
+        self.readers.pop().map(|(_, input)| input)
+    }
+
+    pub fn reader_input(&mut self, reader: Gc<Maj>)
+                        -> Option<&mut MajReaderInput> {
+        self.readers.iter_mut()
+            .find(|(r, _)| Gc::ptr_eq(r, &reader))
+            .map(|(_, input)| input)
+    }
+}
+
+impl MajState {
+    pub fn swap_read_objects(&mut self, objects: Vec<Gc<Maj>>)
+                             -> Vec<Gc<Maj>> {
+        std::mem::replace(&mut self.read_objects, objects)
+    }
+
+    pub fn read_object(&self, index: usize) -> Option<Gc<Maj>> {
+        self.read_objects.get(index).cloned()
+    }
+}
+
+impl MajState {
+    pub fn set_indent_rule(&mut self, sym: Gc<Maj>, args: usize) {
+        if let Maj::Sym(num) = *sym {
+            self.indent_rules.insert(num, args);
+        }
+    }
+
+    pub fn remove_indent_rule(&mut self, sym: Gc<Maj>) {
+        if let Maj::Sym(num) = *sym {
+            self.indent_rules.remove(&num);
+        }
+    }
+
+    pub fn indent_rule(&self, sym: Gc<Maj>) -> Option<usize> {
+        match *sym {
+            Maj::Sym(num) => self.indent_rules.get(&num).cloned(),
+            _ => None,
+        }
+    }
+}
+
 use super::environment::{
     maj_env_push,
     maj_env_lookup,
@@ -326,8 +914,17 @@ impl MajState {
 }
 
 impl MajState {
+    fn binding_env(&self, lexenv: Gc<Maj>, sym: Gc<Maj>) -> Gc<Maj> {
+        if self.specialp(sym) {
+            self.dynamic_env.clone()
+        } else {
+            lexenv
+        }
+    }
+
     pub fn assoc(&self, lexenv: Gc<Maj>, sym: Gc<Maj>) -> Gc<Maj> {
         use crate::axioms::predicates::maj_errorp;
+        let lexenv = self.binding_env(lexenv, sym.clone());
         let result = maj_env_assoc(lexenv, sym.clone());
         if maj_errorp(result.clone()).to_bool() {
             maj_env_assoc(self.global_env.clone(), sym)
@@ -338,6 +935,7 @@ impl MajState {
 
     pub fn lookup(&self, lexenv: Gc<Maj>, sym: Gc<Maj>) -> Gc<Maj> {
         use crate::axioms::predicates::maj_errorp;
+        let lexenv = self.binding_env(lexenv, sym.clone());
         let result = maj_env_lookup(lexenv, sym.clone());
         if maj_errorp(result.clone()).to_bool() {
             maj_env_lookup(self.global_env.clone(), sym)
@@ -1,7 +1,7 @@
 use gc::{Finalize, Gc, GcCell, Trace};
 use super::MajState;
 
-#[derive(Debug, Trace, Finalize, Clone)]
+#[derive(Debug, Trace, Clone)]
 pub enum Maj {
     Sym(u64),
     Cons {
@@ -14,6 +14,12 @@ pub enum Maj {
     Vector(MajVector)
 }
 
+impl Finalize for Maj {
+    fn finalize(&self) {
+        super::state::maj_weak_release(self);
+    }
+}
+
 impl Maj {
     pub fn symbol(state: &mut MajState, str: &str) -> Gc<Maj> {
         Gc::new(Maj::Sym(state.gen_symbol(str)))
@@ -484,4 +490,72 @@ impl Maj {
     pub fn vector_sym() -> Gc<Maj> {
         sym_from_raw(MajRawSym::Vector)
     }
+
+    pub fn condition() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::Condition)
+    }
+
+    pub fn unbound_variable() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::UnboundVar)
+    }
+
+    pub fn arity_error() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::ArityError)
+    }
+
+    pub fn type_error() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::TypeError)
+    }
+
+    pub fn io_error() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::IoError)
+    }
+
+    pub fn match_error() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::MatchError)
+    }
+
+    pub fn syntax_rules() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::SyntaxRules)
+    }
+
+    pub fn record() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::Record)
+    }
+
+    pub fn generic() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::Generic)
+    }
+
+    pub fn next_method() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::NextMethod)
+    }
+
+    pub fn coroutine() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::Coroutine)
+    }
+
+    pub fn global() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::Global)
+    }
+
+    pub fn eof() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::Eof)
+    }
+
+    pub fn reader() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::Reader)
+    }
+
+    pub fn continuation() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::Continuation)
+    }
+
+    pub fn optional() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::Optional)
+    }
+
+    pub fn key() -> Gc<Maj> {
+        sym_from_raw(MajRawSym::Key)
+    }
 }
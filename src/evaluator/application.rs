@@ -29,7 +29,7 @@ pub fn maj_apply(
 
     // closure
     else if maj_closurep(fun.clone()).to_bool() {
-        maj_apply_closure(&mut state, fun, args, env)
+        maj_apply_closure(&mut state, fun, args)
     }
 
     // macro
@@ -48,21 +48,42 @@ pub fn maj_apply(
         }
     }
 
+    // continuation
+    else if maj_continuationp(fun.clone()).to_bool() {
+        maj_apply_continuation(&mut state, fun, args)
+    }
+
+    // generic functions
+    else if maj_genericp(fun.clone()).to_bool() {
+        maj_apply_generic(&mut state, fun, args)
+    }
+    else if maj_next_method_p(fun.clone()).to_bool() {
+        maj_apply_next_method(&mut state, fun, args)
+    }
+
     // otherwise, fail
     else {
-        maj_err(
+        maj_type_err(
             Maj::string("Cannot apply {} to args {}"),
             maj_list!(fun, args))
     }
 }
 
 fn maj_apply_closure(
+    state: &mut MajState,
+    fun: Gc<Maj>,
+    args: Gc<Maj>
+) -> Gc<Maj> {
+    maj_apply_closure_with(state, fun, args, maj_eval_closure_body)
+}
+
+pub fn maj_apply_closure_with<F>(
     mut state: &mut MajState,
     fun: Gc<Maj>,
     args: Gc<Maj>,
-    lexenv: Gc<Maj>
-) -> Gc<Maj> {
-    use crate::core::environment::maj_env_union;
+    enter: F
+) -> Gc<Maj>
+where F: FnOnce(&mut MajState, Gc<Maj>, Gc<Maj>, Gc<Maj>) -> Gc<Maj> {
     let length = maj_length(fun.clone()).to_integer().unwrap();
     if length != 5 {
         return maj_err(
@@ -76,9 +97,14 @@ fn maj_apply_closure(
     let body =
         maj_car(maj_cdr(maj_cdr(maj_cdr(maj_cdr(fun.clone())))));
 
+    if maj_lambda_list_options_p(lambda_list.clone()) {
+        return maj_apply_closure_options(&mut state, env, lambda_list,
+                                         body, args, enter);
+    }
+
     if maj_nilp(args.clone()).to_bool() &&
         !maj_nilp(lambda_list.clone()).to_bool() {
-            return maj_err(
+            return maj_arity_err(
                 Maj::string(
                     "Cannot curry function without arguments"),
                 Maj::nil());
@@ -94,9 +120,9 @@ fn maj_apply_closure(
                     .to_integer()
                     .unwrap();
                 if args_len > ll_len {
-                    return maj_err(Maj::string(
+                    return maj_arity_err(Maj::string(
                         "Too many arguments in function call"),
-                                   Maj::nil());
+                                         Maj::nil());
                 }
             },
             // Dotted list
@@ -109,18 +135,9 @@ fn maj_apply_closure(
     if maj_errorp(extenv.clone()).to_bool() {
         env
     } else if !maj_nilp(uargs.clone()).to_bool() {
-        maj_list!(Maj::lit(),
-                  Maj::closure(),
-                  extenv,
-                  uargs,
-                  body)
+        maj_curry(&state, env, extenv, uargs, body)
     } else {
-        // Implicit `do`
-        let body = Maj::cons(Maj::do_sym(), body);
-        // Unite extended environment with lexical environment
-        // (on evaluation only)
-        let extenv = maj_env_union(extenv, lexenv);
-        maj_eval(&mut state, body, extenv)
+        enter(&mut state, body, extenv, env)
     }
 }
 
@@ -137,7 +154,7 @@ fn maj_bind(
         if !maj_nilp(args.clone()).to_bool() {
             return
                 (Maj::nil(),
-                 maj_err(
+                 maj_arity_err(
                      Maj::string("Arguments {} exceeded lambda-list"),
                      maj_list!(args.clone())));
         }
@@ -205,9 +222,9 @@ fn maj_bind(
         },
         // X. Otherwise, error. Only cons and symbols allowed.
         _ => (Maj::nil(),
-              maj_err(
+              maj_type_err(
                    Maj::string(
-                      "Lambda list can only have symbols or conses"),
+                           "Lambda list can only have symbols or conses"),
                   Maj::nil()))
     }
 }
@@ -245,7 +262,7 @@ fn maj_destructuring_bind(list: Gc<Maj>,
 
     // When args is a non-nil symbol, fail immediately!
     if !maj_consp(args.clone()).to_bool() {
-        return maj_err(
+        return maj_type_err(
             Maj::string("Cannot destructure atomic value {}"),
             maj_list!(maj_car(args.clone())));
     }
@@ -291,6 +308,1247 @@ fn maj_bind_rec_nil(lambda_list: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
     }
 }
 
+fn maj_lambda_list_options_p(lambda_list: Gc<Maj>) -> bool {
+    let mut iter = lambda_list;
+    while let Maj::Cons { car, cdr } = &*iter.clone() {
+        if maj_lambda_list_keyword_p(car.clone()) {
+            return true;
+        }
+        iter = cdr.clone();
+    }
+    false
+}
+
+pub fn maj_lambda_list_keyword_p(x: Gc<Maj>) -> bool {
+    maj_eq(x.clone(), Maj::optional()).to_bool()
+        || maj_eq(x, Maj::key()).to_bool()
+}
+
+fn maj_option_spec(spec: Gc<Maj>) -> Option<(Gc<Maj>, Option<Gc<Maj>>)> {
+    match &*spec.clone() {
+        Maj::Sym(_) => {
+            if maj_nilp(spec.clone()).to_bool()
+                || maj_lambda_list_keyword_p(spec.clone()) {
+                    None
+                } else {
+                    Some((spec, None))
+                }
+        },
+        Maj::Cons { car, cdr } => {
+            let is_pair =
+                maj_symbolp(car.clone()).to_bool()
+                && !maj_nilp(car.clone()).to_bool()
+                && maj_consp(cdr.clone()).to_bool()
+                && maj_nilp(maj_cdr(cdr.clone())).to_bool();
+            if is_pair {
+                Some((car.clone(), Some(maj_car(cdr.clone()))))
+            } else {
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+fn maj_option_default(
+    mut state: &mut MajState,
+    default: Option<Gc<Maj>>,
+    extenv: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    match default {
+        Some(expr) => maj_eval_closure_body(&mut state,
+                                            maj_list!(expr),
+                                            extenv,
+                                            env),
+        None => Maj::nil(),
+    }
+}
+
+fn maj_apply_closure_options<F>(
+    mut state: &mut MajState,
+    env: Gc<Maj>,
+    lambda_list: Gc<Maj>,
+    body: Gc<Maj>,
+    args: Gc<Maj>,
+    enter: F
+) -> Gc<Maj>
+where F: FnOnce(&mut MajState, Gc<Maj>, Gc<Maj>, Gc<Maj>) -> Gc<Maj> {
+    use crate::core::environment::maj_env_push;
+
+    let invalid = |ll: Gc<Maj>| maj_err(
+        Maj::string("Invalid lambda list {}"),
+        maj_list!(ll));
+    let curry = |state: &MajState,
+                 extenv: Gc<Maj>,
+                 ll: Gc<Maj>,
+                 body: Gc<Maj>| maj_curry(state, env.clone(), extenv, ll, body);
+
+    let mut values = vec![];
+    let mut iter = args;
+    while !maj_nilp(iter.clone()).to_bool() {
+        values.push(maj_car(iter.clone()));
+        iter = maj_cdr(iter);
+    }
+
+    // A trailing & forces partial application
+    let force = match values.last() {
+        Some(last) => maj_eq(last.clone(), Maj::ampersand()).to_bool(),
+        None => false,
+    };
+    if force {
+        values.pop();
+    }
+
+    let mut next = 0;
+    let mut extenv = env.clone();
+    let mut ll = lambda_list.clone();
+
+    // 1. Required parameters, with currying
+    while maj_consp(ll.clone()).to_bool()
+        && !maj_lambda_list_keyword_p(maj_car(ll.clone())) {
+            if next == values.len() {
+                if values.is_empty() && !force {
+                    return maj_arity_err(
+                        Maj::string(
+                            "Cannot curry function without arguments"),
+                        Maj::nil());
+                }
+                return curry(&state, extenv, ll, body);
+            }
+            let param = maj_car(ll.clone());
+            extenv = if maj_consp(param.clone()).to_bool() {
+                maj_destructuring_bind(param, values[next].clone(), extenv)
+            } else {
+                maj_env_push(extenv, param, values[next].clone())
+            };
+            if maj_errorp(extenv.clone()).to_bool() {
+                return extenv;
+            }
+            next += 1;
+            ll = maj_cdr(ll);
+        }
+
+    // 2. Optional parameters
+    if maj_eq(maj_car(ll.clone()), Maj::optional()).to_bool() {
+        ll = maj_cdr(ll);
+        while maj_consp(ll.clone()).to_bool()
+            && !maj_lambda_list_keyword_p(maj_car(ll.clone())) {
+                let (sym, default) = match maj_option_spec(maj_car(ll.clone())) {
+                    Some(spec) => spec,
+                    None => return invalid(lambda_list),
+                };
+                let value = if next < values.len() {
+                    next += 1;
+                    values[next - 1].clone()
+                } else if force {
+                    return curry(&state,
+                                 extenv,
+                                 Maj::cons(Maj::optional(), ll),
+                                 body);
+                } else {
+                    let value = maj_option_default(&mut state, default,
+                                                   extenv.clone(),
+                                                   env.clone());
+                    if maj_errorp(value.clone()).to_bool() {
+                        return value;
+                    }
+                    value
+                };
+                extenv = maj_env_push(extenv, sym, value);
+                ll = maj_cdr(ll);
+            }
+    }
+
+    let leftover = values[next..].to_vec();
+
+    // 3. Forced currying stops before keyword and rest parameters
+    if force {
+        if !leftover.is_empty() {
+            return maj_arity_err(
+                Maj::string("Too many arguments in function call"),
+                Maj::nil());
+        }
+        return curry(&state, extenv, ll, body);
+    }
+
+    let mut rest = Maj::nil();
+    for value in leftover.iter().rev() {
+        rest = Maj::cons(value.clone(), rest);
+    }
+
+    // 4. Keyword parameters
+    if maj_eq(maj_car(ll.clone()), Maj::key()).to_bool() {
+        ll = maj_cdr(ll);
+        let mut specs = vec![];
+        while maj_consp(ll.clone()).to_bool() {
+            match maj_option_spec(maj_car(ll.clone())) {
+                Some((sym, default)) => {
+                    let name = format!(":{}", sym.symbol_name(&state));
+                    let key = Maj::symbol(&mut state, &name);
+                    specs.push((sym, key, default));
+                },
+                None => return invalid(lambda_list),
+            }
+            ll = maj_cdr(ll);
+        }
+
+        if leftover.len() % 2 != 0 {
+            return maj_arity_err(
+                Maj::string("Odd number of keyword arguments {}"),
+                maj_list!(rest));
+        }
+
+        if maj_nilp(ll.clone()).to_bool() {
+            for pair in leftover.chunks(2) {
+                let known = specs.iter()
+                    .any(|(_, key, _)| maj_eq(key.clone(), pair[0].clone())
+                         .to_bool());
+                if !known {
+                    return maj_arity_err(
+                        Maj::string("Unknown keyword argument {}"),
+                        maj_list!(pair[0].clone()));
+                }
+            }
+        }
+
+        for (sym, key, default) in specs {
+            let supplied = leftover.chunks(2)
+                .find(|pair| maj_eq(pair[0].clone(), key.clone()).to_bool())
+                .map(|pair| pair[1].clone());
+            let value = match supplied {
+                Some(value) => value,
+                None => {
+                    let value = maj_option_default(&mut state, default,
+                                                   extenv.clone(),
+                                                   env.clone());
+                    if maj_errorp(value.clone()).to_bool() {
+                        return value;
+                    }
+                    value
+                },
+            };
+            extenv = maj_env_push(extenv, sym, value);
+        }
+    } else if maj_consp(ll.clone()).to_bool() {
+        return invalid(lambda_list);
+    } else if maj_nilp(ll.clone()).to_bool() && !leftover.is_empty() {
+        return maj_arity_err(
+            Maj::string("Too many arguments in function call"),
+            Maj::nil());
+    }
+
+    // 5. Rest parameter
+    if !maj_nilp(ll.clone()).to_bool() {
+        if !maj_symbolp(ll.clone()).to_bool() {
+            return invalid(lambda_list);
+        }
+        extenv = maj_env_push(extenv, ll, rest);
+    }
+
+    enter(&mut state, body, extenv, env)
+}
+
+fn maj_split_bindings(
+    state: &MajState,
+    extenv: Gc<Maj>,
+    env: Gc<Maj>
+) -> (Vec<Gc<Maj>>, Vec<Gc<Maj>>) {
+    // Only bindings made on top of `env` belong to this call
+    let mut lexical = vec![];
+    let mut special = vec![];
+    let mut iter = extenv;
+    while maj_consp(iter.clone()).to_bool() && !Gc::ptr_eq(&iter, &env) {
+        let binding = maj_car(iter.clone());
+        if state.specialp(maj_car(binding.clone())) {
+            special.push(binding);
+        } else {
+            lexical.push(binding);
+        }
+        iter = maj_cdr(iter);
+    }
+    (lexical, special)
+}
+
+fn maj_curry(
+    state: &MajState,
+    env: Gc<Maj>,
+    extenv: Gc<Maj>,
+    lambda_list: Gc<Maj>,
+    body: Gc<Maj>
+) -> Gc<Maj> {
+    let (lexical, special) = maj_split_bindings(state, extenv.clone(),
+                                                env.clone());
+    if special.is_empty() {
+        return maj_list!(Maj::lit(), Maj::closure(),
+                         extenv, lambda_list, body);
+    }
+
+    // Special parameters which were already bound are not captured;
+    // the body binds them again, so that they are rebound dynamically
+    // once the remaining arguments are given
+    let mut lexenv = env;
+    for binding in lexical.iter().rev() {
+        lexenv = Maj::cons(binding.clone(), lexenv);
+    }
+    let mut params = Maj::nil();
+    let mut values = Maj::nil();
+    for binding in special.iter() {
+        params = Maj::cons(maj_car(binding.clone()), params);
+        values = Maj::cons(maj_list!(Maj::quote(), maj_cdr(binding.clone())),
+                           values);
+    }
+    let inner = Maj::cons(Maj::fn_sym(), Maj::cons(params, body));
+    maj_list!(Maj::lit(), Maj::closure(),
+              lexenv, lambda_list,
+              maj_list!(Maj::cons(inner, values)))
+}
+
+pub fn maj_eval_closure_body(
+    mut state: &mut MajState,
+    body: Gc<Maj>,
+    extenv: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::core::environment::maj_env_push;
+
+    let (lexical, special) = maj_split_bindings(&state, extenv.clone(),
+                                                env.clone());
+
+    // Implicit `do`
+    let body = Maj::cons(Maj::do_sym(), body);
+
+    if special.is_empty() {
+        return maj_eval(&mut state, body, extenv);
+    }
+
+    let mut lexenv = env;
+    for binding in lexical.iter().rev() {
+        lexenv = Maj::cons(binding.clone(), lexenv);
+    }
+
+    let saved = state.get_dynamic_env();
+    let mut dynenv = saved.clone();
+    for binding in special.iter().rev() {
+        dynenv = maj_env_push(dynenv,
+                              maj_car(binding.clone()),
+                              maj_cdr(binding.clone()));
+    }
+
+    state.set_dynamic_env(dynenv);
+    let result = maj_eval(&mut state, body, lexenv);
+    state.set_dynamic_env(saved);
+    result
+}
+
+pub fn maj_match_pattern(
+    mut state: &mut MajState,
+    pattern: Gc<Maj>,
+    value: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<Option<Gc<Maj>>, Gc<Maj>> {
+    use crate::core::environment::maj_env_push;
+    use super::evaluation::maj_quotep;
+
+    // Wildcard and variables
+    if maj_symbolp(pattern.clone()).to_bool()
+        && !maj_nilp(pattern.clone()).to_bool()
+        && !maj_eq(pattern.clone(), Maj::t()).to_bool()
+        && !maj_keywordp(&state, pattern.clone()).to_bool() {
+            if maj_eq(pattern.clone(), Maj::symbol(&mut state, "_")).to_bool() {
+                return Ok(Some(env));
+            }
+            return Ok(Some(maj_env_push(env, pattern, value)));
+        }
+
+    // Literal atoms
+    if !maj_consp(pattern.clone()).to_bool() {
+        return maj_match_literal(&mut state, pattern, value, env);
+    }
+
+    let head = maj_car(pattern.clone());
+
+    // Quoted data
+    if maj_quotep(pattern.clone()).to_bool() {
+        let datum = maj_car(maj_cdr(pattern));
+        return maj_match_literal(&mut state, datum, value, env);
+    }
+
+    // Predicates
+    if maj_eq(head.clone(), Maj::symbol(&mut state, "?")).to_bool() {
+        let length = maj_length(pattern.clone()).to_integer();
+        if length != Some(2) && length != Some(3) {
+            return Err(maj_err(
+                Maj::string("Invalid predicate pattern {}"),
+                maj_list!(pattern)));
+        }
+        let pred = maj_eval(&mut state,
+                            maj_car(maj_cdr(pattern.clone())),
+                            env.clone());
+        if maj_errorp(pred.clone()).to_bool() {
+            return Err(pred);
+        }
+        let result = maj_apply(&mut state, pred,
+                               maj_list!(value.clone()),
+                               env.clone());
+        if maj_errorp(result.clone()).to_bool() {
+            return Err(result);
+        }
+        if maj_nilp(result).to_bool() {
+            return Ok(None);
+        }
+        return if length == Some(3) {
+            let subpattern = maj_car(maj_cdr(maj_cdr(pattern)));
+            maj_match_pattern(&mut state, subpattern, value, env)
+        } else {
+            Ok(Some(env))
+        };
+    }
+
+    // Vectors, read as (vector p1 ... pn)
+    if maj_eq(head, Maj::symbol(&mut state, "vector")).to_bool() {
+        if !maj_vectorp(value.clone()).to_bool()
+            || maj_stringp(value.clone()).to_bool() {
+                return Ok(None);
+            }
+        let size = maj_vec_length(value.clone()).to_integer().unwrap();
+        let mut subpatterns = maj_cdr(pattern);
+        let mut env = env;
+        for i in 0..size {
+            if !maj_consp(subpatterns.clone()).to_bool() {
+                return Ok(None);
+            }
+            let element = maj_vec_at(Maj::integer(i), value.clone());
+            match maj_match_pattern(&mut state,
+                                    maj_car(subpatterns.clone()),
+                                    element, env)? {
+                Some(newenv) => env = newenv,
+                None => return Ok(None),
+            }
+            subpatterns = maj_cdr(subpatterns);
+        }
+        return if maj_nilp(subpatterns).to_bool() {
+            Ok(Some(env))
+        } else {
+            Ok(None)
+        };
+    }
+
+    // Lists
+    maj_match_list(&mut state, pattern, value, env)
+}
+
+fn maj_match_list(
+    mut state: &mut MajState,
+    pattern: Gc<Maj>,
+    value: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<Option<Gc<Maj>>, Gc<Maj>> {
+    if maj_plain_pattern_p(&mut state, pattern.clone()) {
+        return if maj_pattern_fits(pattern.clone(), value.clone()) {
+            Ok(Some(maj_destructuring_bind(pattern, value, env)))
+        } else {
+            Ok(None)
+        };
+    }
+
+    match &*pattern.clone() {
+        Maj::Cons { car, cdr } => {
+            if !maj_consp(value.clone()).to_bool() {
+                return Ok(None);
+            }
+            match maj_match_pattern(&mut state, car.clone(),
+                                    maj_car(value.clone()), env)? {
+                Some(env) => maj_match_list(&mut state, cdr.clone(),
+                                            maj_cdr(value), env),
+                None => Ok(None),
+            }
+        },
+        // Rest of the list, or an atom ending a dotted pattern
+        _ => maj_match_pattern(&mut state, pattern, value, env),
+    }
+}
+
+fn maj_plain_pattern_p(mut state: &mut MajState, pattern: Gc<Maj>) -> bool {
+    let wildcard = Maj::symbol(&mut state, "_");
+    let mut iter = pattern;
+    while let Maj::Cons { car, cdr } = &*iter.clone() {
+        let plain =
+            if maj_consp(car.clone()).to_bool() {
+                let head = maj_car(car.clone());
+                !maj_eq(head.clone(), Maj::quote()).to_bool()
+                    && !maj_eq(head.clone(), Maj::symbol(&mut state, "?")).to_bool()
+                    && !maj_eq(head, Maj::symbol(&mut state, "vector")).to_bool()
+                    && maj_plain_pattern_p(&mut state, car.clone())
+            } else {
+                maj_symbolp(car.clone()).to_bool()
+                    && !maj_nilp(car.clone()).to_bool()
+                    && !maj_eq(car.clone(), Maj::t()).to_bool()
+                    && !maj_eq(car.clone(), wildcard.clone()).to_bool()
+                    && !maj_keywordp(&state, car.clone()).to_bool()
+            };
+        if !plain {
+            return false;
+        }
+        iter = cdr.clone();
+    }
+    maj_nilp(iter.clone()).to_bool()
+        || (maj_symbolp(iter.clone()).to_bool()
+            && !maj_eq(iter.clone(), Maj::t()).to_bool()
+            && !maj_eq(iter.clone(), wildcard).to_bool()
+            && !maj_keywordp(&state, iter).to_bool())
+}
+
+fn maj_pattern_fits(pattern: Gc<Maj>, value: Gc<Maj>) -> bool {
+    match &*pattern {
+        Maj::Cons { car, cdr } => {
+            maj_consp(value.clone()).to_bool()
+                && maj_pattern_fits(car.clone(), maj_car(value.clone()))
+                && maj_pattern_fits(cdr.clone(), maj_cdr(value))
+        },
+        _ => {
+            !maj_nilp(pattern).to_bool()
+                || maj_nilp(value).to_bool()
+        },
+    }
+}
+
+fn maj_match_literal(
+    mut state: &mut MajState,
+    literal: Gc<Maj>,
+    value: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<Option<Gc<Maj>>, Gc<Maj>> {
+    let equal = Maj::symbol(&mut state, "equal");
+    let equal = state.lookup(Maj::nil(), equal);
+    if maj_errorp(equal.clone()).to_bool() {
+        return Err(equal);
+    }
+    let result = maj_apply(&mut state, equal,
+                           maj_list!(literal, value),
+                           env.clone());
+    if maj_errorp(result.clone()).to_bool() {
+        Err(result)
+    } else if maj_nilp(result).to_bool() {
+        Ok(None)
+    } else {
+        Ok(Some(env))
+    }
+}
+
+#[derive(Clone)]
+enum MajSyntaxBinding {
+    One(Gc<Maj>),
+    Many(Vec<MajSyntaxBinding>),
+}
+
+type MajSyntaxBindings = std::collections::HashMap<u64, MajSyntaxBinding>;
+type MajSyntaxRenames  = std::collections::HashMap<u64, Gc<Maj>>;
+
+pub fn maj_syntax_transformer_p(x: Gc<Maj>) -> bool {
+    maj_literalp(x.clone()).to_bool()
+        && maj_eq(maj_car(maj_cdr(x)), Maj::syntax_rules()).to_bool()
+}
+
+fn maj_expand_syntax_rules(
+    mut state: &mut MajState,
+    transformer: Gc<Maj>,
+    args: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    // (lit syntax-rules <literals> <rules>)
+    let literals = maj_car(maj_cdr(maj_cdr(transformer.clone())));
+    let mut rules = maj_car(maj_cdr(maj_cdr(maj_cdr(transformer))));
+    while !maj_nilp(rules.clone()).to_bool() {
+        let rule = maj_car(rules.clone());
+        let pattern = maj_cdr(maj_car(rule.clone()));
+        let template = maj_car(maj_cdr(rule));
+        let mut bindings = MajSyntaxBindings::new();
+        if maj_syntax_match(&mut state, pattern, args.clone(),
+                            literals.clone(), &mut bindings) {
+            let mut renames = MajSyntaxRenames::new();
+            let expansion = match maj_syntax_instantiate(
+                &mut state, template, &bindings, &mut renames) {
+                Ok(expansion) => expansion,
+                Err(error) => return error,
+            };
+            // Map each new symbol back to the original one
+            let originals: MajSyntaxRenames = renames
+                .into_iter()
+                .filter_map(|(original, renamed)| match *renamed {
+                    Maj::Sym(num) => Some((num, Gc::new(Maj::Sym(original)))),
+                    _ => None,
+                })
+                .collect();
+            return match maj_syntax_resolve(&mut state, expansion,
+                                            &originals, Maj::nil(), env) {
+                Ok(expansion) => expansion,
+                Err(error) => error,
+            };
+        }
+        rules = maj_cdr(rules);
+    }
+    maj_err(Maj::string("No syntax rule matches arguments {}"),
+            maj_list!(args))
+}
+
+fn maj_syntax_match(
+    mut state: &mut MajState,
+    pattern: Gc<Maj>,
+    form: Gc<Maj>,
+    literals: Gc<Maj>,
+    bindings: &mut MajSyntaxBindings
+) -> bool {
+    let ellipsis = Maj::symbol(&mut state, "...");
+    match &*pattern.clone() {
+        Maj::Sym(num) if maj_syntax_variable_p(&mut state, pattern.clone(),
+                                                literals.clone()) => {
+            bindings.insert(*num, MajSyntaxBinding::One(form));
+            true
+        },
+        Maj::Sym(_) => {
+            // Wildcard, literals, nil and t
+            maj_eq(pattern.clone(), Maj::symbol(&mut state, "_")).to_bool()
+                || maj_eq(pattern, form).to_bool()
+        },
+        Maj::Cons { car, cdr } => {
+            if maj_consp(cdr.clone()).to_bool()
+                && maj_eq(maj_car(cdr.clone()), ellipsis).to_bool()
+            {
+                let tail = maj_cdr(cdr.clone());
+                maj_syntax_match_ellipsis(&mut state, car.clone(), tail,
+                                          form, literals, bindings)
+            } else {
+                maj_consp(form.clone()).to_bool()
+                    && maj_syntax_match(&mut state, car.clone(),
+                                        maj_car(form.clone()),
+                                        literals.clone(), bindings)
+                    && maj_syntax_match(&mut state, cdr.clone(),
+                                        maj_cdr(form), literals, bindings)
+            }
+        },
+        _ => matches!(maj_match_literal(&mut state, pattern, form, Maj::nil()),
+                      Ok(Some(_))),
+    }
+}
+
+fn maj_syntax_match_ellipsis(
+    mut state: &mut MajState,
+    subpattern: Gc<Maj>,
+    tail: Gc<Maj>,
+    form: Gc<Maj>,
+    literals: Gc<Maj>,
+    bindings: &mut MajSyntaxBindings
+) -> bool {
+    let mut minimum = 0;
+    let mut iter = tail.clone();
+    while maj_consp(iter.clone()).to_bool() {
+        minimum += 1;
+        iter = maj_cdr(iter);
+    }
+
+    let mut forms = vec![];
+    let mut iter = form;
+    while maj_consp(iter.clone()).to_bool() {
+        forms.push(maj_car(iter.clone()));
+        iter = maj_cdr(iter);
+    }
+    if forms.len() < minimum {
+        return false;
+    }
+
+    let count = forms.len() - minimum;
+    let mut matches = vec![];
+    for form in forms.iter().take(count) {
+        let mut submatch = MajSyntaxBindings::new();
+        if !maj_syntax_match(&mut state, subpattern.clone(), form.clone(),
+                             literals.clone(), &mut submatch) {
+            return false;
+        }
+        matches.push(submatch);
+    }
+
+    let mut vars = vec![];
+    maj_syntax_pattern_vars(&mut state, subpattern, literals.clone(), &mut vars);
+    for var in vars {
+        let items = matches
+            .iter()
+            .map(|submatch| submatch.get(&var).cloned()
+                 .unwrap_or(MajSyntaxBinding::Many(vec![])))
+            .collect();
+        bindings.insert(var, MajSyntaxBinding::Many(items));
+    }
+
+    // Remaining forms, keeping the end of a dotted form
+    let rest = forms.into_iter().skip(count).rev()
+        .fold(iter, |acc, form| Maj::cons(form, acc));
+    maj_syntax_match(&mut state, tail, rest, literals, bindings)
+}
+
+fn maj_syntax_variable_p(
+    mut state: &mut MajState,
+    sym: Gc<Maj>,
+    literals: Gc<Maj>
+) -> bool {
+    use super::evaluation::maj_shadowedp;
+    maj_symbolp(sym.clone()).to_bool()
+        && !maj_nilp(sym.clone()).to_bool()
+        && !maj_eq(sym.clone(), Maj::t()).to_bool()
+        && !maj_eq(sym.clone(), Maj::symbol(&mut state, "_")).to_bool()
+        && !maj_eq(sym.clone(), Maj::symbol(&mut state, "...")).to_bool()
+        && !maj_shadowedp(sym, literals)
+}
+
+fn maj_syntax_pattern_vars(
+    mut state: &mut MajState,
+    pattern: Gc<Maj>,
+    literals: Gc<Maj>,
+    vars: &mut Vec<u64>
+) {
+    match &*pattern.clone() {
+        Maj::Sym(num) => {
+            if maj_syntax_variable_p(&mut state, pattern, literals) {
+                vars.push(*num);
+            }
+        },
+        Maj::Cons { car, cdr } => {
+            maj_syntax_pattern_vars(&mut state, car.clone(),
+                                    literals.clone(), vars);
+            maj_syntax_pattern_vars(&mut state, cdr.clone(),
+                                    literals, vars);
+        },
+        _ => {},
+    }
+}
+
+fn maj_syntax_instantiate(
+    mut state: &mut MajState,
+    template: Gc<Maj>,
+    bindings: &MajSyntaxBindings,
+    renames: &mut MajSyntaxRenames
+) -> Result<Gc<Maj>, Gc<Maj>> {
+    let ellipsis = Maj::symbol(&mut state, "...");
+    match &*template.clone() {
+        Maj::Sym(num) => match bindings.get(num) {
+            Some(MajSyntaxBinding::One(form)) => Ok(form.clone()),
+            Some(MajSyntaxBinding::Many(_)) => Err(maj_err(
+                Maj::string("Pattern variable {} used without ellipsis"),
+                maj_list!(template))),
+            None => Ok(maj_syntax_rename(&mut state, template, renames)),
+        },
+        Maj::Cons { car, cdr } => {
+            if maj_consp(cdr.clone()).to_bool()
+                && maj_eq(maj_car(cdr.clone()), ellipsis).to_bool()
+            {
+                let forms = maj_syntax_instantiate_ellipsis(
+                    &mut state, car.clone(), bindings, renames)?;
+                let tail = maj_syntax_instantiate(
+                    &mut state, maj_cdr(cdr.clone()), bindings, renames)?;
+                Ok(forms.into_iter().rev()
+                   .fold(tail, |acc, form| Maj::cons(form, acc)))
+            } else {
+                let car = maj_syntax_instantiate(
+                    &mut state, car.clone(), bindings, renames)?;
+                let cdr = maj_syntax_instantiate(
+                    &mut state, cdr.clone(), bindings, renames)?;
+                Ok(Maj::cons(car, cdr))
+            }
+        },
+        _ => Ok(template),
+    }
+}
+
+fn maj_syntax_instantiate_ellipsis(
+    mut state: &mut MajState,
+    template: Gc<Maj>,
+    bindings: &MajSyntaxBindings,
+    renames: &mut MajSyntaxRenames
+) -> Result<Vec<Gc<Maj>>, Gc<Maj>> {
+    let mut vars = vec![];
+    maj_syntax_pattern_vars(&mut state, template.clone(),
+                            Maj::nil(), &mut vars);
+    let repeated: Vec<(u64, &Vec<MajSyntaxBinding>)> = vars
+        .into_iter()
+        .filter_map(|var| match bindings.get(&var) {
+            Some(MajSyntaxBinding::Many(items)) => Some((var, items)),
+            _ => None,
+        })
+        .collect();
+
+    if repeated.is_empty() {
+        return Err(maj_err(
+            Maj::string("No pattern variable to repeat in {}"),
+            maj_list!(template)));
+    }
+
+    let count = repeated[0].1.len();
+    if repeated.iter().any(|(_, items)| items.len() != count) {
+        return Err(maj_err(
+            Maj::string("Mismatched ellipsis lengths in {}"),
+            maj_list!(template)));
+    }
+
+    let mut forms = vec![];
+    for i in 0..count {
+        let mut iteration = bindings.clone();
+        for (var, items) in repeated.iter() {
+            iteration.insert(*var, items[i].clone());
+        }
+        forms.push(maj_syntax_instantiate(&mut state, template.clone(),
+                                          &iteration, renames)?);
+    }
+    Ok(forms)
+}
+
+fn maj_syntax_rename(
+    mut state: &mut MajState,
+    sym: Gc<Maj>,
+    renames: &mut MajSyntaxRenames
+) -> Gc<Maj> {
+    let fixed = ["&", "&optional", "&key", "_", "..."];
+    let name = sym.symbol_name(&state);
+    if maj_nilp(sym.clone()).to_bool()
+        || maj_eq(sym.clone(), Maj::t()).to_bool()
+        || maj_keywordp(&state, sym.clone()).to_bool()
+        || fixed.contains(&name.as_ref())
+    {
+        return sym;
+    }
+    match *sym {
+        Maj::Sym(num) => renames
+            .entry(num)
+            .or_insert_with(|| Maj::gensym(&mut state))
+            .clone(),
+        _ => sym,
+    }
+}
+
+fn maj_syntax_resolve(
+    mut state: &mut MajState,
+    form: Gc<Maj>,
+    originals: &MajSyntaxRenames,
+    bound: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<Gc<Maj>, Gc<Maj>> {
+    use super::evaluation::maj_shadowedp;
+
+    if maj_symbolp(form.clone()).to_bool() {
+        return Ok(match maj_syntax_original(form.clone(), originals) {
+            Some(original) if !maj_shadowedp(form.clone(), bound) =>
+                maj_syntax_reference(&state, original, env),
+            _ => form,
+        });
+    }
+
+    if !maj_consp(form.clone()).to_bool() {
+        return Ok(form);
+    }
+
+    let head = maj_car(form.clone());
+    let args = maj_cdr(form.clone());
+    if !maj_symbolp(head.clone()).to_bool()
+        || maj_shadowedp(head.clone(), bound.clone())
+    {
+        return maj_syntax_resolve_each(&mut state, form, originals, bound, env);
+    }
+
+    // Renamed heads that are not bound refer to the global context
+    let renamed = maj_syntax_original(head.clone(), originals);
+    let name = renamed.clone().unwrap_or(head);
+
+    let resolved_args = match name.symbol_name(&state).as_ref() {
+        "lit" => Some(Ok(args.clone())),
+        "quote" | "syntax-rules" =>
+            Some(Ok(maj_syntax_unrename(args.clone(), originals))),
+        "quasiquote" =>
+            Some(maj_syntax_resolve_quasi(&mut state, args.clone(),
+                                          originals, bound.clone(),
+                                          env.clone())),
+        "if" | "do" | "and" | "or" | "apply" | "while" |
+        "unwind-protect" | "catch" | "set-car" | "set-cdr" |
+        "multiple-value-list" =>
+            Some(maj_syntax_resolve_each(&mut state, args.clone(),
+                                         originals, bound.clone(),
+                                         env.clone())),
+        "def" | "defvar" | "defparameter" | "set" =>
+            Some(maj_syntax_resolve_definition(&mut state, args.clone(),
+                                               originals, bound.clone(),
+                                               env.clone())),
+        "fn" | "mac" =>
+            Some(maj_syntax_resolve_lambda(&mut state, args.clone(),
+                                           originals, bound.clone(),
+                                           env.clone())),
+        "letrec" =>
+            Some(maj_syntax_resolve_letrec(&mut state, args.clone(),
+                                           originals, bound.clone(),
+                                           env.clone())),
+        "handler-case" | "match" | "multiple-value-bind" =>
+            Some(maj_syntax_resolve_binding_form(&mut state, name.clone(),
+                                                 args.clone(), originals,
+                                                 bound.clone(),
+                                                 env.clone())),
+        _ => None,
+    };
+    if let Some(args) = resolved_args {
+        return Ok(Maj::cons(name, args?));
+    }
+
+    let mac = if renamed.is_some() {
+        state.lookup(Maj::nil(), name)
+    } else {
+        state.lookup(env.clone(), name)
+    };
+    if maj_macrop(mac.clone()).to_bool() {
+        let (expansion, worked) = expand_macro(&mut state, mac, args, env.clone());
+        if !worked {
+            return Err(expansion);
+        }
+        return maj_syntax_resolve(&mut state, expansion, originals, bound, env);
+    }
+
+    maj_syntax_resolve_each(&mut state, form, originals, bound, env)
+}
+
+fn maj_syntax_original(sym: Gc<Maj>, originals: &MajSyntaxRenames) -> Option<Gc<Maj>> {
+    match *sym {
+        Maj::Sym(num) => originals.get(&num).cloned(),
+        _ => None,
+    }
+}
+
+fn maj_syntax_unrename(form: Gc<Maj>, originals: &MajSyntaxRenames) -> Gc<Maj> {
+    match &*form {
+        Maj::Sym(_) => maj_syntax_original(form.clone(), originals).unwrap_or(form),
+        Maj::Cons { car, cdr } => Maj::cons(
+            maj_syntax_unrename(car.clone(), originals),
+            maj_syntax_unrename(cdr.clone(), originals)),
+        _ => form,
+    }
+}
+
+fn maj_syntax_reference(
+    state: &MajState,
+    original: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::core::environment::{ maj_env_assoc, maj_env_lookup };
+    if maj_errorp(maj_env_assoc(env, original.clone())).to_bool() {
+        return original;
+    }
+    let global = maj_env_lookup(state.get_global_env(), original.clone());
+    if maj_closurep(global.clone()).to_bool()
+        || maj_primitivep(global.clone()).to_bool()
+        || maj_macrop(global).to_bool()
+    {
+        maj_list!(Maj::lit(), Maj::global(), original)
+    } else {
+        original
+    }
+}
+
+fn maj_syntax_resolve_each(
+    mut state: &mut MajState,
+    list: Gc<Maj>,
+    originals: &MajSyntaxRenames,
+    bound: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<Gc<Maj>, Gc<Maj>> {
+    match &*list {
+        Maj::Cons { car, cdr } => {
+            let car = maj_syntax_resolve(&mut state, car.clone(), originals,
+                                         bound.clone(), env.clone())?;
+            let cdr = maj_syntax_resolve_each(&mut state, cdr.clone(),
+                                              originals, bound, env)?;
+            Ok(Maj::cons(car, cdr))
+        },
+        _ => maj_syntax_resolve(&mut state, list.clone(), originals, bound, env),
+    }
+}
+
+fn maj_syntax_resolve_quasi(
+    mut state: &mut MajState,
+    template: Gc<Maj>,
+    originals: &MajSyntaxRenames,
+    bound: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<Gc<Maj>, Gc<Maj>> {
+    use super::evaluation::{ maj_unquotep, maj_unquote_splice_p };
+    if !maj_consp(template.clone()).to_bool() {
+        return Ok(maj_syntax_unrename(template, originals));
+    }
+    let head = maj_syntax_unrename(maj_car(template.clone()), originals);
+    let unquoted = Maj::cons(head.clone(), maj_cdr(template.clone()));
+    if maj_unquotep(unquoted.clone()).to_bool()
+        || maj_unquote_splice_p(unquoted).to_bool()
+    {
+        let args = maj_syntax_resolve_each(&mut state, maj_cdr(template),
+                                           originals, bound, env)?;
+        return Ok(Maj::cons(head, args));
+    }
+    let car = maj_syntax_resolve_quasi(&mut state, maj_car(template.clone()),
+                                       originals, bound.clone(), env.clone())?;
+    let cdr = maj_syntax_resolve_quasi(&mut state, maj_cdr(template),
+                                       originals, bound, env)?;
+    Ok(Maj::cons(car, cdr))
+}
+
+fn maj_syntax_resolve_definition(
+    mut state: &mut MajState,
+    args: Gc<Maj>,
+    originals: &MajSyntaxRenames,
+    bound: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<Gc<Maj>, Gc<Maj>> {
+    use super::evaluation::maj_shadowedp;
+    if !maj_consp(args.clone()).to_bool() {
+        return Ok(args);
+    }
+    let sym = maj_car(args.clone());
+    let sym = if maj_shadowedp(sym.clone(), bound.clone()) {
+        sym
+    } else {
+        maj_syntax_original(sym.clone(), originals).unwrap_or(sym)
+    };
+    let rest = maj_syntax_resolve_each(&mut state, maj_cdr(args),
+                                       originals, bound, env)?;
+    Ok(Maj::cons(sym, rest))
+}
+
+fn maj_syntax_resolve_lambda(
+    mut state: &mut MajState,
+    args: Gc<Maj>,
+    originals: &MajSyntaxRenames,
+    bound: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<Gc<Maj>, Gc<Maj>> {
+    use super::evaluation::maj_bound_symbols;
+    if !maj_consp(args.clone()).to_bool() {
+        return Ok(args);
+    }
+
+    let mut bound = bound;
+    let mut options = false;
+    let mut params = vec![];
+    let mut iter = maj_car(args.clone());
+    while let Maj::Cons { car, cdr } = &*iter.clone() {
+        let param = car.clone();
+        if maj_eq(param.clone(), Maj::optional()).to_bool()
+            || maj_eq(param.clone(), Maj::key()).to_bool()
+        {
+            options = true;
+            params.push(param);
+        } else if options && maj_consp(param.clone()).to_bool() {
+            // (sym default)
+            let sym = maj_car(param.clone());
+            let default = maj_syntax_resolve_each(
+                &mut state, maj_cdr(param), originals,
+                bound.clone(), env.clone())?;
+            bound = maj_bound_symbols(sym.clone(), bound);
+            params.push(Maj::cons(sym, default));
+        } else {
+            bound = maj_bound_symbols(param.clone(), bound);
+            params.push(param);
+        }
+        iter = cdr.clone();
+    }
+    bound = maj_bound_symbols(iter.clone(), bound);
+    let lambda_list = params.into_iter().rev()
+        .fold(iter, |acc, param| Maj::cons(param, acc));
+
+    let body = maj_syntax_resolve_each(&mut state, maj_cdr(args),
+                                       originals, bound, env)?;
+    Ok(Maj::cons(lambda_list, body))
+}
+
+fn maj_syntax_resolve_letrec(
+    mut state: &mut MajState,
+    args: Gc<Maj>,
+    originals: &MajSyntaxRenames,
+    bound: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<Gc<Maj>, Gc<Maj>> {
+    if !maj_consp(args.clone()).to_bool() {
+        return Ok(args);
+    }
+
+    // (letrec ((sym lambda-list . body) ...) . body)
+    let mut bound = bound;
+    let mut iter = maj_car(args.clone());
+    while maj_consp(iter.clone()).to_bool() {
+        let clause = maj_car(iter.clone());
+        if maj_consp(clause.clone()).to_bool() {
+            bound = Maj::cons(maj_car(clause), bound);
+        }
+        iter = maj_cdr(iter);
+    }
+
+    let mut clauses = vec![];
+    let mut iter = maj_car(args.clone());
+    while maj_consp(iter.clone()).to_bool() {
+        let clause = maj_car(iter.clone());
+        clauses.push(if maj_consp(clause.clone()).to_bool() {
+            let lambda = maj_syntax_resolve_lambda(
+                &mut state, maj_cdr(clause.clone()), originals,
+                bound.clone(), env.clone())?;
+            Maj::cons(maj_car(clause), lambda)
+        } else {
+            clause
+        });
+        iter = maj_cdr(iter);
+    }
+    let bindings = clauses.into_iter().rev()
+        .fold(iter, |acc, clause| Maj::cons(clause, acc));
+
+    let body = maj_syntax_resolve_each(&mut state, maj_cdr(args),
+                                       originals, bound, env)?;
+    Ok(Maj::cons(bindings, body))
+}
+
+fn maj_syntax_resolve_binding_form(
+    mut state: &mut MajState,
+    name: Gc<Maj>,
+    args: Gc<Maj>,
+    originals: &MajSyntaxRenames,
+    bound: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<Gc<Maj>, Gc<Maj>> {
+    use super::evaluation::maj_bound_symbols;
+    let name = name.symbol_name(&state);
+    if name == "multiple-value-bind" {
+        // (vars form . body)
+        if !maj_consp(args.clone()).to_bool()
+            || !maj_consp(maj_cdr(args.clone())).to_bool()
+        {
+            return Ok(args);
+        }
+        let vars = maj_car(args.clone());
+        let form = maj_syntax_resolve(&mut state, maj_car(maj_cdr(args.clone())),
+                                      originals, bound.clone(), env.clone())?;
+        let bound = maj_bound_symbols(vars.clone(), bound);
+        let body = maj_syntax_resolve_each(&mut state, maj_cdr(maj_cdr(args)),
+                                           originals, bound, env)?;
+        return Ok(Maj::cons(vars, Maj::cons(form, body)));
+    }
+
+    // (expr . clauses)
+    if !maj_consp(args.clone()).to_bool() {
+        return Ok(args);
+    }
+    let expr = maj_syntax_resolve(&mut state, maj_car(args.clone()),
+                                  originals, bound.clone(), env.clone())?;
+    let mut clauses = vec![];
+    let mut iter = maj_cdr(args);
+    while maj_consp(iter.clone()).to_bool() {
+        let clause = maj_car(iter.clone());
+        clauses.push(if !maj_consp(clause.clone()).to_bool() {
+            clause
+        } else if name == "match" {
+            maj_syntax_resolve_match_clause(&mut state, clause, originals,
+                                            bound.clone(), env.clone())?
+        } else {
+            // (type (var) . body)
+            let error_type = maj_syntax_unrename(maj_car(clause.clone()),
+                                                 originals);
+            let vars = maj_car(maj_cdr(clause.clone()));
+            let bound = maj_bound_symbols(vars.clone(), bound.clone());
+            let body = maj_syntax_resolve_each(
+                &mut state, maj_cdr(maj_cdr(clause)), originals,
+                bound, env.clone())?;
+            Maj::cons(error_type, Maj::cons(vars, body))
+        });
+        iter = maj_cdr(iter);
+    }
+    let clauses = clauses.into_iter().rev()
+        .fold(iter, |acc, clause| Maj::cons(clause, acc));
+    Ok(Maj::cons(expr, clauses))
+}
+
+fn maj_syntax_resolve_match_clause(
+    mut state: &mut MajState,
+    clause: Gc<Maj>,
+    originals: &MajSyntaxRenames,
+    bound: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<Gc<Maj>, Gc<Maj>> {
+    // (pattern . body) or (pattern when guard . body)
+    let (pattern, bound) = maj_syntax_resolve_pattern(
+        &mut state, maj_car(clause.clone()), originals, bound, env.clone())?;
+    let mut rest = maj_cdr(clause);
+    let guard_sym = Maj::symbol(&mut state, "when");
+    let mut guard = None;
+    if maj_consp(rest.clone()).to_bool() {
+        let head = maj_syntax_unrename(maj_car(rest.clone()), originals);
+        if maj_eq(head.clone(), guard_sym).to_bool() {
+            guard = Some(head);
+            rest = maj_cdr(rest);
+        }
+    }
+    let rest = maj_syntax_resolve_each(&mut state, rest, originals, bound, env)?;
+    Ok(Maj::cons(pattern, match guard {
+        Some(guard) => Maj::cons(guard, rest),
+        None => rest,
+    }))
+}
+
+fn maj_syntax_resolve_pattern(
+    mut state: &mut MajState,
+    pattern: Gc<Maj>,
+    originals: &MajSyntaxRenames,
+    bound: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<(Gc<Maj>, Gc<Maj>), Gc<Maj>> {
+    if maj_symbolp(pattern.clone()).to_bool() {
+        let bound = Maj::cons(pattern.clone(), bound);
+        return Ok((pattern, bound));
+    }
+    if !maj_consp(pattern.clone()).to_bool() {
+        return Ok((pattern, bound));
+    }
+
+    let head = maj_syntax_unrename(maj_car(pattern.clone()), originals);
+    let name = head.symbol_name(&state);
+    if maj_symbolp(head.clone()).to_bool() && name == "quote" {
+        return Ok((maj_syntax_unrename(pattern, originals), bound));
+    }
+    if maj_symbolp(head.clone()).to_bool() && name == "?" {
+        let args = maj_cdr(pattern);
+        if !maj_consp(args.clone()).to_bool() {
+            return Ok((Maj::cons(head, args), bound));
+        }
+        let pred = maj_syntax_resolve(&mut state, maj_car(args.clone()),
+                                      originals, bound.clone(), env.clone())?;
+        let (subpatterns, bound) = maj_syntax_resolve_pattern_list(
+            &mut state, maj_cdr(args), originals, bound, env)?;
+        return Ok((Maj::cons(head, Maj::cons(pred, subpatterns)), bound));
+    }
+    if maj_symbolp(head.clone()).to_bool() && name == "vector" {
+        let (subpatterns, bound) = maj_syntax_resolve_pattern_list(
+            &mut state, maj_cdr(pattern), originals, bound, env)?;
+        return Ok((Maj::cons(head, subpatterns), bound));
+    }
+    maj_syntax_resolve_pattern_list(&mut state, pattern, originals, bound, env)
+}
+
+fn maj_syntax_resolve_pattern_list(
+    mut state: &mut MajState,
+    patterns: Gc<Maj>,
+    originals: &MajSyntaxRenames,
+    bound: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<(Gc<Maj>, Gc<Maj>), Gc<Maj>> {
+    match &*patterns {
+        Maj::Cons { car, cdr } => {
+            let (car, bound) = maj_syntax_resolve_pattern(
+                &mut state, car.clone(), originals, bound, env.clone())?;
+            let (cdr, bound) = maj_syntax_resolve_pattern_list(
+                &mut state, cdr.clone(), originals, bound, env)?;
+            Ok((Maj::cons(car, cdr), bound))
+        },
+        _ => maj_syntax_resolve_pattern(&mut state, patterns.clone(),
+                                        originals, bound, env),
+    }
+}
+
 pub fn expand_macro(
     mut state: &mut MajState,
     mac: Gc<Maj>,
@@ -298,12 +1556,14 @@ pub fn expand_macro(
     env: Gc<Maj>
 ) -> (Gc<Maj>, bool) {
     if maj_macrop(mac.clone()).to_bool() {
-        // (lit macro <closure>)
-        let closure = maj_car(maj_cdr(maj_cdr(mac)));
-        let result = maj_apply(&mut state,
-                               closure,
-                               args.clone(),
-                               env);
+        // (lit macro <closure>) or (lit macro <transformer>)
+        let transformer = maj_car(maj_cdr(maj_cdr(mac)));
+        let result = if maj_syntax_transformer_p(transformer.clone()) {
+            maj_expand_syntax_rules(&mut state, transformer,
+                                    args.clone(), env)
+        } else {
+            maj_apply(&mut state, transformer, args.clone(), env)
+        };
         if maj_closurep(result.clone()).to_bool() {
             (maj_err(
                 Maj::string(
@@ -320,12 +1580,153 @@ pub fn expand_macro(
     }
 }
 
+fn maj_apply_continuation(
+    mut state: &mut MajState,
+    fun: Gc<Maj>,
+    args: Gc<Maj>
+) -> Gc<Maj> {
+    // (lit continuation tag)
+    let tag = maj_car(maj_cdr(maj_cdr(fun.clone())));
+    if !maj_nilp(maj_cdr(args.clone())).to_bool() {
+        return maj_arity_err(
+            Maj::string("Too many arguments for continuation {}"),
+            maj_list!(fun));
+    }
+    if !state.continuation_activep(tag.clone()) {
+        return maj_err(
+            Maj::string("Continuation {} invoked outside of its extent"),
+            maj_list!(fun));
+    }
+    maj_throw(&mut state, tag, maj_car(args))
+}
+
+fn maj_type_precedence(mut state: &mut MajState, x: Gc<Maj>) -> Vec<Gc<Maj>> {
+    let mut precedence = vec![maj_type(&mut state, x.clone())];
+    if maj_numberp(x.clone()).to_bool() {
+        precedence.push(Maj::symbol(&mut state, "number"));
+    } else if (maj_consp(x.clone()).to_bool()
+                && !maj_literalp(x.clone()).to_bool())
+        || maj_nilp(x.clone()).to_bool()
+    {
+        precedence.push(Maj::symbol(&mut state, "list"));
+    } else if maj_recordp(x).to_bool() {
+        precedence.push(Maj::record());
+    }
+    precedence.push(Maj::t());
+    precedence
+}
+
+pub fn maj_applicable_methods(
+    mut state: &mut MajState,
+    name: Gc<Maj>,
+    args: Gc<Maj>
+) -> Result<Vec<crate::core::state::MajMethod>, Gc<Maj>> {
+    let required = match state.generic_required(name.clone()) {
+        Some(required) => required,
+        None => return Err(maj_err(
+            Maj::string("{} is not a generic function"),
+            maj_list!(name))),
+    };
+
+    let mut precedences = Vec::new();
+    let mut itr = args.clone();
+    for _ in 0..required {
+        if !maj_consp(itr.clone()).to_bool() {
+            return Err(maj_arity_err(
+                Maj::string("Too few arguments for {}"),
+                maj_list!(name)));
+        }
+        let precedence = maj_type_precedence(&mut state, maj_car(itr.clone()));
+        precedences.push(precedence);
+        itr = maj_cdr(itr);
+    }
+
+    let mut applicable = Vec::new();
+    for method in state.generic_methods(name) {
+        let mut key = Vec::new();
+        for (spec, precedence) in
+            method.specializers.iter().zip(precedences.iter())
+        {
+            match precedence.iter()
+                .position(|ty| maj_eq(ty.clone(), spec.clone()).to_bool())
+            {
+                Some(position) => key.push(position),
+                None => break,
+            }
+        }
+        if key.len() == required {
+            applicable.push((key, method));
+        }
+    }
+    applicable.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(applicable.into_iter().map(|(_, method)| method).collect())
+}
+
+fn maj_apply_generic(
+    mut state: &mut MajState,
+    fun: Gc<Maj>,
+    args: Gc<Maj>
+) -> Gc<Maj> {
+    // (lit generic name)
+    let name = maj_car(maj_cdr(maj_cdr(fun)));
+    match maj_applicable_methods(&mut state, name.clone(), args.clone()) {
+        Ok(methods) => {
+            if methods.is_empty() {
+                return maj_err(
+                    Maj::string("No applicable method for {} with arguments {}"),
+                    maj_list!(name, args));
+            }
+            let mut functions = Maj::nil();
+            for method in methods.iter().rev() {
+                functions = Maj::cons(method.function.clone(), functions);
+            }
+            maj_call_methods(&mut state, name, functions, args)
+        },
+        Err(error) => error,
+    }
+}
+
+fn maj_call_methods(
+    mut state: &mut MajState,
+    name: Gc<Maj>,
+    functions: Gc<Maj>,
+    args: Gc<Maj>
+) -> Gc<Maj> {
+    let next = maj_list!(Maj::lit(), Maj::next_method(), name,
+                         maj_cdr(functions.clone()), args.clone());
+    maj_apply_closure(&mut state, maj_car(functions),
+                      Maj::cons(next, args))
+}
+
+fn maj_apply_next_method(
+    mut state: &mut MajState,
+    fun: Gc<Maj>,
+    args: Gc<Maj>
+) -> Gc<Maj> {
+    // (lit next-method name functions args)
+    let name = maj_car(maj_cdr(maj_cdr(fun.clone())));
+    let functions = maj_car(maj_cdr(maj_cdr(maj_cdr(fun.clone()))));
+    if maj_nilp(functions.clone()).to_bool() {
+        return maj_err(Maj::string("No next method for {}"),
+                       maj_list!(name));
+    }
+    let args =
+        if maj_nilp(args.clone()).to_bool() {
+            maj_car(maj_cdr(maj_cdr(maj_cdr(maj_cdr(fun)))))
+        } else {
+            args
+        };
+    maj_call_methods(&mut state, name, functions, args)
+}
+
 pub fn apply_primitive(mut state: &mut MajState,
                        prim: Gc<Maj>,
                        args: Gc<Maj>,
                        env: Gc<Maj>) -> Gc<Maj> {
     use crate::printing::maj_format;
     use crate::axioms::MajPrimArgs;
+    // Arguments never return multiple values through a primitive
+    state.clear_values();
     let primitive = state.find_primitive(prim.clone());
     match primitive {
         Some((function, arity)) => {
@@ -335,7 +1736,7 @@ pub fn apply_primitive(mut state: &mut MajState,
             match *arity {
                 MajPrimArgs::None => {
                     if argl != 0 {
-                        maj_err(
+                        maj_arity_err(
                             Maj::string("{} requires no arguments"),
                             maj_list!(prim))
                     } else {
@@ -350,7 +1751,7 @@ pub fn apply_primitive(mut state: &mut MajState,
                         curry_primitive(&mut state, prim, n, argl, args, env, false)
                     } else if argl > n {
                         // Fail on argl > n
-                        maj_err(
+                        maj_arity_err(
                             Maj::string("Too many arguments for {}"),
                             maj_list!(prim))
                     } else {
@@ -417,7 +1818,7 @@ fn curry_primitive(mut state: &mut MajState,
                    env: Gc<Maj>,
                    variadicp: bool) -> Gc<Maj> {
     if num_params == 0 {
-        return maj_err(
+        return maj_arity_err(
             Maj::string(
                 "Cannot curry function without arguments"),
             Maj::nil());
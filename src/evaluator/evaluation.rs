@@ -10,13 +10,15 @@ use crate::axioms::predicates::{
     maj_numberp,
     maj_charp,
     maj_streamp,
-    maj_stringp,
-    maj_macrop
+    maj_vectorp,
+    maj_macrop,
+    maj_keywordp
 };
 use crate::axioms::primitives::{
     maj_car,
     maj_cdr,
     maj_err,
+    maj_type_err,
     maj_length
 };
 
@@ -30,6 +32,52 @@ use super::maj_apply;
 pub fn maj_eval(mut state: &mut MajState,
                 expr: Gc<Maj>,
                 env: Gc<Maj>) -> Gc<Maj> {
+    // Values of previous forms never reach this one
+    state.clear_values();
+    let result = maj_eval_form(&mut state, expr.clone(), env);
+    maj_error_contextualize(&mut state, result, expr)
+}
+
+fn maj_error_contextualize(
+    state: &mut MajState,
+    result: Gc<Maj>,
+    form: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::axioms::primitives::{
+        maj_condition_contextp,
+        maj_condition_form,
+        maj_condition_stack,
+        maj_condition_location,
+        maj_condition_backtrace,
+        maj_error_with_context
+    };
+    if !maj_errorp(result.clone()).to_bool() {
+        result
+    } else if !maj_condition_contextp(result.clone()) {
+        let location = state.location(form.clone());
+        maj_error_with_context(result, form,
+                               state.call_stack(),
+                               location,
+                               state.backtrace())
+    } else if maj_nilp(maj_condition_location(result.clone())).to_bool() {
+        let location = state.location(form);
+        if maj_nilp(location.clone()).to_bool() {
+            result
+        } else {
+            maj_error_with_context(result.clone(),
+                                   maj_condition_form(result.clone()),
+                                   maj_condition_stack(result.clone()),
+                                   location,
+                                   maj_condition_backtrace(result))
+        }
+    } else {
+        result
+    }
+}
+
+fn maj_eval_form(mut state: &mut MajState,
+                 expr: Gc<Maj>,
+                 env: Gc<Maj>) -> Gc<Maj> {
     // use crate::maj_format;
     // println!("EVAL: {}", maj_format(&state, expr.clone()));
 
@@ -39,9 +87,14 @@ pub fn maj_eval(mut state: &mut MajState,
     }
 
     /* Special forms */
+    // references to the global context
+    else if maj_global_reference_p(expr.clone()) {
+        maj_handle_global_reference(&state, expr)
+    }
+
     // self-evaluating forms:
-    // Literals, numbers, characters, streams
-    else if maj_is_selfeval(expr.clone()) {
+    // Literals, numbers, characters, streams, vectors, keywords
+    else if maj_is_selfeval(&state, expr.clone()) {
         expr
     }
     
@@ -50,6 +103,11 @@ pub fn maj_eval(mut state: &mut MajState,
         state.lookup(env, expr)
     }
 
+    // compiled calls
+    else if maj_direct_call_p(expr.clone()) {
+        maj_handle_direct_call(&mut state, expr, env)
+    }
+
     // quote
     else if maj_quotep(expr.clone()).to_bool() {
         maj_handle_quote(expr)
@@ -62,7 +120,12 @@ pub fn maj_eval(mut state: &mut MajState,
 
     // macros
     else if maj_macp(expr.clone()).to_bool() {
-        maj_handle_mac(expr, env)
+        maj_handle_mac(&mut state, expr, env)
+    }
+
+    // hygienic macros
+    else if maj_syntax_rules_p(expr.clone()).to_bool() {
+        maj_handle_syntax_rules(expr)
     }
 
     // definitions
@@ -70,6 +133,14 @@ pub fn maj_eval(mut state: &mut MajState,
         maj_handle_definition(&mut state, expr, env)
     }
 
+    // special variables
+    else if maj_defvarp(&mut state, expr.clone()).to_bool() {
+        maj_handle_special_definition(&mut state, expr, env, false)
+    }
+    else if maj_defparameterp(&mut state, expr.clone()).to_bool() {
+        maj_handle_special_definition(&mut state, expr, env, true)
+    }
+
     // redefinitions
     else if maj_setp(&mut state, expr.clone()).to_bool() {
         maj_handle_redefinition(&mut state, expr, env)
@@ -90,7 +161,7 @@ pub fn maj_eval(mut state: &mut MajState,
 
     // closures
     else if maj_fnp(expr.clone()).to_bool() {
-        maj_handle_fn(expr, env)
+        maj_handle_fn(&mut state, expr, env)
     }
 
     // do
@@ -128,8 +199,45 @@ pub fn maj_eval(mut state: &mut MajState,
         maj_handle_unwind_protect(&mut state, expr, env)
     }
 
+    // catch
+    else if maj_catchp(&mut state, expr.clone()).to_bool() {
+        maj_handle_catch(&mut state, expr, env)
+    }
+
+    // handler-case
+    else if maj_handler_case_p(&mut state, expr.clone()).to_bool() {
+        maj_handle_handler_case(&mut state, expr, env)
+    }
+
+    // match
+    else if maj_matchp(&mut state, expr.clone()).to_bool() {
+        maj_handle_match(&mut state, expr, env)
+    }
+
+    // multiple values
+    else if maj_multiple_value_list_p(&mut state, expr.clone()).to_bool() {
+        maj_handle_multiple_value_list(&mut state, expr, env)
+    }
+    else if maj_multiple_value_bind_p(&mut state, expr.clone()).to_bool() {
+        maj_handle_multiple_value_bind(&mut state, expr, env)
+    }
+
+    // methods
+    else if maj_defmethodp(&mut state, expr.clone()).to_bool() {
+        maj_handle_defmethod(&mut state, expr, env)
+    }
+
+    // coroutines
+    else if maj_yieldp(&mut state, expr.clone()).to_bool() {
+        maj_handle_yield(expr)
+    }
+    else if maj_forp(&mut state, expr.clone()).to_bool() {
+        maj_handle_for(&mut state, expr, env)
+    }
+
     // application
     else {
+        let form = expr.clone();
         let fun = maj_eval(&mut state,
                            maj_car(expr.clone()),
                            env.clone());
@@ -150,13 +258,28 @@ pub fn maj_eval(mut state: &mut MajState,
             return args;
         }
 
-        stacker::maybe_grow(
-            STACK_RED_ZONE,
-            STACK_PER_RECURSION,
-            || maj_apply(&mut state, fun, args, env))
+        maj_apply_in_frame(&mut state, form, fun, args, env)
     }
 }
 
+fn maj_apply_in_frame(
+    mut state: &mut MajState,
+    form: Gc<Maj>,
+    fun: Gc<Maj>,
+    args: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    state.push_frame(form.clone(), fun.clone(), args.clone());
+    let result = stacker::maybe_grow(
+        STACK_RED_ZONE,
+        STACK_PER_RECURSION,
+        || maj_apply(&mut state, fun, args, env));
+    let source = state.source_form(form);
+    let result = maj_error_contextualize(&mut state, result, source);
+    state.pop_frame();
+    result
+}
+
 fn maj_evlist(mut state: &mut MajState,
               list: Gc<Maj>,
               env: Gc<Maj>) -> Gc<Maj> {
@@ -184,9 +307,8 @@ fn maj_evlist(mut state: &mut MajState,
 }
 
 fn maj_handle_quote(expr: Gc<Maj>) -> Gc<Maj> {
-    let length = maj_length(expr.clone())
-        .to_integer().unwrap();
-    if length != 2 {
+    let length = maj_length(expr.clone()).to_integer();
+    if length != Some(2) {
         return maj_err(
             Maj::string("Invalid syntax: {}"),
             maj_list!(expr));
@@ -195,10 +317,21 @@ fn maj_handle_quote(expr: Gc<Maj>) -> Gc<Maj> {
     maj_car(maj_cdr(expr))
 }
 
+pub fn maj_global_reference_p(x: Gc<Maj>) -> bool {
+    maj_literalp(x.clone()).to_bool()
+        && maj_eq(maj_car(maj_cdr(x)), Maj::global()).to_bool()
+}
+
+fn maj_handle_global_reference(state: &MajState, expr: Gc<Maj>) -> Gc<Maj> {
+    use crate::core::environment::maj_env_lookup;
+    // (lit global sym)
+    let sym = maj_car(maj_cdr(maj_cdr(expr)));
+    maj_env_lookup(state.get_global_env(), sym)
+}
+
 fn maj_handle_quasiquote(mut state: &mut MajState, expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
-    let length = maj_length(expr.clone())
-        .to_integer().unwrap();
-    if length != 2 {
+    let length = maj_length(expr.clone()).to_integer();
+    if length != Some(2) {
         return maj_err(
             Maj::string("Invalid syntax: {}"),
             maj_list!(expr));
@@ -252,14 +385,58 @@ fn maj_do_quasiquote(
         }
 }
 
+fn maj_bound_keyword(
+    state: &MajState,
+    lambda_list: Gc<Maj>
+) -> Option<Gc<Maj>> {
+    use crate::axioms::predicates::maj_consp;
+    use super::application::maj_lambda_list_keyword_p;
+    let mut options = false;
+    let mut iter = lambda_list;
+    while maj_consp(iter.clone()).to_bool() {
+        let param = maj_car(iter.clone());
+        if maj_lambda_list_keyword_p(param.clone()) {
+            options = true;
+        } else if maj_consp(param.clone()).to_bool() {
+            // Options carry a default form; other lists destructure
+            let found = if options {
+                let sym = maj_car(param);
+                if maj_keywordp(state, sym.clone()).to_bool() {
+                    Some(sym)
+                } else {
+                    None
+                }
+            } else {
+                maj_bound_keyword(state, param)
+            };
+            if found.is_some() {
+                return found;
+            }
+        } else if maj_keywordp(state, param.clone()).to_bool() {
+            return Some(param);
+        }
+        iter = maj_cdr(iter);
+    }
+    if maj_keywordp(state, iter.clone()).to_bool() {
+        Some(iter)
+    } else {
+        None
+    }
+}
+
+fn maj_keyword_binding_err(sym: Gc<Maj>) -> Gc<Maj> {
+    maj_type_err(
+        Maj::string("Cannot bind keyword {}"),
+        maj_list!(sym))
+}
+
 fn maj_handle_definition(
     mut state: &mut MajState,
     expr: Gc<Maj>,
     env: Gc<Maj>
 ) -> Gc<Maj> {
-    let length = maj_length(expr.clone())
-        .to_integer().unwrap();
-    if length != 3 {
+    let length = maj_length(expr.clone()).to_integer();
+    if length != Some(3) {
         return maj_err(
             Maj::string("Invalid syntax: {}"),
             maj_list!(expr));
@@ -268,6 +445,10 @@ fn maj_handle_definition(
     let sym = maj_car(maj_cdr(expr.clone()));
     let val = maj_car(maj_cdr(maj_cdr(expr)));
 
+    if maj_keywordp(&state, sym.clone()).to_bool() {
+        return maj_keyword_binding_err(sym);
+    }
+
     // Evaluate associated value before binding
     let val = maj_eval(&mut state, val, env);
     if maj_errorp(val.clone()).to_bool() {
@@ -275,6 +456,8 @@ fn maj_handle_definition(
     }
 
     if maj_symbolp(sym.clone()).to_bool() {
+        // Compiled calls must follow the new definition
+        state.mark_redefined(sym.clone());
         // Try finding thing on environment.
         // An error means it does not exist
         let element = state.assoc(Maj::nil(), sym.clone());
@@ -293,20 +476,58 @@ fn maj_handle_definition(
             state.push(sym, val)
         }
     } else {
-        maj_err(
+        maj_type_err(
             Maj::string("{} is not a symbol"),
             maj_list!(sym))
     }
 }
 
+fn maj_handle_special_definition(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>,
+    overwrite: bool
+) -> Gc<Maj> {
+    let length = maj_length(expr.clone()).to_integer();
+    let valid_length = match length {
+        Some(length) if overwrite => length == 3,
+        Some(length) => length == 2 || length == 3,
+        None => false,
+    };
+    if !valid_length {
+        return maj_err(
+            Maj::string("Invalid syntax: {}"),
+            maj_list!(expr));
+    }
+
+    let sym = maj_car(maj_cdr(expr.clone()));
+    if !maj_symbolp(sym.clone()).to_bool() {
+        return maj_type_err(
+            Maj::string("{} is not a symbol"),
+            maj_list!(sym));
+    }
+
+    if maj_keywordp(&state, sym.clone()).to_bool() {
+        return maj_keyword_binding_err(sym);
+    }
+
+    state.declare_special(sym.clone());
+
+    let boundp = !maj_errorp(state.assoc(Maj::nil(), sym.clone())).to_bool();
+    if length == Some(2) || (boundp && !overwrite) {
+        sym
+    } else {
+        maj_handle_definition(&mut state, expr, env)
+    }
+}
+
 fn maj_handle_redefinition(
     mut state: &mut MajState,
     expr: Gc<Maj>,
     env: Gc<Maj>
 ) -> Gc<Maj> {
-    let length = maj_length(expr.clone())
-        .to_integer().unwrap();
-    if length != 3 {
+    let length = maj_length(expr.clone()).to_integer();
+    if length != Some(3) {
         return maj_err(
             Maj::string("Invalid syntax: {}"),
             maj_list!(expr));
@@ -327,6 +548,12 @@ fn maj_handle_redefinition(
         if maj_errorp(element.clone()).to_bool() {
             element
         } else {
+            // Compiled calls must follow global redefinitions
+            if state.find_primitive(sym.clone()).is_some()
+                && Gc::ptr_eq(&element, &state.assoc(Maj::nil(), sym.clone()))
+            {
+                state.mark_redefined(sym.clone());
+            }
             // When found, replace binding cons
             let new_binding = Maj::cons(sym.clone(), val);
             unsafe {
@@ -338,7 +565,7 @@ fn maj_handle_redefinition(
             sym
         }
     } else {
-        maj_err(
+        maj_type_err(
             Maj::string("{} is not a symbol"),
             maj_list!(sym))
     }
@@ -350,9 +577,8 @@ fn maj_handle_redefine_cxr_helper(
     env: Gc<Maj>
 ) -> Result<(Gc<Maj>, Gc<Maj>), Gc<Maj>> {
     use crate::axioms::predicates::maj_consp;
-    let length = maj_length(expr.clone())
-        .to_integer().unwrap();
-    if length != 3 {
+    let length = maj_length(expr.clone()).to_integer();
+    if length != Some(3) {
         return Err(maj_err(
             Maj::string("Invalid syntax: {}"),
             maj_list!(expr)));
@@ -370,7 +596,7 @@ fn maj_handle_redefine_cxr_helper(
     if maj_errorp(pair.clone()).to_bool() {
         return Err(pair);
     } else if !maj_consp(pair.clone()).to_bool() {
-        return Err(maj_err(
+        return Err(maj_type_err(
             Maj::string("{} is not a cons cell"),
             maj_list!(pair)));
     }
@@ -378,6 +604,16 @@ fn maj_handle_redefine_cxr_helper(
     Ok((pair, value))
 }
 
+pub fn maj_replace_pair(pair: Gc<Maj>, car: Gc<Maj>, cdr: Gc<Maj>) {
+    let new_pair = Maj::cons(car, cdr);
+    unsafe {
+        let rawpair = Gc::into_raw(pair);
+        std::ptr::copy_nonoverlapping(
+            Gc::into_raw(new_pair),
+            rawpair as *mut Maj, 1);
+    }
+}
+
 fn maj_handle_redefine_car(
     mut state: &mut MajState,
     expr: Gc<Maj>,
@@ -385,13 +621,7 @@ fn maj_handle_redefine_car(
 ) -> Gc<Maj> {
     match maj_handle_redefine_cxr_helper(&mut state, expr, env) {
         Ok((pair, value)) => {
-            let new_pair = Maj::cons(value, maj_cdr(pair.clone()));
-            unsafe {
-                let rawpair = Gc::into_raw(pair.clone());
-                std::ptr::copy_nonoverlapping(
-                    Gc::into_raw(new_pair.clone()),
-                    rawpair as *mut Maj, 1);
-            }
+            maj_replace_pair(pair.clone(), value, maj_cdr(pair.clone()));
             pair
         },
         Err(error) => error,
@@ -405,13 +635,7 @@ fn maj_handle_redefine_cdr(
 ) -> Gc<Maj> {
     match maj_handle_redefine_cxr_helper(&mut state, expr, env) {
         Ok((pair, value)) => {
-            let new_pair = Maj::cons(maj_car(pair.clone()), value);
-            unsafe {
-                let rawpair = Gc::into_raw(pair.clone());
-                std::ptr::copy_nonoverlapping(
-                    Gc::into_raw(new_pair.clone()),
-                    rawpair as *mut Maj, 1);
-            }
+            maj_replace_pair(pair.clone(), maj_car(pair.clone()), value);
             pair
         },
         Err(error) => error,
@@ -419,9 +643,8 @@ fn maj_handle_redefine_cdr(
 }
 
 fn maj_handle_if(mut state: &mut MajState, expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
-    let length = maj_length(expr.clone())
-        .to_integer().unwrap();
-    if length != 4 {
+    let length = maj_length(expr.clone()).to_integer();
+    if length != Some(4) {
         return maj_err(
             Maj::string("Invalid syntax: {}"),
             maj_list!(expr));
@@ -445,10 +668,13 @@ fn maj_handle_if(mut state: &mut MajState, expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Ma
     }
 }
 
-fn maj_handle_fn(expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
-    let length = maj_length(expr.clone())
-        .to_integer().unwrap();
-    if length < 3 {
+fn maj_handle_fn(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    let length = maj_length(expr.clone()).to_integer();
+    if length.is_none() || length.unwrap() < 3 {
         return maj_err(
             Maj::string("Invalid syntax: {}"),
             maj_list!(expr));
@@ -462,7 +688,14 @@ fn maj_handle_fn(expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
         lambda_list
     } else if maj_errorp(body.clone()).to_bool() {
         body
+    } else if let Some(sym) = maj_bound_keyword(&state,
+                                                lambda_list.clone()) {
+        maj_keyword_binding_err(sym)
     } else {
+        let body = maj_expand_body(&mut state,
+                                   lambda_list.clone(),
+                                   body,
+                                   env.clone());
         // (lit closure <env> <lambda-list> ((<body>)))
         maj_list!(Maj::lit(),
                   Maj::closure(),
@@ -470,10 +703,13 @@ fn maj_handle_fn(expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
     }
 }
 
-fn maj_handle_mac(expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
-    let length = maj_length(expr.clone())
-        .to_integer().unwrap();
-    if length < 3 {
+fn maj_handle_mac(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    let length = maj_length(expr.clone()).to_integer();
+    if length.is_none() || length.unwrap() < 3 {
         return maj_err(
             Maj::string("Invalid syntax: {}"),
             maj_list!(expr));
@@ -482,6 +718,13 @@ fn maj_handle_mac(expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
     // (mac lambda-list . body)
     let lambda_list = maj_car(maj_cdr(expr.clone()));
     let body = maj_cdr(maj_cdr(expr));
+    if let Some(sym) = maj_bound_keyword(&state, lambda_list.clone()) {
+        return maj_keyword_binding_err(sym);
+    }
+    let body = maj_expand_body(&mut state,
+                               lambda_list.clone(),
+                               body,
+                               env.clone());
 
     // (lit macro (lit closure <env> lambda-list (body)))
     maj_list!(Maj::lit(),
@@ -492,6 +735,56 @@ fn maj_handle_mac(expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
                   env, lambda_list, body))
 }
 
+fn maj_handle_syntax_rules(expr: Gc<Maj>) -> Gc<Maj> {
+    use crate::axioms::predicates::{ maj_consp, maj_proper_list_p };
+    if !maj_proper_list_p(expr.clone()).to_bool()
+        || maj_length(expr.clone()).to_integer().map_or(true, |l| l < 2)
+    {
+        return maj_err(
+            Maj::string("Invalid syntax: {}"),
+            maj_list!(expr));
+    }
+
+    // (syntax-rules literals . rules)
+    let literals = maj_car(maj_cdr(expr.clone()));
+    let rules = maj_cdr(maj_cdr(expr));
+
+    let mut iter = literals.clone();
+    while !maj_nilp(iter.clone()).to_bool() {
+        if !maj_consp(iter.clone()).to_bool()
+            || !maj_symbolp(maj_car(iter.clone())).to_bool()
+        {
+            return maj_err(
+                Maj::string("Invalid syntax-rules literals {}"),
+                maj_list!(literals));
+        }
+        iter = maj_cdr(iter);
+    }
+
+    let mut iter = rules.clone();
+    while !maj_nilp(iter.clone()).to_bool() {
+        // (pattern template)
+        let rule = maj_car(iter.clone());
+        if !maj_proper_list_p(rule.clone()).to_bool()
+            || maj_length(rule.clone()).to_integer() != Some(2)
+            || !maj_consp(maj_car(rule.clone())).to_bool()
+        {
+            return maj_err(
+                Maj::string("Invalid syntax rule {}"),
+                maj_list!(rule));
+        }
+        iter = maj_cdr(iter);
+    }
+
+    // (lit macro (lit syntax-rules <literals> <rules>))
+    maj_list!(Maj::lit(),
+              Maj::macro_sym(),
+              maj_list!(
+                  Maj::lit(),
+                  Maj::syntax_rules(),
+                  literals, rules))
+}
+
 fn maj_handle_do(mut state: &mut MajState, expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
     let mut itr    = maj_cdr(expr.clone());
     let mut result = Maj::nil();
@@ -507,9 +800,9 @@ fn maj_handle_do(mut state: &mut MajState, expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Ma
 }
 
 fn maj_handle_apply(mut state: &mut MajState, expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
-    let length = maj_length(expr.clone())
-        .to_integer().unwrap();
-    if length != 3 {
+    use crate::axioms::predicates::maj_proper_list_p;
+    let length = maj_length(expr.clone()).to_integer();
+    if length != Some(3) {
         return maj_err(
             Maj::string("Invalid syntax: {}"),
             maj_list!(expr));
@@ -532,6 +825,10 @@ fn maj_handle_apply(mut state: &mut MajState, expr: Gc<Maj>, env: Gc<Maj>) -> Gc
             Maj::nil())
     } else if maj_errorp(args.clone()).to_bool() {
         args
+    } else if !maj_proper_list_p(args.clone()).to_bool() {
+        maj_type_err(
+            Maj::string("{} is not a proper list"),
+            maj_list!(args))
     } else {
         maj_eval(&mut state, Maj::cons(func, args), env)
     }
@@ -543,9 +840,8 @@ fn maj_handle_letrec(
     env: Gc<Maj>
 ) -> Gc<Maj> {
     use crate::axioms::predicates::maj_consp;
-    let length = maj_length(expr.clone())
-        .to_integer().unwrap();
-    if length < 3 {
+    let length = maj_length(expr.clone()).to_integer();
+    if length.is_none() || length.unwrap() < 3 {
         return maj_err(
             Maj::string("Invalid syntax: {}"),
             maj_list!(expr));
@@ -637,9 +933,8 @@ fn maj_handle_while(
     env: Gc<Maj>
 ) -> Gc<Maj> {
     // (while pred . body)
-    let length = maj_length(expr.clone())
-        .to_integer().unwrap();
-    if length < 2 {
+    let length = maj_length(expr.clone()).to_integer();
+    if length.is_none() || length.unwrap() < 2 {
         return maj_err(
             Maj::string("Invalid syntax: {}"),
             maj_list!(expr));
@@ -676,9 +971,8 @@ fn maj_handle_unwind_protect(
     env: Gc<Maj>
 ) -> Gc<Maj> {
     // (unwind-protect expr cleanup)
-    let length = maj_length(expr.clone())
-        .to_integer().unwrap();
-    if length != 3 {
+    let length = maj_length(expr.clone()).to_integer();
+    if length != Some(3) {
         return maj_err(
             Maj::string("Invalid syntax: {}"),
             maj_list!(expr));
@@ -692,89 +986,1591 @@ fn maj_handle_unwind_protect(
     result
 }
 
-fn maj_handle_and(mut state: &mut MajState, expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
-    let forms = maj_cdr(expr);
-    let mut iter = forms;
-    let mut result = Maj::t();
-    while !maj_nilp(iter.clone()).to_bool() {
-        let form = maj_car(iter.clone());
-        result = maj_eval(&mut state, form, env.clone());
-        if maj_nilp(result.clone()).to_bool() {
-            return Maj::nil();
+fn maj_handle_catch(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::axioms::primitives::maj_condition_irritants;
+    // (catch tag . body)
+    let length = maj_length(expr.clone()).to_integer();
+    if length.is_none() || length.unwrap() < 2 {
+        return maj_err(
+            Maj::string("Invalid syntax: {}"),
+            maj_list!(expr));
+    }
+
+    let tag  = maj_car(maj_cdr(expr.clone()));
+    let body = maj_cdr(maj_cdr(expr));
+    let body = Maj::cons(Maj::do_sym(), body);
+
+    let tag = maj_eval(&mut state, tag, env.clone());
+    if maj_errorp(tag.clone()).to_bool() {
+        return tag;
+    }
+
+    let result = maj_eval(&mut state, body, env);
+    if maj_throwp(&mut state, result.clone()).to_bool() {
+        // (lit error throw fmt tag value)
+        let irritants = maj_condition_irritants(result.clone());
+        if maj_eq(maj_car(irritants.clone()), tag).to_bool() {
+            return maj_car(maj_cdr(irritants));
         }
-        iter = maj_cdr(iter.clone());
     }
     result
 }
 
-fn maj_handle_or(mut state: &mut MajState, expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
-    let forms = maj_cdr(expr);
-    let mut iter = forms;
-    let mut result = Maj::nil();
+fn maj_handle_handler_case(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    // (handler-case expr . clauses)
+    let length = maj_length(expr.clone()).to_integer();
+    if length.is_none() || length.unwrap() < 2 {
+        return maj_err(
+            Maj::string("Invalid syntax: {}"),
+            maj_list!(expr));
+    }
+
+    let form    = maj_car(maj_cdr(expr.clone()));
+    let clauses = maj_cdr(maj_cdr(expr));
+
+    let result = maj_eval(&mut state, form, env.clone());
+    if !maj_errorp(result.clone()).to_bool()
+        || maj_throwp(&mut state, result.clone()).to_bool()
+    {
+        return result;
+    }
+
+    match maj_handler_case_clause(clauses, result.clone(), env) {
+        Ok(Some((body, env))) => maj_eval(&mut state, body, env),
+        Ok(None) => result,
+        Err(error) => error,
+    }
+}
+
+fn maj_handler_case_clause(
+    clauses: Gc<Maj>,
+    error: Gc<Maj>,
+    env: Gc<Maj>
+) -> Result<Option<(Gc<Maj>, Gc<Maj>)>, Gc<Maj>> {
+    use crate::axioms::predicates::maj_consp;
+    use crate::axioms::primitives::{
+        maj_condition_type,
+        maj_error_to_condition
+    };
+    use crate::core::environment::maj_env_push;
+    let kind = maj_condition_type(error.clone());
+    let mut iter = clauses;
     while !maj_nilp(iter.clone()).to_bool() {
-        let form = maj_car(iter.clone());
-        result = maj_eval(&mut state, form, env.clone());
-        if !maj_nilp(result.clone()).to_bool() {
-            return result;
+        // (type (var) . body)
+        let clause = maj_car(iter.clone());
+        if !maj_consp(clause.clone()).to_bool() {
+            return Err(maj_err(Maj::string(
+                "Syntax error on handler-case: {} is not a proper clause"),
+                maj_list!(clause)));
         }
-        iter = maj_cdr(iter.clone());
+
+        let clause_type = maj_car(clause.clone());
+        if maj_eq(clause_type.clone(), Maj::error()).to_bool()
+            || maj_eq(clause_type, kind.clone()).to_bool()
+        {
+            let vars = maj_car(maj_cdr(clause.clone()));
+            let body = maj_cdr(maj_cdr(clause));
+            let body = Maj::cons(Maj::do_sym(), body);
+            let env =
+                if maj_consp(vars.clone()).to_bool() {
+                    maj_env_push(env,
+                                 maj_car(vars),
+                                 maj_error_to_condition(error))
+                } else {
+                    env
+                };
+            return Ok(Some((body, env)));
+        }
+        iter = maj_cdr(iter);
     }
-    result
+    Ok(None)
 }
 
-fn maj_is_selfeval(x: Gc<Maj>) -> bool {
-    maj_literalp(x.clone()).to_bool()
-        || maj_nilp(x.clone()).to_bool()
-        || maj_eq(x.clone(), Maj::t()).to_bool()
-        || maj_eq(x.clone(), Maj::ampersand()).to_bool()
-        || maj_eq(x.clone(), Maj::apply()).to_bool()
-        || maj_numberp(x.clone()).to_bool()
-        || maj_charp(x.clone()).to_bool()
-        || maj_streamp(x.clone()).to_bool()
-        || maj_stringp(x.clone()).to_bool()
-}
+fn maj_handle_match(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_consp;
+    use crate::axioms::primitives::maj_match_err;
+    use super::application::{
+        maj_match_pattern,
+        maj_eval_closure_body
+    };
+
+    // (match expr . clauses)
+    let length = maj_length(expr.clone()).to_integer();
+    if length.is_none() || length.unwrap() < 2 {
+        return maj_err(
+            Maj::string("Invalid syntax: {}"),
+            maj_list!(expr));
+    }
 
-pub fn maj_quotep(x: Gc<Maj>) -> Gc<Maj> {
-    maj_eq(maj_car(x), Maj::quote())
-}
+    let value = maj_eval(&mut state,
+                         maj_car(maj_cdr(expr.clone())),
+                         env.clone());
+    if maj_errorp(value.clone()).to_bool() {
+        return value;
+    }
 
-pub fn maj_quasiquotep(x: Gc<Maj>) -> Gc<Maj> {
-    maj_eq(maj_car(x), Maj::quasiquote())
-}
+    let when_sym = Maj::symbol(&mut state, "when");
+    let mut clauses = maj_cdr(maj_cdr(expr));
+    while !maj_nilp(clauses.clone()).to_bool() {
+        let clause = maj_car(clauses.clone());
+        clauses = maj_cdr(clauses);
+        if !maj_consp(clause.clone()).to_bool() {
+            return maj_err(Maj::string(
+                "Syntax error on match: {} is not a proper clause"),
+                maj_list!(clause));
+        }
 
-pub fn maj_unquotep(x: Gc<Maj>) -> Gc<Maj> {
-    maj_eq(maj_car(x), Maj::unquote())
-}
+        let pattern = maj_car(clause.clone());
+        let mut body = maj_cdr(clause.clone());
+        let mut guard = None;
+        if maj_eq(maj_car(body.clone()), when_sym.clone()).to_bool() {
+            if !maj_consp(maj_cdr(body.clone())).to_bool() {
+                return maj_err(Maj::string(
+                    "Syntax error on match: {} has no guard"),
+                    maj_list!(clause));
+            }
+            guard = Some(maj_car(maj_cdr(body.clone())));
+            body = maj_cdr(maj_cdr(body));
+        }
 
-pub fn maj_unquote_splice_p(x: Gc<Maj>) -> Gc<Maj> {
-    maj_eq(maj_car(x), Maj::unquote_splice())
-}
+        let extenv = match maj_match_pattern(&mut state, pattern,
+                                             value.clone(), env.clone()) {
+            Err(error)        => return error,
+            Ok(None)          => continue,
+            Ok(Some(extenv))  => extenv,
+        };
+
+        if let Some(guard) = guard {
+            let result = maj_eval_closure_body(&mut state,
+                                               maj_list!(guard),
+                                               extenv.clone(),
+                                               env.clone());
+            if maj_errorp(result.clone()).to_bool() {
+                return result;
+            }
+            if maj_nilp(result).to_bool() {
+                continue;
+            }
+        }
 
-fn maj_defp(mut state: &mut MajState,
-            x: Gc<Maj>) -> Gc<Maj> {
-    let car = maj_car(x);
-    maj_eq(car, Maj::symbol(&mut state, "def"))
-}
+        return maj_eval_closure_body(&mut state, body, extenv, env.clone());
+    }
 
-fn maj_setp(mut state: &mut MajState,
-            x: Gc<Maj>) -> Gc<Maj> {
-    let car = maj_car(x);
-    maj_eq(car, Maj::symbol(&mut state, "set"))
+    maj_match_err(
+        Maj::string("No clause matches {}"),
+        maj_list!(value))
 }
 
-fn maj_set_car_p(mut state: &mut MajState,
-            x: Gc<Maj>) -> Gc<Maj> {
-    let car = maj_car(x);
-    maj_eq(car, Maj::symbol(&mut state, "set-car"))
+fn maj_eval_values(
+    mut state: &mut MajState,
+    form: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    let result = maj_eval(&mut state, form, env);
+    if maj_errorp(result.clone()).to_bool() {
+        state.clear_values();
+        return result;
+    }
+    state.take_values(result)
 }
 
-fn maj_set_cdr_p(mut state: &mut MajState,
-            x: Gc<Maj>) -> Gc<Maj> {
-    let car = maj_car(x);
-    maj_eq(car, Maj::symbol(&mut state, "set-cdr"))
+fn maj_handle_multiple_value_list(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    // (multiple-value-list form)
+    let length = maj_length(expr.clone()).to_integer();
+    if length != Some(2) {
+        return maj_err(
+            Maj::string("Invalid syntax: {}"),
+            maj_list!(expr));
+    }
+    maj_eval_values(&mut state, maj_car(maj_cdr(expr)), env)
 }
 
-fn maj_dop(mut state: &mut MajState,
+fn maj_handle_multiple_value_bind(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::core::environment::maj_env_push;
+    use crate::axioms::predicates::maj_proper_list_p;
+    use super::application::maj_eval_closure_body;
+
+    // (multiple-value-bind vars form . body)
+    let length = maj_length(expr.clone()).to_integer();
+    if length.is_none() || length.unwrap() < 3 {
+        return maj_err(
+            Maj::string("Invalid syntax: {}"),
+            maj_list!(expr));
+    }
+
+    let vars = maj_car(maj_cdr(expr.clone()));
+    let form = maj_car(maj_cdr(maj_cdr(expr.clone())));
+    let body = maj_cdr(maj_cdr(maj_cdr(expr)));
+
+    if !maj_proper_list_p(vars.clone()).to_bool() {
+        return maj_type_err(
+            Maj::string("{} is not a proper list"),
+            maj_list!(vars));
+    }
+
+    let mut values = maj_eval_values(&mut state, form, env.clone());
+    if maj_errorp(values.clone()).to_bool() {
+        return values;
+    }
+
+    let mut extenv = env.clone();
+    let mut iter = vars;
+    while !maj_nilp(iter.clone()).to_bool() {
+        let var = maj_car(iter.clone());
+        if !maj_symbolp(var.clone()).to_bool() {
+            return maj_type_err(
+                Maj::string("{} is not a symbol"),
+                maj_list!(var));
+        }
+        extenv = maj_env_push(extenv, var, maj_car(values.clone()));
+        values = maj_cdr(values);
+        iter = maj_cdr(iter);
+    }
+
+    maj_eval_closure_body(&mut state, body, extenv, env)
+}
+
+fn maj_handle_defmethod(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_consp;
+    use crate::axioms::primitives::{ maj_required_params, maj_make_generic };
+    use crate::core::state::MajMethod;
+    use crate::maj_dotted_list;
+    let length = maj_length(expr.clone()).to_integer();
+    if length.is_none() || length.unwrap() < 3 {
+        return maj_err(
+            Maj::string("Invalid syntax: {}"),
+            maj_list!(expr));
+    }
+
+    // (defmethod name lambda-list . body)
+    let name = maj_car(maj_cdr(expr.clone()));
+    let lambda_list = maj_car(maj_cdr(maj_cdr(expr.clone())));
+    let body = maj_cdr(maj_cdr(maj_cdr(expr)));
+    if !maj_symbolp(name.clone()).to_bool() || maj_nilp(name.clone()).to_bool() {
+        return maj_type_err(
+            Maj::string("{} is not a symbol"),
+            maj_list!(name));
+    }
+
+    let required = maj_required_params(lambda_list.clone());
+    if maj_errorp(required.clone()).to_bool() {
+        return required;
+    }
+
+    // Strip specializers from required parameters
+    let mut specializers = Vec::new();
+    let mut params = Vec::new();
+    let mut itr = required;
+    let mut rest = lambda_list;
+    while !maj_nilp(itr.clone()).to_bool() {
+        let param = maj_car(itr.clone());
+        if maj_consp(param.clone()).to_bool() {
+            params.push(maj_car(param.clone()));
+            specializers.push(maj_car(maj_cdr(param)));
+        } else {
+            params.push(param);
+            specializers.push(Maj::t());
+        }
+        itr = maj_cdr(itr);
+        rest = maj_cdr(rest);
+    }
+    let mut plain = rest;
+    for param in params.iter().rev() {
+        plain = Maj::cons(param.clone(), plain);
+    }
+
+    match state.generic_required(name.clone()) {
+        Some(n) if n != specializers.len() => {
+            return maj_err(
+                Maj::string("Method for {} must have {} required parameters"),
+                maj_list!(name, Maj::integer(n as i64)));
+        },
+        Some(_) => {},
+        None => {
+            let generic = maj_make_generic(&mut state, name.clone(),
+                                           plain.clone());
+            let definition = maj_list!(
+                Maj::symbol(&mut state, "def"),
+                name.clone(),
+                maj_list!(Maj::quote(), generic));
+            let result = maj_handle_definition(&mut state, definition,
+                                               env.clone());
+            if maj_errorp(result.clone()).to_bool() {
+                return result;
+            }
+        },
+    }
+
+    let next = Maj::symbol(&mut state, "call-next-method");
+    let function = maj_handle_fn(
+        &mut state,
+        maj_dotted_list!(Maj::fn_sym(), Maj::cons(next, plain), body),
+        env);
+    if maj_errorp(function.clone()).to_bool() {
+        return function;
+    }
+    state.add_method(name.clone(), MajMethod { specializers, function });
+    name
+}
+
+fn maj_handle_yield(expr: Gc<Maj>) -> Gc<Maj> {
+    maj_err(
+        Maj::string("Cannot yield outside of the body of a coroutine: {}"),
+        maj_list!(expr))
+}
+
+fn maj_handle_for(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::core::environment::maj_env_push;
+    use crate::axioms::predicates::maj_coroutinep;
+    use crate::evaluator::application::maj_eval_closure_body;
+
+    let (var, generator, body) = match maj_for_parts(expr.clone()) {
+        Some(parts) => parts,
+        None => return maj_err(
+            Maj::string("Invalid syntax: {}"),
+            maj_list!(expr)),
+    };
+
+    let generator = maj_eval(&mut state, generator, env.clone());
+    if maj_errorp(generator.clone()).to_bool() {
+        return generator;
+    }
+    if !maj_coroutinep(generator.clone()).to_bool() {
+        return maj_type_err(Maj::string("{} is not a coroutine"),
+                            maj_list!(generator));
+    }
+
+    loop {
+        match maj_resume(&mut state, generator.clone(), Maj::nil()) {
+            MajResume::Yield(value) => {
+                let extenv = maj_env_push(env.clone(), var.clone(), value);
+                let result = maj_eval_closure_body(&mut state, body.clone(),
+                                                   extenv, env.clone());
+                if maj_errorp(result.clone()).to_bool() {
+                    return result;
+                }
+            },
+            MajResume::Return(value) => {
+                return if maj_errorp(value.clone()).to_bool() {
+                    value
+                } else {
+                    Maj::nil()
+                };
+            },
+        }
+    }
+}
+
+fn maj_for_parts(expr: Gc<Maj>) -> Option<(Gc<Maj>, Gc<Maj>, Gc<Maj>)> {
+    use crate::axioms::predicates::maj_proper_list_p;
+    // (for (var generator) . body)
+    let binding = maj_car(maj_cdr(expr.clone()));
+    let body = maj_cdr(maj_cdr(expr));
+    if !maj_proper_list_p(binding.clone()).to_bool()
+        || maj_length(binding.clone()).to_integer() != Some(2)
+    {
+        return None;
+    }
+    let var = maj_car(binding.clone());
+    if !maj_symbolp(var.clone()).to_bool() || maj_nilp(var.clone()).to_bool() {
+        return None;
+    }
+    Some((var, maj_car(maj_cdr(binding)), body))
+}
+
+fn maj_handle_and(mut state: &mut MajState, expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
+    let forms = maj_cdr(expr);
+    let mut iter = forms;
+    let mut result = Maj::t();
+    while !maj_nilp(iter.clone()).to_bool() {
+        let form = maj_car(iter.clone());
+        result = maj_eval(&mut state, form, env.clone());
+        if maj_errorp(result.clone()).to_bool() {
+            return result;
+        }
+        if maj_nilp(result.clone()).to_bool() {
+            return Maj::nil();
+        }
+        iter = maj_cdr(iter.clone());
+    }
+    result
+}
+
+fn maj_handle_or(mut state: &mut MajState, expr: Gc<Maj>, env: Gc<Maj>) -> Gc<Maj> {
+    let forms = maj_cdr(expr);
+    let mut iter = forms;
+    let mut result = Maj::nil();
+    while !maj_nilp(iter.clone()).to_bool() {
+        let form = maj_car(iter.clone());
+        result = maj_eval(&mut state, form, env.clone());
+        if !maj_nilp(result.clone()).to_bool() {
+            if !maj_nilp(maj_cdr(iter)).to_bool() {
+                // Only the last form may return multiple values
+                state.clear_values();
+            }
+            return result;
+        }
+        iter = maj_cdr(iter.clone());
+    }
+    result
+}
+
+pub fn maj_expand_all(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    maj_expand_form(&mut state, expr, env, Maj::nil())
+}
+
+pub fn maj_expand_body(
+    mut state: &mut MajState,
+    lambda_list: Gc<Maj>,
+    body: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    let args = Maj::cons(lambda_list, body);
+    let args = maj_expand_lambda(&mut state, args, env, Maj::nil());
+    maj_cdr(args)
+}
+
+fn maj_expand_form(
+    mut state: &mut MajState,
+    form: Gc<Maj>,
+    env: Gc<Maj>,
+    shadowed: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_consp;
+    use crate::evaluator::application::expand_macro;
+
+    let mut expr = form.clone();
+    while maj_consp(expr.clone()).to_bool() {
+        // Circular forms are left for the evaluator to reject
+        if maj_length(expr.clone()).to_integer().is_none() {
+            break;
+        }
+        if let Some(result) = maj_expand_special(&mut state,
+                                                 expr.clone(),
+                                                 env.clone(),
+                                                 shadowed.clone()) {
+            expr = result;
+            break;
+        }
+
+        let head = maj_car(expr.clone());
+        let mac =
+            if maj_symbolp(head.clone()).to_bool()
+            && !maj_shadowedp(head.clone(), shadowed.clone())
+        {
+            state.lookup(env.clone(), head)
+        } else {
+            head
+        };
+
+        if !maj_macrop(mac.clone()).to_bool() {
+            expr = maj_expand_each(&mut state, expr, env.clone(),
+                                   shadowed.clone());
+            if state.compilingp() {
+                expr = maj_compile_call(&mut state, expr, mac,
+                                        env, shadowed);
+            }
+            break;
+        }
+
+        // Local bindings are visible to hygienic macros
+        let local_env = maj_expand_env(env.clone(), shadowed.clone());
+        let (result, worked) =
+            expand_macro(&mut state, mac, maj_cdr(expr), local_env);
+        if !worked {
+            return form;
+        }
+        expr = result;
+    }
+
+    if !Gc::ptr_eq(&expr, &form)
+        && maj_nilp(state.location(expr.clone())).to_bool()
+    {
+        let location = state.location(form);
+        if !maj_nilp(location.clone()).to_bool() {
+            state.set_location(expr.clone(), location);
+        }
+    }
+    expr
+}
+
+fn maj_expand_special(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>,
+    shadowed: Gc<Maj>
+) -> Option<Gc<Maj>> {
+    let head = maj_car(expr.clone());
+    if !maj_symbolp(head.clone()).to_bool() {
+        return None;
+    }
+
+    let args = maj_cdr(expr.clone());
+    let new_args = match head.symbol_name(&state).as_ref() {
+        "quote" | "quasiquote" | "lit" | "syntax-rules" => args,
+        "if" | "do" | "and" | "or" | "apply" | "while" |
+        "unwind-protect" | "catch" | "set-car" | "set-cdr" |
+        "multiple-value-list" | "yield" =>
+            maj_expand_each(&mut state, args, env, shadowed),
+        // (def sym val), (multiple-value-bind vars form . body)
+        "def" | "defvar" | "defparameter" | "set" |
+        "multiple-value-bind" =>
+            maj_expand_clause(&mut state, args, env, shadowed, 1),
+        // (fn lambda-list . body)
+        "fn" | "mac" =>
+            maj_expand_lambda(&mut state, args, env, shadowed),
+        // (letrec ((sym lambda-list . body) ...) . body)
+        "letrec" =>
+            maj_expand_letrec(&mut state, args, env, shadowed),
+        // (defmethod name lambda-list . body)
+        "defmethod" =>
+            maj_expand_clause(&mut state, args, env, shadowed, 2),
+        // (handler-case expr (type (var) . body) ...)
+        "handler-case" =>
+            maj_expand_clauses(&mut state, args, env, shadowed, 2),
+        // (match expr (pattern . body) ...)
+        "match" =>
+            maj_expand_clauses(&mut state, args, env, shadowed, 1),
+        // (for (var generator) . body)
+        "for" =>
+            maj_expand_for(&mut state, args, env, shadowed),
+        _ => return None,
+    };
+    Some(maj_expand_rebuild(expr, head, new_args))
+}
+
+fn maj_expand_rebuild(
+    cons: Gc<Maj>,
+    car: Gc<Maj>,
+    cdr: Gc<Maj>
+) -> Gc<Maj> {
+    if Gc::ptr_eq(&car, &maj_car(cons.clone()))
+        && Gc::ptr_eq(&cdr, &maj_cdr(cons.clone()))
+    {
+        cons
+    } else {
+        Maj::cons(car, cdr)
+    }
+}
+
+fn maj_expand_map<F>(
+    mut state: &mut MajState,
+    list: Gc<Maj>,
+    f: &mut F
+) -> Gc<Maj>
+where F: FnMut(&mut MajState, Gc<Maj>) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_consp;
+    if !maj_consp(list.clone()).to_bool() {
+        return list;
+    }
+    let car = f(&mut state, maj_car(list.clone()));
+    let cdr = maj_expand_map(&mut state, maj_cdr(list.clone()), f);
+    maj_expand_rebuild(list, car, cdr)
+}
+
+fn maj_expand_each(
+    mut state: &mut MajState,
+    list: Gc<Maj>,
+    env: Gc<Maj>,
+    shadowed: Gc<Maj>
+) -> Gc<Maj> {
+    maj_expand_map(&mut state, list, &mut |state, form| {
+        maj_expand_form(state, form, env.clone(), shadowed.clone())
+    })
+}
+
+fn maj_expand_clause(
+    mut state: &mut MajState,
+    clause: Gc<Maj>,
+    env: Gc<Maj>,
+    shadowed: Gc<Maj>,
+    skip: usize
+) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_consp;
+    if skip == 0 {
+        return maj_expand_each(&mut state, clause, env, shadowed);
+    }
+    if !maj_consp(clause.clone()).to_bool() {
+        return clause;
+    }
+    let head = maj_car(clause.clone());
+    let shadowed = maj_bound_symbols(head.clone(), shadowed);
+    let rest = maj_expand_clause(&mut state, maj_cdr(clause.clone()),
+                                 env, shadowed, skip - 1);
+    maj_expand_rebuild(clause, head, rest)
+}
+
+fn maj_expand_clauses(
+    mut state: &mut MajState,
+    args: Gc<Maj>,
+    env: Gc<Maj>,
+    shadowed: Gc<Maj>,
+    skip: usize
+) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_consp;
+    if !maj_consp(args.clone()).to_bool() {
+        return args;
+    }
+    let expr = maj_expand_form(&mut state, maj_car(args.clone()),
+                               env.clone(), shadowed.clone());
+    let clauses = maj_expand_map(
+        &mut state, maj_cdr(args.clone()),
+        &mut |state, clause| {
+            maj_expand_clause(state, clause, env.clone(),
+                              shadowed.clone(), skip)
+        });
+    maj_expand_rebuild(args, expr, clauses)
+}
+
+fn maj_expand_lambda(
+    mut state: &mut MajState,
+    args: Gc<Maj>,
+    env: Gc<Maj>,
+    shadowed: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_consp;
+    let compiling = state.compilingp();
+    if !maj_consp(args.clone()).to_bool()
+        || (compiling && state.expandedp(maj_cdr(args.clone())))
+    {
+        return args;
+    }
+    let args = maj_expand_clause(&mut state, args, env, shadowed, 1);
+    if compiling {
+        state.mark_expanded(maj_cdr(args.clone()));
+    }
+    args
+}
+
+fn maj_expand_letrec(
+    mut state: &mut MajState,
+    args: Gc<Maj>,
+    env: Gc<Maj>,
+    shadowed: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_consp;
+    if !maj_consp(args.clone()).to_bool() {
+        return args;
+    }
+
+    let bindings = maj_car(args.clone());
+    let mut shadowed = shadowed;
+    let mut iter = bindings.clone();
+    while maj_consp(iter.clone()).to_bool() {
+        let clause = maj_car(iter.clone());
+        if maj_consp(clause.clone()).to_bool() {
+            shadowed = Maj::cons(maj_car(clause), shadowed);
+        }
+        iter = maj_cdr(iter);
+    }
+
+    let new_bindings = maj_expand_map(
+        &mut state, bindings,
+        &mut |state, clause| {
+            if !maj_consp(clause.clone()).to_bool() {
+                return clause;
+            }
+            let lambda = maj_expand_lambda(state, maj_cdr(clause.clone()),
+                                           env.clone(), shadowed.clone());
+            maj_expand_rebuild(clause.clone(), maj_car(clause), lambda)
+        });
+    let body = maj_expand_each(&mut state, maj_cdr(args.clone()),
+                               env, shadowed);
+    maj_expand_rebuild(args, new_bindings, body)
+}
+
+fn maj_expand_for(
+    mut state: &mut MajState,
+    args: Gc<Maj>,
+    env: Gc<Maj>,
+    shadowed: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_consp;
+    let binding = maj_car(args.clone());
+    if !maj_consp(args.clone()).to_bool()
+        || !maj_consp(binding.clone()).to_bool()
+    {
+        return args;
+    }
+    let var = maj_car(binding.clone());
+    let generator = maj_expand_each(&mut state, maj_cdr(binding.clone()),
+                                    env.clone(), shadowed.clone());
+    let binding = maj_expand_rebuild(binding, var.clone(), generator);
+    let shadowed = maj_bound_symbols(var, shadowed);
+    let body = maj_expand_each(&mut state, maj_cdr(args.clone()),
+                               env, shadowed);
+    maj_expand_rebuild(args, binding, body)
+}
+
+pub fn maj_bound_symbols(x: Gc<Maj>, acc: Gc<Maj>) -> Gc<Maj> {
+    use crate::axioms::predicates::maj_consp;
+    if maj_symbolp(x.clone()).to_bool() {
+        Maj::cons(x, acc)
+    } else if maj_consp(x.clone()).to_bool() {
+        let acc = maj_bound_symbols(maj_car(x.clone()), acc);
+        maj_bound_symbols(maj_cdr(x), acc)
+    } else {
+        acc
+    }
+}
+
+pub fn maj_shadowedp(sym: Gc<Maj>, shadowed: Gc<Maj>) -> bool {
+    let mut iter = shadowed;
+    while !maj_nilp(iter.clone()).to_bool() {
+        if maj_eq(maj_car(iter.clone()), sym.clone()).to_bool() {
+            return true;
+        }
+        iter = maj_cdr(iter);
+    }
+    false
+}
+
+fn maj_expand_env(env: Gc<Maj>, shadowed: Gc<Maj>) -> Gc<Maj> {
+    use crate::core::environment::maj_env_push;
+    let mut env = env;
+    let mut iter = shadowed;
+    while !maj_nilp(iter.clone()).to_bool() {
+        env = maj_env_push(env, maj_car(iter.clone()), Maj::nil());
+        iter = maj_cdr(iter);
+    }
+    env
+}
+
+fn maj_compile_call(
+    state: &mut MajState,
+    expr: Gc<Maj>,
+    fun: Gc<Maj>,
+    env: Gc<Maj>,
+    shadowed: Gc<Maj>
+) -> Gc<Maj> {
+    use crate::axioms::predicates::{ maj_closurep, maj_primitivep };
+    use crate::core::environment::maj_env_assoc;
+    let head = maj_car(expr.clone());
+    if !maj_symbolp(head.clone()).to_bool()
+        || maj_shadowedp(head.clone(), shadowed)
+        || state.specialp(head.clone())
+        || !maj_errorp(maj_env_assoc(env, head.clone())).to_bool()
+    {
+        return expr;
+    }
+
+    let declaration = state.inline_declaration(head.clone());
+    let compile = match declaration {
+        Some(false) => false,
+        Some(true)  => maj_closurep(fun.clone()).to_bool()
+            || (maj_primitivep(fun.clone()).to_bool()
+                && maj_compile_arity_p(state, fun.clone(),
+                                       maj_cdr(expr.clone()))),
+        None => maj_primitivep(fun.clone()).to_bool()
+            && maj_eq(maj_car(maj_cdr(maj_cdr(fun.clone()))),
+                      head).to_bool()
+            && maj_compile_arity_p(state, fun.clone(),
+                                   maj_cdr(expr.clone())),
+    };
+
+    if !compile {
+        return expr;
+    }
+    let compiled = Maj::cons(fun, maj_cdr(expr.clone()));
+    state.set_source(compiled.clone(), expr);
+    compiled
+}
+
+fn maj_compile_arity_p(state: &MajState, prim: Gc<Maj>, args: Gc<Maj>) -> bool {
+    use crate::axioms::MajPrimArgs;
+    use crate::axioms::predicates::maj_proper_list_p;
+    use crate::axioms::primitives::maj_last;
+    if !maj_proper_list_p(args.clone()).to_bool() {
+        return false;
+    }
+    let name = maj_car(maj_cdr(maj_cdr(prim)));
+    let argl = maj_length(args.clone()).to_integer().unwrap() as u64;
+    match state.find_primitive(name) {
+        Some((_, MajPrimArgs::None)) => argl == 0,
+        Some((_, MajPrimArgs::Required(n))) => argl == *n,
+        Some((_, MajPrimArgs::Variadic(n))) =>
+            argl >= *n
+            && !maj_eq(maj_last(args), Maj::ampersand()).to_bool(),
+        None => false,
+    }
+}
+
+fn maj_direct_call_p(x: Gc<Maj>) -> bool {
+    use crate::axioms::predicates::maj_consp;
+    if !maj_consp(x.clone()).to_bool() {
+        return false;
+    }
+    let head = maj_car(x);
+    if !maj_consp(head.clone()).to_bool()
+        || !maj_eq(maj_car(head.clone()), Maj::lit()).to_bool()
+    {
+        return false;
+    }
+    let kind = maj_car(maj_cdr(head));
+    maj_eq(kind.clone(), Maj::prim()).to_bool()
+        || maj_eq(kind, Maj::closure()).to_bool()
+}
+
+fn maj_stale_call_p(state: &MajState, expr: Gc<Maj>) -> bool {
+    use crate::axioms::predicates::maj_primitivep;
+    let fun = maj_car(expr);
+    maj_primitivep(fun.clone()).to_bool()
+        && state.redefinedp(maj_car(maj_cdr(maj_cdr(fun))))
+}
+
+fn maj_handle_direct_call(
+    mut state: &mut MajState,
+    expr: Gc<Maj>,
+    env: Gc<Maj>
+) -> Gc<Maj> {
+    if maj_stale_call_p(&state, expr.clone()) {
+        let source = state.source_form(expr.clone());
+        if !Gc::ptr_eq(&source, &expr) {
+            return maj_eval(&mut state, source, env);
+        }
+    }
+    let fun = maj_car(expr.clone());
+    let args = maj_evlist(&mut state, maj_cdr(expr.clone()), env.clone());
+    if maj_errorp(args.clone()).to_bool() {
+        return args;
+    }
+    maj_apply_in_frame(&mut state, expr, fun, args, env)
+}
+
+#[derive(Clone)]
+pub enum MajCoroutineFrame {
+    // Remaining forms of a `do`
+    Do { rest: Gc<Maj>, env: Gc<Maj> },
+    // Branches of an `if`, waiting for its predicate
+    If { conseq: Gc<Maj>, altern: Gc<Maj>, env: Gc<Maj> },
+    // A `while`, waiting for its predicate or for its body
+    While {
+        pred:    Gc<Maj>,
+        body:    Gc<Maj>,
+        env:     Gc<Maj>,
+        result:  Gc<Maj>,
+        testing: bool,
+    },
+    // Remaining forms of an `and` or an `or`
+    And { rest: Gc<Maj>, env: Gc<Maj> },
+    Or { rest: Gc<Maj>, env: Gc<Maj> },
+    // A `def` or `set`, waiting for its value
+    Assign { head: Gc<Maj>, sym: Gc<Maj>, env: Gc<Maj> },
+    // An application, waiting for the value of its next element
+    Apply {
+        form: Gc<Maj>,
+        done: Vec<Gc<Maj>>,
+        rest: Gc<Maj>,
+        env:  Gc<Maj>,
+    },
+    // A `for`, waiting for its generator or for its body
+    ForInit { var: Gc<Maj>, body: Gc<Maj>, env: Gc<Maj> },
+    For {
+        var:       Gc<Maj>,
+        generator: Gc<Maj>,
+        body:      Gc<Maj>,
+        env:       Gc<Maj>,
+    },
+    // A `catch`, waiting for its tag or for its body
+    CatchTag { body: Gc<Maj>, env: Gc<Maj> },
+    Catch { tag: Gc<Maj> },
+    // An `unwind-protect`, waiting for its form or for its cleanup
+    UnwindProtect { cleanup: Gc<Maj>, env: Gc<Maj> },
+    Cleanup { result: Gc<Maj> },
+    // A `handler-case`, waiting for its form
+    HandlerCase { clauses: Gc<Maj>, env: Gc<Maj> },
+    // A `yield`, waiting for the value to be yielded
+    Yield,
+}
+
+enum MajCoroutineStep {
+    Eval(Gc<Maj>, Gc<Maj>),
+    Return(Gc<Maj>),
+}
+
+pub enum MajResume {
+    Yield(Gc<Maj>),
+    Return(Gc<Maj>),
+}
+
+pub fn maj_resume(
+    mut state: &mut MajState,
+    coroutine: Gc<Maj>,
+    args: Gc<Maj>
+) -> MajResume {
+    use crate::core::state::MajCoroutineStatus;
+    use crate::axioms::predicates::maj_coroutinep;
+    use crate::axioms::primitives::maj_arity_err;
+    use MajCoroutineStep::*;
+
+    if !maj_coroutinep(coroutine.clone()).to_bool() {
+        return MajResume::Return(
+            maj_type_err(Maj::string("{} is not a coroutine"),
+                         maj_list!(coroutine)));
+    }
+
+    match state.coroutine_status(coroutine.clone()) {
+        Some(MajCoroutineStatus::Suspended) => {},
+        Some(MajCoroutineStatus::Running) => return MajResume::Return(
+            maj_err(Maj::string("Cannot resume running coroutine {}"),
+                    maj_list!(coroutine))),
+        None => return MajResume::Return(
+            maj_err(Maj::string("Cannot resume dead coroutine {}"),
+                    maj_list!(coroutine))),
+    }
+
+    let (function, started, mut frames) = {
+        let co = state.coroutine_mut(coroutine.clone()).unwrap();
+        if co.started && !maj_nilp(maj_cdr(args.clone())).to_bool() {
+            return MajResume::Return(
+                maj_arity_err(Maj::string("Too many arguments to resume {}"),
+                              maj_list!(coroutine)));
+        }
+        co.status = MajCoroutineStatus::Running;
+        (co.function.clone(), co.started, std::mem::take(&mut co.frames))
+    };
+
+    let step = if started {
+        Return(maj_car(args))
+    } else {
+        match maj_coroutine_enter(&mut state, function, args) {
+            Ok((body, env)) => Eval(Maj::cons(Maj::do_sym(), body), env),
+            Err(result) => {
+                let co = state.coroutine_mut(coroutine.clone()).unwrap();
+                co.status = MajCoroutineStatus::Suspended;
+                return MajResume::Return(
+                    if maj_errorp(result.clone()).to_bool() {
+                        result
+                    } else {
+                        maj_arity_err(
+                            Maj::string("Too few arguments to start {}"),
+                            maj_list!(coroutine))
+                    });
+            },
+        }
+    };
+
+    let result = maj_run_coroutine(&mut state, &mut frames, step);
+    match result {
+        MajResume::Yield(_) => {
+            let co = state.coroutine_mut(coroutine).unwrap();
+            co.started = true;
+            co.status = MajCoroutineStatus::Suspended;
+            co.frames = frames;
+        },
+        MajResume::Return(_) => state.remove_coroutine(coroutine),
+    }
+    result
+}
+
+fn maj_coroutine_enter(
+    mut state: &mut MajState,
+    fun: Gc<Maj>,
+    args: Gc<Maj>
+) -> Result<(Gc<Maj>, Gc<Maj>), Gc<Maj>> {
+    use crate::axioms::predicates::maj_consp;
+    use crate::evaluator::application::maj_apply_closure_with;
+
+    let mut entered = None;
+    let result = maj_apply_closure_with(
+        &mut state, fun.clone(), args,
+        |_, body, extenv, _| {
+            entered = Some((body, extenv));
+            Maj::nil()
+        });
+    let (body, extenv) = match entered {
+        Some(entered) => entered,
+        None => return Err(result),
+    };
+
+    // (lit closure <env> lambda-list . body)
+    let env = maj_car(maj_cdr(maj_cdr(fun)));
+    let mut itr = extenv.clone();
+    while maj_consp(itr.clone()).to_bool() && !Gc::ptr_eq(&itr, &env) {
+        let sym = maj_car(maj_car(itr.clone()));
+        if state.specialp(sym.clone()) {
+            return Err(maj_err(
+                Maj::string("Cannot bind special variable {} in a coroutine"),
+                maj_list!(sym)));
+        }
+        itr = maj_cdr(itr);
+    }
+    Ok((body, extenv))
+}
+
+fn maj_run_coroutine(
+    mut state: &mut MajState,
+    frames: &mut Vec<MajCoroutineFrame>,
+    step: MajCoroutineStep
+) -> MajResume {
+    use MajCoroutineStep::*;
+    let mut step = step;
+    loop {
+        step = match step {
+            Eval(expr, env) =>
+                maj_coroutine_eval(&mut state, frames, expr, env),
+            Return(value) if maj_errorp(value.clone()).to_bool() =>
+                match maj_coroutine_unwind(&mut state, frames, value) {
+                    Ok(step) => step,
+                    Err(error) => return MajResume::Return(error),
+                },
+            Return(value) => {
+                match frames.pop() {
+                    None => return MajResume::Return(value),
+                    Some(MajCoroutineFrame::Yield) =>
+                        return MajResume::Yield(value),
+                    Some(frame) =>
+                        maj_coroutine_continue(&mut state, frames,
+                                               frame, value),
+                }
+            },
+        };
+    }
+}
+
+fn maj_coroutine_unwind(
+    mut state: &mut MajState,
+    frames: &mut Vec<MajCoroutineFrame>,
+    error: Gc<Maj>
+) -> Result<MajCoroutineStep, Gc<Maj>> {
+    use crate::axioms::primitives::maj_condition_irritants;
+    use MajCoroutineFrame::*;
+    use MajCoroutineStep::*;
+    let throw = maj_throwp(&mut state, error.clone()).to_bool();
+    while let Some(frame) = frames.pop() {
+        match frame {
+            Catch { tag } if throw => {
+                // (lit error throw fmt tag value)
+                let irritants = maj_condition_irritants(error.clone());
+                if maj_eq(maj_car(irritants.clone()), tag).to_bool() {
+                    return Ok(Return(maj_car(maj_cdr(irritants))));
+                }
+            },
+            UnwindProtect { cleanup, env } => {
+                frames.push(Cleanup { result: error });
+                return Ok(Eval(cleanup, env));
+            },
+            // Errors on the cleanup form are ignored
+            Cleanup { result } => return Ok(Return(result)),
+            HandlerCase { clauses, env } if !throw => {
+                return Ok(
+                    match maj_handler_case_clause(clauses, error.clone(), env) {
+                        Ok(Some((body, env))) => Eval(body, env),
+                        Ok(None) => Return(error),
+                        Err(error) => Return(error),
+                    });
+            },
+            _ => {},
+        }
+    }
+    Err(error)
+}
+
+fn maj_yieldsp(state: &MajState, expr: Gc<Maj>) -> bool {
+    use crate::axioms::predicates::maj_consp;
+    if !maj_consp(expr.clone()).to_bool() {
+        return false;
+    }
+    let head = maj_car(expr.clone());
+    if maj_symbolp(head.clone()).to_bool() {
+        match head.symbol_name(&state).as_ref() {
+            "yield" => return true,
+            "quote" | "quasiquote" | "lit" | "fn" | "mac" |
+            "syntax-rules" => return false,
+            _ => {},
+        }
+    }
+    let head_yields = match maj_immediate_body(head.clone()) {
+        Some(body) => maj_body_yields_p(state, body),
+        None => maj_yieldsp(state, head),
+    };
+    head_yields || maj_body_yields_p(state, maj_cdr(expr))
+}
+
+fn maj_body_yields_p(state: &MajState, forms: Gc<Maj>) -> bool {
+    use crate::axioms::predicates::maj_consp;
+    let mut itr = forms;
+    while maj_consp(itr.clone()).to_bool() {
+        if maj_yieldsp(state, maj_car(itr.clone())) {
+            return true;
+        }
+        itr = maj_cdr(itr);
+    }
+    false
+}
+
+fn maj_immediate_body(head: Gc<Maj>) -> Option<Gc<Maj>> {
+    use crate::axioms::predicates::maj_consp;
+    // ((fn lambda-list . body) . args)
+    if maj_consp(head.clone()).to_bool()
+        && maj_eq(maj_car(head.clone()), Maj::fn_sym()).to_bool()
+    {
+        Some(maj_cdr(maj_cdr(head)))
+    } else {
+        None
+    }
+}
+
+fn maj_coroutine_eval(
+    mut state: &mut MajState,
+    frames: &mut Vec<MajCoroutineFrame>,
+    expr: Gc<Maj>,
+    env: Gc<Maj>
+) -> MajCoroutineStep {
+    use MajCoroutineFrame::*;
+    use MajCoroutineStep::*;
+
+    let expr = if maj_direct_call_p(expr.clone())
+        && maj_stale_call_p(&state, expr.clone())
+    {
+        state.source_form(expr)
+    } else {
+        expr
+    };
+
+    if !maj_yieldsp(&state, expr.clone()) {
+        return Return(maj_eval(&mut state, expr, env));
+    }
+
+    let head = maj_car(expr.clone());
+    let args = maj_cdr(expr.clone());
+    let length = maj_length(expr.clone()).to_integer().unwrap_or(0);
+    let fail = |state: &mut MajState, error: Gc<Maj>| {
+        Return(maj_error_contextualize(state, error, expr.clone()))
+    };
+    let invalid = || maj_err(Maj::string("Invalid syntax: {}"),
+                             maj_list!(expr.clone()));
+
+    let name =
+        if maj_symbolp(head.clone()).to_bool() {
+            head.symbol_name(&state)
+        } else {
+            String::new()
+        };
+    match name.as_ref() {
+        "yield" => {
+            if length > 2 {
+                return fail(&mut state, invalid());
+            }
+            frames.push(Yield);
+            Eval(maj_car(args), env)
+        },
+        "do" => maj_coroutine_sequence(frames, args, env),
+        "if" => {
+            if length != 4 {
+                return fail(&mut state, invalid());
+            }
+            frames.push(If {
+                conseq: maj_car(maj_cdr(args.clone())),
+                altern: maj_car(maj_cdr(maj_cdr(args.clone()))),
+                env:    env.clone(),
+            });
+            Eval(maj_car(args), env)
+        },
+        "while" => {
+            if length < 2 {
+                return fail(&mut state, invalid());
+            }
+            let pred = maj_car(args.clone());
+            frames.push(While {
+                pred:    pred.clone(),
+                body:    Maj::cons(Maj::do_sym(), maj_cdr(args)),
+                env:     env.clone(),
+                result:  Maj::nil(),
+                testing: true,
+            });
+            Eval(pred, env)
+        },
+        "and" | "or" => {
+            if maj_nilp(args.clone()).to_bool() {
+                return Return(if name == "and" { Maj::t() } else { Maj::nil() });
+            }
+            let rest = maj_cdr(args.clone());
+            frames.push(if name == "and" {
+                And { rest, env: env.clone() }
+            } else {
+                Or { rest, env: env.clone() }
+            });
+            Eval(maj_car(args), env)
+        },
+        "def" | "set" => {
+            if length != 3 {
+                return fail(&mut state, invalid());
+            }
+            frames.push(Assign {
+                head,
+                sym: maj_car(args.clone()),
+                env: env.clone(),
+            });
+            Eval(maj_car(maj_cdr(args)), env)
+        },
+        "for" => match maj_for_parts(expr.clone()) {
+            Some((var, generator, body)) => {
+                frames.push(ForInit { var, body, env: env.clone() });
+                Eval(generator, env)
+            },
+            None => fail(&mut state, invalid()),
+        },
+        "catch" => {
+            if length < 2 {
+                return fail(&mut state, invalid());
+            }
+            frames.push(CatchTag {
+                body: Maj::cons(Maj::do_sym(), maj_cdr(args.clone())),
+                env:  env.clone(),
+            });
+            Eval(maj_car(args), env)
+        },
+        "unwind-protect" => {
+            if length != 3 {
+                return fail(&mut state, invalid());
+            }
+            frames.push(UnwindProtect {
+                cleanup: maj_car(maj_cdr(args.clone())),
+                env:     env.clone(),
+            });
+            Eval(maj_car(args), env)
+        },
+        "handler-case" => {
+            if length < 2 {
+                return fail(&mut state, invalid());
+            }
+            frames.push(HandlerCase {
+                clauses: maj_cdr(args.clone()),
+                env:     env.clone(),
+            });
+            Eval(maj_car(args), env)
+        },
+        "mac" | "apply" | "letrec" | "match" | "multiple-value-list" |
+        "multiple-value-bind" | "defvar" | "defparameter" |
+        "set-car" | "set-cdr" | "defmethod" => {
+            let error = maj_err(Maj::string("Cannot yield inside {}"),
+                                maj_list!(head));
+            fail(&mut state, error)
+        },
+        _ => {
+            frames.push(Apply {
+                form: expr.clone(),
+                done: Vec::new(),
+                rest: args,
+                env:  env.clone(),
+            });
+            Eval(head, env)
+        },
+    }
+}
+
+fn maj_coroutine_sequence(
+    frames: &mut Vec<MajCoroutineFrame>,
+    forms: Gc<Maj>,
+    env: Gc<Maj>
+) -> MajCoroutineStep {
+    if maj_nilp(forms.clone()).to_bool() {
+        return MajCoroutineStep::Return(Maj::nil());
+    }
+    let rest = maj_cdr(forms.clone());
+    if !maj_nilp(rest.clone()).to_bool() {
+        frames.push(MajCoroutineFrame::Do { rest, env: env.clone() });
+    }
+    MajCoroutineStep::Eval(maj_car(forms), env)
+}
+
+fn maj_coroutine_continue(
+    mut state: &mut MajState,
+    frames: &mut Vec<MajCoroutineFrame>,
+    frame: MajCoroutineFrame,
+    value: Gc<Maj>
+) -> MajCoroutineStep {
+    use crate::axioms::predicates::{ maj_consp, maj_coroutinep };
+    use MajCoroutineFrame::*;
+    use MajCoroutineStep::*;
+
+    match frame {
+        Do { rest, env } => maj_coroutine_sequence(frames, rest, env),
+        If { conseq, altern, env } =>
+            if maj_nilp(value).to_bool() {
+                Eval(altern, env)
+            } else {
+                Eval(conseq, env)
+            },
+        While { pred, body, env, result, testing } =>
+            if !testing {
+                frames.push(While {
+                    pred: pred.clone(), body, env: env.clone(),
+                    result: value, testing: true,
+                });
+                Eval(pred, env)
+            } else if maj_nilp(value).to_bool() {
+                Return(result)
+            } else {
+                frames.push(While {
+                    pred, body: body.clone(), env: env.clone(),
+                    result, testing: false,
+                });
+                Eval(body, env)
+            },
+        And { rest, env } =>
+            if maj_nilp(value.clone()).to_bool()
+                || maj_nilp(rest.clone()).to_bool()
+            {
+                Return(value)
+            } else {
+                frames.push(And { rest: maj_cdr(rest.clone()),
+                                  env: env.clone() });
+                Eval(maj_car(rest), env)
+            },
+        Or { rest, env } =>
+            if !maj_nilp(value.clone()).to_bool()
+                || maj_nilp(rest.clone()).to_bool()
+            {
+                Return(value)
+            } else {
+                frames.push(Or { rest: maj_cdr(rest.clone()),
+                                 env: env.clone() });
+                Eval(maj_car(rest), env)
+            },
+        Assign { head, sym, env } => {
+            let form = maj_list!(head, sym, maj_list!(Maj::quote(), value));
+            Return(maj_eval(&mut state, form, env))
+        },
+        Apply { form, mut done, rest, env } => {
+            done.push(value);
+            if maj_consp(rest.clone()).to_bool() {
+                let next = maj_car(rest.clone());
+                frames.push(Apply { form, done, rest: maj_cdr(rest),
+                                    env: env.clone() });
+                Eval(next, env)
+            } else {
+                maj_coroutine_apply(&mut state, form, done, env)
+            }
+        },
+        ForInit { var, body, env } =>
+            if maj_coroutinep(value.clone()).to_bool() {
+                maj_coroutine_for(&mut state, frames, var, value, body, env)
+            } else {
+                Return(maj_type_err(Maj::string("{} is not a coroutine"),
+                                    maj_list!(value)))
+            },
+        For { var, generator, body, env } =>
+            maj_coroutine_for(&mut state, frames, var, generator, body, env),
+        CatchTag { body, env } => {
+            frames.push(Catch { tag: value });
+            Eval(body, env)
+        },
+        UnwindProtect { cleanup, env } => {
+            frames.push(Cleanup { result: value });
+            Eval(cleanup, env)
+        },
+        Cleanup { result } => Return(result),
+        Catch { .. } | HandlerCase { .. } | Yield => Return(value),
+    }
+}
+
+fn maj_coroutine_apply(
+    mut state: &mut MajState,
+    form: Gc<Maj>,
+    done: Vec<Gc<Maj>>,
+    env: Gc<Maj>
+) -> MajCoroutineStep {
+    use crate::axioms::predicates::maj_closurep;
+    let fun = done[0].clone();
+    let mut args = Maj::nil();
+    for arg in done[1..].iter().rev() {
+        args = Maj::cons(arg.clone(), args);
+    }
+
+    let immediate = maj_immediate_body(maj_car(form.clone()))
+        .map_or(false, |body| maj_body_yields_p(&state, body));
+    if immediate && maj_closurep(fun.clone()).to_bool() {
+        return match maj_coroutine_enter(&mut state, fun, args) {
+            Ok((body, extenv)) =>
+                MajCoroutineStep::Eval(Maj::cons(Maj::do_sym(), body),
+                                       extenv),
+            Err(result) => MajCoroutineStep::Return(
+                maj_error_contextualize(&mut state, result, form)),
+        };
+    }
+
+    if maj_macrop(fun.clone()).to_bool() {
+        let error = maj_err(Maj::string("Cannot yield inside {}"),
+                            maj_list!(maj_car(form.clone())));
+        return MajCoroutineStep::Return(
+            maj_error_contextualize(&mut state, error, form));
+    }
+
+    MajCoroutineStep::Return(
+        maj_apply_in_frame(&mut state, form, fun, args, env))
+}
+
+fn maj_coroutine_for(
+    mut state: &mut MajState,
+    frames: &mut Vec<MajCoroutineFrame>,
+    var: Gc<Maj>,
+    generator: Gc<Maj>,
+    body: Gc<Maj>,
+    env: Gc<Maj>
+) -> MajCoroutineStep {
+    use crate::core::environment::maj_env_push;
+    match maj_resume(&mut state, generator.clone(), Maj::nil()) {
+        MajResume::Yield(value) => {
+            if state.specialp(var.clone()) {
+                return MajCoroutineStep::Return(maj_err(
+                    Maj::string("Cannot bind special variable {} in a coroutine"),
+                    maj_list!(var)));
+            }
+            let extenv = maj_env_push(env.clone(), var.clone(), value);
+            frames.push(MajCoroutineFrame::For {
+                var, generator, body: body.clone(), env,
+            });
+            MajCoroutineStep::Eval(Maj::cons(Maj::do_sym(), body), extenv)
+        },
+        MajResume::Return(value) => MajCoroutineStep::Return(
+            if maj_errorp(value.clone()).to_bool() {
+                value
+            } else {
+                Maj::nil()
+            }),
+    }
+}
+
+fn maj_is_selfeval(state: &MajState, x: Gc<Maj>) -> bool {
+    maj_literalp(x.clone()).to_bool()
+        || maj_nilp(x.clone()).to_bool()
+        || maj_eq(x.clone(), Maj::t()).to_bool()
+        || maj_eq(x.clone(), Maj::ampersand()).to_bool()
+        || maj_eq(x.clone(), Maj::apply()).to_bool()
+        || maj_numberp(x.clone()).to_bool()
+        || maj_charp(x.clone()).to_bool()
+        || maj_streamp(x.clone()).to_bool()
+        || maj_vectorp(x.clone()).to_bool()
+        || maj_keywordp(state, x).to_bool()
+}
+
+pub fn maj_quotep(x: Gc<Maj>) -> Gc<Maj> {
+    maj_eq(maj_car(x), Maj::quote())
+}
+
+pub fn maj_quasiquotep(x: Gc<Maj>) -> Gc<Maj> {
+    maj_eq(maj_car(x), Maj::quasiquote())
+}
+
+pub fn maj_unquotep(x: Gc<Maj>) -> Gc<Maj> {
+    maj_eq(maj_car(x), Maj::unquote())
+}
+
+pub fn maj_unquote_splice_p(x: Gc<Maj>) -> Gc<Maj> {
+    maj_eq(maj_car(x), Maj::unquote_splice())
+}
+
+fn maj_syntax_rules_p(x: Gc<Maj>) -> Gc<Maj> {
+    maj_eq(maj_car(x), Maj::syntax_rules())
+}
+
+fn maj_defp(mut state: &mut MajState,
+            x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "def"))
+}
+
+fn maj_multiple_value_list_p(mut state: &mut MajState,
+                             x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "multiple-value-list"))
+}
+
+fn maj_multiple_value_bind_p(mut state: &mut MajState,
+                             x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "multiple-value-bind"))
+}
+
+fn maj_matchp(mut state: &mut MajState,
+              x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "match"))
+}
+
+fn maj_defmethodp(mut state: &mut MajState,
+                  x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "defmethod"))
+}
+
+fn maj_yieldp(mut state: &mut MajState,
+              x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "yield"))
+}
+
+fn maj_forp(mut state: &mut MajState,
+            x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "for"))
+}
+
+fn maj_defvarp(mut state: &mut MajState,
+               x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "defvar"))
+}
+
+fn maj_defparameterp(mut state: &mut MajState,
+                     x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "defparameter"))
+}
+
+fn maj_setp(mut state: &mut MajState,
+            x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "set"))
+}
+
+fn maj_set_car_p(mut state: &mut MajState,
+            x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "set-car"))
+}
+
+fn maj_set_cdr_p(mut state: &mut MajState,
+            x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "set-cdr"))
+}
+
+fn maj_dop(mut state: &mut MajState,
            x: Gc<Maj>) -> Gc<Maj> {
     let car = maj_car(x);
     maj_eq(car, Maj::symbol(&mut state, "do"))
@@ -832,3 +2628,27 @@ fn maj_unwind_protect_p(mut state: &mut MajState,
     let car = maj_car(x);
     maj_eq(car, Maj::symbol(&mut state, "unwind-protect"))
 }
+
+#[inline]
+fn maj_catchp(mut state: &mut MajState,
+              x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "catch"))
+}
+
+#[inline]
+fn maj_handler_case_p(mut state: &mut MajState,
+                      x: Gc<Maj>) -> Gc<Maj> {
+    let car = maj_car(x);
+    maj_eq(car, Maj::symbol(&mut state, "handler-case"))
+}
+
+fn maj_throwp(mut state: &mut MajState,
+              x: Gc<Maj>) -> Gc<Maj> {
+    use crate::axioms::primitives::maj_condition_type;
+    if !maj_errorp(x.clone()).to_bool() {
+        return Maj::nil();
+    }
+    maj_eq(maj_condition_type(x),
+           Maj::symbol(&mut state, "throw"))
+}
@@ -1,4 +1,5 @@
 #![feature(seek_stream_len)]
+#![cfg_attr(test, feature(test))]
 
 pub mod core;
 pub mod axioms;
@@ -12,8 +13,13 @@ mod tests;
 use self::core::{ Maj, MajState };
 use self::printing::{ maj_format, maj_format_raw };
 use self::evaluator::maj_eval;
-use self::reader::tokenizer::maj_tokenize;
-use self::reader::parser::maj_parse;
+use self::reader::tokenizer::{
+    maj_tokenize,
+    maj_input_status,
+    MajInputStatus,
+    MajReadError
+};
+use self::reader::parser::maj_read_text;
 
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
@@ -27,7 +33,9 @@ use colored::*;
 use std::{ env, process };
 
 #[derive(Completer, Helper, Highlighter, Hinter)]
-struct MajInputValidator {}
+struct MajInputValidator {
+    dispatch: Vec<char>,
+}
 
 impl Validator for MajInputValidator {
     fn validate(
@@ -40,56 +48,25 @@ impl Validator for MajInputValidator {
             Valid
         };
 
-        let input = ctx.input();
-        let mut ignore_one = false;
-        let mut count = 0;
-        for c in input.chars() {
-            if ignore_one {
-                ignore_one = false;
-            } else {
-                match c {
-                    '(' => {
-                        count += 1;
-                    },
-                    ')' => {
-                        if count == 0 {
-                            count = -1;
-                            break;
-                        } else {
-                            count -= 1;
-                        }
-                    },
-                    '\\' => {
-                        // Gambiarra for preventing
-                        // incomplete input on characters
-                        // such as #\( and #\)
-                        ignore_one = true;
-                    }
-                    _ => {},
-                }
-            }
-        }
-
-        if count > 0 {
-            Ok(Incomplete)
-        } else if count < 0 {
-            Ok(
+        match maj_input_status(ctx.input(), &self.dispatch) {
+            MajInputStatus::Incomplete => Ok(Incomplete),
+            MajInputStatus::Unbalanced => Ok(
                 Invalid(Some("No matching parenthesis found"
                              .to_owned()))
-            )
-        } else {
-            Ok(Valid(None))
+            ),
+            MajInputStatus::Complete => Ok(Valid(None)),
         }
     }
 }
 
 fn repl(mut state: &mut MajState, options: &ArgsOptions) {
-    use crate::axioms::predicates::maj_errorp;
     if !options.silent {
         println!("Press C-c or C-d to quit");
     }
 
-    let validator = MajInputValidator {};
+    let validator = MajInputValidator {
+        dispatch: Vec::new(),
+    };
 
     let mut rl = Editor::new();
     rl.set_helper(Some(validator));
@@ -103,6 +80,9 @@ fn repl(mut state: &mut MajState, options: &ArgsOptions) {
     let mut show_tokens = false;
 
     loop {
+        if let Some(validator) = rl.helper_mut() {
+            validator.dispatch = state.dispatch_chars();
+        }
         let readline = rl.readline(&prompt);
         match readline {
             Ok(line) => {
@@ -118,59 +98,50 @@ fn repl(mut state: &mut MajState, options: &ArgsOptions) {
                             format!("{}", "> ".green())
                         };
                 } else {
-                    //use crate::printing::maj_pretty_format;
-                    match maj_tokenize(line.as_ref()) {
-                        Ok(tokens) => {
+                    if show_tokens {
+                        if let Ok(tokens) = maj_tokenize(line.as_ref()) {
+                            println!("{}",
+                                     format!("{:?}", tokens)
+                                     .magenta());
+                        }
+                    }
+                    match maj_read_text(&mut state, line.as_ref()) {
+                        Ok(expressions) => {
                             if show_tokens {
                                 println!("{}",
-                                         format!("{:?}", tokens)
-                                         .magenta());
+                                         maj_format_raw(
+                                             &state,
+                                             expressions.clone(),
+                                             false).magenta());
                             }
-                            match maj_parse(&mut state,
-                                                  tokens.clone()) {
-                                Ok(expressions) => {
-                                    if show_tokens {
-                                        println!("{}",
-                                                 maj_format_raw(
-                                                     &state,
-                                                     expressions.clone(),
-                                                     false).magenta());
-                                    }
-                                    use crate::axioms::utils::{
-                                        STACK_RED_ZONE,
-                                        STACK_PER_RECURSION
-                                    };
-                                    let results =
-                                        stacker::maybe_grow(
-                                            STACK_RED_ZONE,
-                                            STACK_PER_RECURSION,
-                                            || maj_eval(&mut state,
-                                                        Maj::cons(
-                                                            Maj::do_sym(),
-                                                            expressions),
-                                                        Maj::nil()));
-                                            if !maj_errorp(results.clone()).to_bool() {
-                                                println!("{}",
-                                                         maj_format(&state, results)
-                                                         .cyan());
-                                            } else {
-                                                eprintln!("{} {}",
-                                                          "Error:".red().bold(),
-                                                          maj_format(&state, results));
-                                            }
-                                            
-                                        },
-                                    Err(msg) =>
-                                        eprintln!("{} {}",
-                                                  "Parser error:".red().bold(),
-                                                  msg),
-                                }
-                            },
-                            Err((line, msg)) =>
-                                eprintln!("{} on line {}: {}",
-                                          "Syntax error".red().bold(),
-                                          line, msg),
-                        };
+                            use crate::axioms::utils::{
+                                STACK_RED_ZONE,
+                                STACK_PER_RECURSION
+                            };
+                            let results =
+                                stacker::maybe_grow(
+                                    STACK_RED_ZONE,
+                                    STACK_PER_RECURSION,
+                                    || maj_eval(&mut state,
+                                                Maj::cons(
+                                                    Maj::do_sym(),
+                                                    expressions),
+                                                Maj::nil()));
+                            report_result(&mut state, results,
+                                          options.backtrace);
+                        },
+                        Err(MajReadError::Lexical(line, msg)) =>
+                            eprintln!("{} on line {}: {}",
+                                      "Syntax error".red().bold(),
+                                      line, msg),
+                        Err(MajReadError::Syntax(msg)) =>
+                            eprintln!("{} {}",
+                                      "Parser error:".red().bold(),
+                                      msg),
+                        Err(MajReadError::Source(err)) =>
+                            report_result(&mut state, err,
+                                          options.backtrace),
+                    };
                     }
                 },
                 Err(ReadlineError::Interrupted) => {
@@ -208,6 +179,7 @@ struct ArgsOptions {
     quit:        bool,
     execlines:   Vec<String>,
     showhelp:    bool,
+    backtrace:   usize,
 }
 
 impl ArgsOptions {
@@ -219,6 +191,7 @@ impl ArgsOptions {
             quit:        false,
             execlines:   Vec::new(),
             showhelp:    false,
+            backtrace:   10,
         }
     }
 
@@ -227,6 +200,7 @@ impl ArgsOptions {
 
         let mut fetch_load        = false;
         let mut fetch_exec        = false;
+        let mut fetch_backtrace   = false;
         let mut fetch_programname = true;
 
         for argument in env::args() {
@@ -239,6 +213,13 @@ impl ArgsOptions {
             } else if fetch_exec {
                 options.execlines.push(argument);
                 fetch_exec = false;
+            } else if fetch_backtrace {
+                options.backtrace = match argument.parse() {
+                    Ok(depth) => depth,
+                    Err(_) => return Err(
+                        format!("Invalid backtrace depth: {}", argument)),
+                };
+                fetch_backtrace = false;
             } else {
                 match argument.as_ref() {
                     "--load" | "-l" => fetch_load = true,
@@ -252,6 +233,7 @@ impl ArgsOptions {
                         options.quit = true;
                         fetch_load = true;
                     },
+                    "--backtrace-depth" => fetch_backtrace = true,
                     _ => return Err(
                         format!("Unknown argument: {}", argument)),
                 }
@@ -270,6 +252,94 @@ static VERSION: &'static str = include_str!(
 static TARGET: &'static str = include_str!(
     concat!(env!("OUT_DIR"), "/target.txt"));
 
+fn format_location(state: &MajState, location: gc::Gc<Maj>) -> String {
+    use crate::axioms::primitives::{ maj_car, maj_cdr };
+    let file = maj_car(location.clone())
+        .stringify()
+        .unwrap_or_default();
+    let line = maj_car(maj_cdr(location.clone()));
+    let column = maj_car(maj_cdr(maj_cdr(location)));
+    format!("{}:{}:{}", file,
+            maj_format(&state, line),
+            maj_format(&state, column))
+}
+
+fn report_error(state: &MajState, error: gc::Gc<Maj>, depth: usize) {
+    use crate::axioms::predicates::{ maj_eq, maj_nilp };
+    use crate::axioms::primitives::{
+        maj_car,
+        maj_cdr,
+        maj_length,
+        maj_condition_type,
+        maj_condition_form,
+        maj_condition_location,
+        maj_condition_backtrace
+    };
+    let kind = maj_condition_type(error.clone());
+    let form = maj_condition_form(error.clone());
+    let location = maj_condition_location(error.clone());
+    let backtrace = maj_condition_backtrace(error.clone());
+    let header =
+        if maj_eq(kind.clone(), Maj::error()).to_bool() {
+            String::from("Error:")
+        } else {
+            format!("Error ({}):", maj_format(&state, kind))
+        };
+    eprintln!("{} {}", header.red().bold(),
+              maj_format(&state, error));
+    if !maj_nilp(form.clone()).to_bool() {
+        eprintln!("  {} {}", "in".dimmed(),
+                  maj_format(&state, form));
+    }
+    if !maj_nilp(location.clone()).to_bool() {
+        eprintln!("  {} {}", "at".dimmed(),
+                  format_location(&state, location));
+    }
+    if depth == 0 || maj_nilp(backtrace.clone()).to_bool() {
+        return;
+    }
+    eprintln!("{}", "Backtrace:".dimmed());
+    let mut itr = backtrace.clone();
+    let mut index = 0;
+    while !maj_nilp(itr.clone()).to_bool() && index < depth {
+        let frame = maj_car(itr.clone());
+        let call = Maj::cons(maj_car(frame.clone()),
+                             maj_car(maj_cdr(frame.clone())));
+        let location = maj_car(maj_cdr(maj_cdr(frame)));
+        if maj_nilp(location.clone()).to_bool() {
+            eprintln!("  {}: {}", index, maj_format(&state, call));
+        } else {
+            eprintln!("  {}: {} {} {}", index,
+                      maj_format(&state, call),
+                      "at".dimmed(),
+                      format_location(&state, location));
+        }
+        itr = maj_cdr(itr);
+        index += 1;
+    }
+    if let Some(total) = maj_length(backtrace).to_integer() {
+        let remaining = total - index as i64;
+        if remaining > 0 {
+            eprintln!("  {}", format!("... {} more frame(s)",
+                                      remaining).dimmed());
+        }
+    }
+}
+
+fn report_result(mut state: &mut MajState, result: gc::Gc<Maj>, depth: usize) {
+    use crate::axioms::predicates::maj_errorp;
+    use crate::printing::{ maj_pretty_format, maj_with_print_objects };
+    if maj_errorp(result.clone()).to_bool() {
+        report_error(&state, result, depth);
+        return;
+    }
+    match maj_with_print_objects(&mut state, result.clone(),
+                                 |state| maj_pretty_format(state, result)) {
+        Ok(string) => println!("{}", string.cyan()),
+        Err(error) => report_error(&state, error, depth),
+    }
+}
+
 fn handle_load_file(options: &ArgsOptions, mut state: &mut MajState) {
     use crate::axioms::primitives::maj_load;
     use crate::axioms::predicates::maj_errorp;
@@ -280,8 +350,7 @@ fn handle_load_file(options: &ArgsOptions, mut state: &mut MajState) {
         let results =
             maj_load(&mut state, Maj::nil(), Maj::string(file));
         if maj_errorp(results.clone()).to_bool() {
-            eprintln!("{} {}", "Error:".red().bold(),
-                      maj_format(&state, results));
+            report_error(&state, results, options.backtrace);
             if options.quit {
                 process::exit(1);
             }
@@ -291,26 +360,24 @@ fn handle_load_file(options: &ArgsOptions, mut state: &mut MajState) {
 
 fn handle_exec_evals(options: &ArgsOptions, mut state: &mut MajState) {
     for codeline in &options.execlines {
-        match maj_tokenize(codeline.as_ref()) {
-            Ok(tokens) => {
-                match maj_parse(&mut state, tokens.clone()) {
-                    Ok(expressions) => {
-                        let results =
-                            maj_eval(&mut state,
-                                     Maj::cons(Maj::do_sym(),
-                                               expressions),
-                                     Maj::nil());
-                        println!("{}", maj_format(&state, results)
-                                 .cyan());
-                    },
-                    Err(msg) => {
-                        eprintln!("Parser error: {}", msg)
-                    },
-                }
+        match maj_read_text(&mut state, codeline.as_ref()) {
+            Ok(expressions) => {
+                let results =
+                    maj_eval(&mut state,
+                             Maj::cons(Maj::do_sym(),
+                                       expressions),
+                             Maj::nil());
+                report_result(&mut state, results,
+                              options.backtrace);
             },
-            Err((line, msg)) =>
+            Err(MajReadError::Lexical(line, msg)) =>
                 eprintln!("Syntax error on line {}: {}",
                           line, msg),
+            Err(MajReadError::Syntax(msg)) => {
+                eprintln!("Parser error: {}", msg)
+            },
+            Err(MajReadError::Source(err)) =>
+                report_result(&mut state, err, options.backtrace),
         }
     }
 }
@@ -327,7 +394,10 @@ Options:
 \t                    commands given through arguments
 \t-e, --eval [text]   Evaluate given string of text
 \t-h, -?, --help      Show this help text
-\t--script [file]     Same as --silent --quit --load [file]",
+\t--script [file]     Same as --silent --quit --load [file]
+\t--backtrace-depth [n]
+\t                    Show at most n frames of backtrace on
+\t                    uncaught errors (default: 10)",
              programname);
 }
 
@@ -1,13 +1,21 @@
 use std::fmt::Write;
+use std::collections::{ HashMap, HashSet };
 use gc::Gc;
 use crate::core::{ Maj, MajState };
-use crate::core::types::MajStream;
+use crate::core::types::{ MajStream, MajNumber };
 use crate::axioms::predicates::{
+    maj_eq,
     maj_nilp,
-    maj_stringp,
     maj_closurep,
     maj_primitivep,
-    maj_macrop
+    maj_macrop,
+    maj_conditionp,
+    maj_continuationp,
+    maj_recordp,
+    maj_genericp,
+    maj_next_method_p,
+    maj_coroutinep,
+    maj_readerp
 };
 use crate::axioms::primitives::{
     maj_car,
@@ -22,15 +30,42 @@ pub fn maj_format_raw(
     state: &MajState,
     obj: Gc<Maj>,
     format_read_macros: bool
+) -> String {
+    let mut labels = maj_print_labels(state, obj.clone(), format_read_macros);
+    maj_format_labeled(state, obj, format_read_macros, &mut labels)
+}
+
+fn maj_format_labeled(
+    state: &MajState,
+    obj: Gc<Maj>,
+    rm: bool,
+    labels: &mut MajPrintLabels
+) -> String {
+    // Compiled calls are shown as the forms they were compiled from
+    let obj = state.source_form(obj);
+    match labels.visit(&obj) {
+        MajLabelUse::Reference(label) => format!("#{}#", label),
+        MajLabelUse::Define(label) =>
+            format!("#{}={}", label,
+                    maj_format_object(state, obj, rm, labels)),
+        MajLabelUse::Plain => maj_format_object(state, obj, rm, labels),
+    }
+}
+
+fn maj_format_object(
+    state: &MajState,
+    obj: Gc<Maj>,
+    rm: bool,
+    labels: &mut MajPrintLabels
 ) -> String {
     match &*obj.clone() {
         Maj::Sym(_) => obj.symbol_name(&state),
         Maj::Cons { car: _, cdr: _ } =>
-            maj_format_list(&state, obj, format_read_macros),
+            maj_format_list(&state, obj, rm, labels),
         Maj::Char(c) => maj_format_char(*c),
         Maj::Stream(s) => maj_format_stream(s, obj),
         Maj::Vector(_) =>
-            maj_format_vector(&state, obj, format_read_macros),
+            maj_format_vector(&state, obj, rm, labels),
         _ => format!("{}", obj)
     }
 }
@@ -40,20 +75,52 @@ pub fn maj_format_char(c: char) -> String {
     format!("#\\{}",
             match c {
                 '\x07' => "␇",
-                ' ' => "space",
-                '\n' => "newline",
-                '\t' => "tab",
+                ' '    => "space",
+                '\n'   => "newline",
+                '\t'   => "tab",
+                '\r'   => "return",
+                '\x1b' => "escape",
+                '\0'   => "nul",
+                '\x08' => "backspace",
+                '\x7f' => "delete",
                 _ => {
-                    f = format!("{}", c);
+                    // The symbol for bell is also the name of
+                    // the bell character
+                    f = if c.is_control() || c == '␇' {
+                        format!("x{:x}", c as u32)
+                    } else {
+                        format!("{}", c)
+                    };
                     &f
                 },
             })
 }
 
+pub fn maj_format_string(text: &str) -> String {
+    let mut buffer = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"'  => buffer.push_str("\\\""),
+            '\\' => buffer.push_str("\\\\"),
+            '\n' => buffer.push_str("\\n"),
+            '\t' => buffer.push_str("\\t"),
+            '\r' => buffer.push_str("\\r"),
+            '\0' => buffer.push_str("\\0"),
+            c if c.is_control() => {
+                write!(&mut buffer, "\\x{:x};", c as u32).unwrap();
+            },
+            c => buffer.push(c),
+        }
+    }
+    buffer.push('"');
+    buffer
+}
+
 fn maj_format_list(
     state: &MajState,
     list: Gc<Maj>,
-    rm: bool
+    rm: bool,
+    labels: &mut MajPrintLabels
 ) -> String {
     use crate::axioms::predicates::maj_errorp;
     use crate::evaluator::evaluation::{
@@ -63,31 +130,32 @@ fn maj_format_list(
         maj_unquote_splice_p
     };
 
-    if rm {
+    // Read macros are not abbreviated when their argument is labeled
+    if rm && !labels.sharedp(&maj_cdr(list.clone())) {
         if maj_quotep(list.clone()).to_bool() {
             return format!(
-                "'{}", maj_format_raw(
+                "'{}", maj_format_labeled(
                     state,
                     maj_car(maj_cdr(list)),
-                    rm));
+                    rm, labels));
         } else if maj_quasiquotep(list.clone()).to_bool() {
             return format!(
-                "`{}", maj_format_raw(
+                "`{}", maj_format_labeled(
                     state,
                     maj_car(maj_cdr(list)),
-                    rm));
+                    rm, labels));
         } else if maj_unquotep(list.clone()).to_bool() {
             return format!(
-                ",{}", maj_format_raw(
+                ",{}", maj_format_labeled(
                     state,
                     maj_car(maj_cdr(list)),
-                    rm));
+                    rm, labels));
         } else if maj_unquote_splice_p(list.clone()).to_bool() {
             return format!(
-                ",@{}", maj_format_raw(
+                ",@{}", maj_format_labeled(
                     state,
                     maj_car(maj_cdr(list)),
-                    rm));
+                    rm, labels));
         }
     }
     
@@ -103,6 +171,28 @@ fn maj_format_list(
     } else if maj_macrop(list.clone()).to_bool() && rm {
         // Handle macros
         return maj_format_macro(&state, list, rm);
+    } else if maj_conditionp(list.clone()).to_bool() && rm {
+        // Handle conditions
+        return maj_format_condition(&state, list, rm);
+    } else if maj_continuationp(list.clone()).to_bool() && rm {
+        // Handle continuations
+        return format!("#<continuation {{{:p}}}>",
+                       Gc::into_raw(list));
+    } else if maj_coroutinep(list.clone()).to_bool() && rm {
+        // Handle coroutines
+        return format!("#<coroutine {{{:p}}}>",
+                       Gc::into_raw(list));
+    } else if maj_readerp(list.clone()).to_bool() && rm {
+        // Handle readers
+        return format!("#<reader {{{:p}}}>",
+                       Gc::into_raw(list));
+    } else if maj_recordp(list.clone()).to_bool() && rm {
+        // Handle records
+        return maj_format_record(&state, list, rm, labels);
+    } else if (maj_genericp(list.clone()).to_bool()
+               || maj_next_method_p(list.clone()).to_bool()) && rm {
+        // Handle generic functions and next methods
+        return maj_format_generic(&state, list, rm);
     }
 
     // Handle all other lists
@@ -113,21 +203,24 @@ fn maj_format_list(
         match &*itr.clone() {
             Maj::Cons { car, cdr } => {
                 write!(&mut buffer, "{}",
-                       maj_format_raw(
+                       maj_format_labeled(
                            &state, car.clone(),
-                           rm))
+                           rm, labels))
                     .unwrap();
                 if maj_nilp(cdr.clone()).to_bool() {
                     break;
                 }
-                if let Maj::Cons { car: _, cdr: _ } = *cdr.clone() {
+                let labeled = labels.sharedp(cdr);
+                if let (Maj::Cons { car: _, cdr: _ }, false) =
+                    (&*cdr.clone(), labeled)
+                {
                     write!(&mut buffer, " ").unwrap();
                     itr = cdr.clone();
                 } else {
                     write!(&mut buffer, " . {}",
-                           maj_format_raw(
+                           maj_format_labeled(
                                &state, cdr.clone(),
-                               rm))
+                               rm, labels))
                         .unwrap();
                     break;
                 }
@@ -142,7 +235,8 @@ fn maj_format_list(
 fn maj_format_vector(
     state: &MajState,
     vector: Gc<Maj>,
-    rm: bool
+    rm: bool,
+    labels: &mut MajPrintLabels
 ) -> String {
     let mut buffer = String::new();
     use crate::core::types::MajVector;
@@ -180,15 +274,15 @@ fn maj_format_vector(
                 write!(&mut buffer, "]").unwrap();
             },
             MajVector::Char(s) => {
-                write!(&mut buffer, "\"{}\"",
-                       s.borrow()).unwrap();
+                buffer.push_str(&maj_format_string(&s.borrow()));
             },
             MajVector::Any(v) => {
                 write!(&mut buffer, "[").unwrap();
                 let len = v.borrow().len();
                 for (i, obj) in v.borrow().iter().enumerate() {
                     write!(&mut buffer, "{}{}",
-                           maj_format_raw(&state, obj.clone(), rm),
+                           maj_format_labeled(&state, obj.clone(),
+                                              rm, labels),
                            if (i + 1) < len {
                                " "
                            } else {
@@ -220,6 +314,14 @@ fn maj_format_closure(
 
 fn maj_format_macro(state: &MajState, mac: Gc<Maj>, rm: bool) -> String {
     let closure = maj_car(maj_cdr(maj_cdr(mac)));
+    if maj_eq(maj_car(maj_cdr(closure.clone())),
+              Maj::syntax_rules()).to_bool() {
+        // (lit syntax-rules <literals> <rules>)
+        let literals = maj_car(maj_cdr(maj_cdr(closure.clone())));
+        return format!("#<macro (syntax-rules {}) {{{:p}}}>",
+                       maj_format_raw(&state, literals, rm),
+                       Gc::into_raw(closure));
+    }
     let lambda_list = maj_car(
         maj_cdr(maj_cdr(maj_cdr(closure.clone()))));
     format!("#<macro (mac {}) {{{:p}}}>",
@@ -242,16 +344,73 @@ fn maj_format_primitive(
 }
 
 fn maj_format_error(state: &MajState, error: Gc<Maj>, rm: bool) -> String {
-    use crate::axioms::primitives::maj_format_prim;
-    // (lit error fmt . rest)
-    let fmt  = maj_car(maj_cdr(maj_cdr(error.clone())));
-    let rest = maj_cdr(maj_cdr(maj_cdr(error)));
+    use crate::axioms::primitives::{
+        maj_format_prim,
+        maj_condition_format,
+        maj_condition_irritants
+    };
+    // (lit error [type] fmt . rest)
+    let fmt  = maj_condition_format(error.clone());
+    let rest = maj_condition_irritants(error);
     let formatted = maj_format_prim(&state, fmt, rest);
     let formatted = maj_format_raw(&state, formatted, rm);
     let len = formatted.len();
     format!("{}", &formatted[1..len-1])
 }
 
+fn maj_format_condition(
+    state: &MajState,
+    condition: Gc<Maj>,
+    rm: bool
+) -> String {
+    use crate::axioms::primitives::maj_condition_type;
+    // (lit condition type fmt . rest)
+    let kind = maj_condition_type(condition.clone());
+    format!("#<condition {} \"{}\">",
+            maj_format_raw(&state, kind, rm),
+            maj_format_error(&state, condition, rm))
+}
+
+fn maj_format_record(
+    state: &MajState,
+    record: Gc<Maj>,
+    rm: bool,
+    labels: &mut MajPrintLabels
+) -> String {
+    if let Some(repr) = state.print_object(record.clone()) {
+        return repr;
+    }
+    // (lit record type fields . values)
+    let kind = maj_car(maj_cdr(maj_cdr(record.clone())));
+    let mut fields = maj_car(maj_cdr(maj_cdr(maj_cdr(record.clone()))));
+    let mut values = maj_cdr(maj_cdr(maj_cdr(maj_cdr(record))));
+    let mut buffer = format!("#<record {}", maj_format_raw(&state, kind, rm));
+    while !maj_nilp(fields.clone()).to_bool() {
+        write!(&mut buffer, " ({} {})",
+               maj_format_raw(&state, maj_car(fields.clone()), rm),
+               maj_format_labeled(&state, maj_car(values.clone()),
+                                  rm, labels))
+            .unwrap();
+        fields = maj_cdr(fields);
+        values = maj_cdr(values);
+    }
+    write!(&mut buffer, ">").unwrap();
+    buffer
+}
+
+fn maj_format_generic(
+    state: &MajState,
+    generic: Gc<Maj>,
+    rm: bool
+) -> String {
+    // (lit generic name) or (lit next-method name . rest)
+    let kind = maj_car(maj_cdr(generic.clone()));
+    let name = maj_car(maj_cdr(maj_cdr(generic)));
+    format!("#<function ({} {})>",
+            maj_format_raw(&state, kind, rm),
+            maj_format_raw(&state, name, rm))
+}
+
 fn maj_format_stream(s: &MajStream, obj: Gc<Maj>) -> String {
     use crate::core::types::MajStreamDirection;
     format!("#<stream ({}) {{{:p}}}>",
@@ -262,65 +421,663 @@ fn maj_format_stream(s: &MajStream, obj: Gc<Maj>) -> String {
             Gc::into_raw(obj))
 }
 
-fn maj_pformat_helper(
+struct MajPrintLabels {
+    shared: HashSet<usize>,
+    labels: HashMap<usize, usize>,
+}
+
+enum MajLabelUse {
+    Plain,
+    Define(usize),
+    Reference(usize),
+}
+
+fn maj_print_key(obj: &Gc<Maj>) -> usize {
+    &**obj as *const Maj as usize
+}
+
+impl MajPrintLabels {
+    fn sharedp(&self, obj: &Gc<Maj>) -> bool {
+        self.shared.contains(&maj_print_key(obj))
+    }
+
+    fn visit(&mut self, obj: &Gc<Maj>) -> MajLabelUse {
+        let key = maj_print_key(obj);
+        if !self.shared.contains(&key) {
+            MajLabelUse::Plain
+        } else if let Some(label) = self.labels.get(&key) {
+            MajLabelUse::Reference(*label)
+        } else {
+            let label = self.labels.len() + 1;
+            self.labels.insert(key, label);
+            MajLabelUse::Define(label)
+        }
+    }
+}
+
+fn maj_print_circle_p(state: &MajState) -> bool {
+    use crate::axioms::predicates::maj_errorp;
+    match state.find_symbol("*print-circle*") {
+        Some(sym) => {
+            let flag = state.lookup(Maj::nil(), Gc::new(Maj::Sym(sym)));
+            !maj_nilp(flag.clone()).to_bool() && !maj_errorp(flag).to_bool()
+        },
+        None => false,
+    }
+}
+
+fn maj_print_children(
     state: &MajState,
     obj: Gc<Maj>,
-    ind: u64,
-) -> String {
-    use crate::axioms::predicates::maj_atomp;
-    use crate::axioms::primitives::maj_length;
-    let indent_max = 8;
+    head: bool,
+    rm: bool
+) -> Vec<(Gc<Maj>, bool)> {
+    use crate::core::types::MajVector;
+    use crate::axioms::predicates::maj_consp;
+    match &*obj {
+        Maj::Vector(MajVector::Any(v)) =>
+            v.borrow().iter().map(|elt| (elt.clone(), true)).collect(),
+        Maj::Cons { car, cdr } => {
+            if !(head && rm && maj_eq(car.clone(), Maj::lit()).to_bool()) {
+                return vec![(car.clone(), true),
+                            (cdr.clone(), !maj_consp(cdr.clone()).to_bool())];
+            }
+            let mut children = Vec::new();
+            if maj_recordp(obj.clone()).to_bool()
+                && state.print_object(obj.clone()).is_none() {
+                    // (lit record type fields . values)
+                    let mut values =
+                        maj_cdr(maj_cdr(maj_cdr(maj_cdr(obj.clone()))));
+                    while maj_consp(values.clone()).to_bool() {
+                        children.push((maj_car(values.clone()), true));
+                        values = maj_cdr(values);
+                    }
+                }
+            children
+        },
+        _ => Vec::new(),
+    }
+}
 
-    let mut buffer = String::new();
-    for _ in 0..ind {
-        write!(&mut buffer, " ").unwrap();
-    }
-
-    if maj_atomp(obj.clone()).to_bool()
-        || maj_stringp(obj.clone()).to_bool()
-        || (maj_length(obj.clone())
-            .to_integer()
-            .unwrap() < indent_max)
-    {
-        write!(&mut buffer, "{}",
-               maj_format(&state, obj))
-            .unwrap();
-    } else {
-        write!(&mut buffer, "(").unwrap();
-        write!(&mut buffer, "{}",
-               maj_format(
-                   &state,
-                   maj_car(obj.clone())))
-            .unwrap();
-        let mut itr = maj_cdr(obj.clone());
-
-        // TODO: Add check for special forms:
-        // (and (member (prin1 (pop x)) *specials*)
-        //      ...)
-        while maj_length(itr.clone())
-            .to_integer()
-            .unwrap() >= indent_max
+fn maj_print_labels(
+    state: &MajState,
+    obj: Gc<Maj>,
+    rm: bool
+) -> MajPrintLabels {
+    use crate::core::types::MajVector;
+    enum Step {
+        Enter(Gc<Maj>, bool),
+        Leave(usize),
+    }
+    let circle = maj_print_circle_p(state);
+    let mut shared = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut path = HashSet::new();
+    let mut pending = vec![Step::Enter(obj, true)];
+    while let Some(step) = pending.pop() {
+        let (obj, head) = match step {
+            Step::Enter(obj, head) => (obj, head),
+            Step::Leave(key) => {
+                path.remove(&key);
+                continue;
+            },
+        };
+        let obj = if head { state.source_form(obj) } else { obj };
+        match &*obj {
+            Maj::Cons { car: _, cdr: _ } | Maj::Vector(MajVector::Any(_)) => {},
+            _ => continue,
+        }
+        let key = maj_print_key(&obj);
+        if path.contains(&key) || (circle && visited.contains(&key)) {
+            shared.insert(key);
+            continue;
+        }
+        if !visited.insert(key) {
+            continue;
+        }
+        path.insert(key);
+        pending.push(Step::Leave(key));
+        for (child, head) in maj_print_children(state, obj, head, rm)
+            .into_iter()
+            .rev()
         {
-            write!(&mut buffer, " ").unwrap();
-            itr = maj_cdr(itr);
+            pending.push(Step::Enter(child, head));
         }
+    }
+    MajPrintLabels {
+        shared,
+        labels: HashMap::new(),
+    }
+}
 
-        itr = maj_cdr(obj.clone());
-        while !maj_nilp(itr.clone()).to_bool() {
-            write!(&mut buffer, "\n{}",
-            maj_pformat_helper(&state,
-                               maj_car(itr.clone()),
-                               ind + 2))
-                   .unwrap();
-            itr = maj_cdr(itr);
+enum MajDoc {
+    Text(String),
+    Line,
+    Nest(usize, Box<MajDoc>),
+    Align(Box<MajDoc>),
+    Group(Box<MajDoc>),
+    Fill(Vec<MajDoc>),
+    Concat(Vec<MajDoc>),
+}
+
+#[derive(Clone, Copy)]
+enum MajLayout<'a> {
+    Doc(usize, bool, &'a MajDoc),
+    Separator(usize, bool, &'a MajDoc),
+}
+
+fn maj_doc_push<'a>(
+    pending: &mut Vec<MajLayout<'a>>,
+    indent: usize,
+    column: usize,
+    flat: bool,
+    doc: &'a MajDoc
+) {
+    match doc {
+        MajDoc::Nest(amount, doc) =>
+            pending.push(MajLayout::Doc(indent + amount, flat, doc)),
+        MajDoc::Align(doc) =>
+            pending.push(MajLayout::Doc(column, flat, doc)),
+        MajDoc::Group(doc) =>
+            pending.push(MajLayout::Doc(indent, flat, doc)),
+        MajDoc::Concat(docs) => {
+            for doc in docs.iter().rev() {
+                pending.push(MajLayout::Doc(indent, flat, doc));
+            }
+        },
+        MajDoc::Fill(docs) => {
+            for (i, doc) in docs.iter().enumerate().rev() {
+                pending.push(MajLayout::Doc(indent, flat, doc));
+                if i > 0 {
+                    pending.push(MajLayout::Separator(indent, flat, doc));
+                }
+            }
+        },
+        MajDoc::Text(_) | MajDoc::Line => {},
+    }
+}
+
+fn maj_doc_fits(
+    mut width: isize,
+    mut pending: Vec<MajLayout>,
+    rest: &[MajLayout]
+) -> bool {
+    let mut rest = rest.iter().rev();
+    while width >= 0 {
+        let item = match pending.pop() {
+            Some(item) => item,
+            None => match rest.next() {
+                Some(item) => *item,
+                None => return true,
+            },
+        };
+        match item {
+            MajLayout::Separator(_, true, _) => width -= 1,
+            MajLayout::Separator(_, false, _) => return true,
+            MajLayout::Doc(_, flat, doc) => match doc {
+                MajDoc::Text(text) => width -= text.chars().count() as isize,
+                MajDoc::Line if flat => width -= 1,
+                MajDoc::Line => return true,
+                _ => maj_doc_push(&mut pending, 0, 0, flat, doc),
+            },
+        }
+    }
+    false
+}
+
+fn maj_doc_layout(doc: &MajDoc, margin: usize) -> String {
+    let mut buffer = String::new();
+    let mut column = 0;
+    let mut pending = vec![MajLayout::Doc(0, false, doc)];
+    while let Some(item) = pending.pop() {
+        let remaining = margin as isize - column as isize;
+        match item {
+            MajLayout::Separator(indent, flat, next) => {
+                let next = vec![MajLayout::Doc(indent, true, next)];
+                if flat || maj_doc_fits(remaining - 1, next, &[]) {
+                    buffer.push(' ');
+                    column += 1;
+                } else {
+                    write!(&mut buffer, "\n{:1$}", "", indent).unwrap();
+                    column = indent;
+                }
+            },
+            MajLayout::Doc(indent, flat, doc) => match doc {
+                MajDoc::Text(text) => {
+                    buffer.push_str(text);
+                    column += text.chars().count();
+                },
+                MajDoc::Line if flat => {
+                    buffer.push(' ');
+                    column += 1;
+                },
+                MajDoc::Line => {
+                    write!(&mut buffer, "\n{:1$}", "", indent).unwrap();
+                    column = indent;
+                },
+                MajDoc::Group(inner) if !flat => {
+                    let group = vec![MajLayout::Doc(indent, true, &**inner)];
+                    let flat = maj_doc_fits(remaining, group, &pending);
+                    pending.push(MajLayout::Doc(indent, flat, inner));
+                },
+                _ => maj_doc_push(&mut pending, indent, column, flat, doc),
+            },
         }
-        write!(&mut buffer, ")").unwrap();
     }
     buffer
 }
 
-pub fn maj_pretty_format(state: &MajState,obj: Gc<Maj>) -> String {
-    maj_pformat_helper(state, obj, 0)
+fn maj_pretty_doc(state: &MajState, obj: Gc<Maj>) -> MajDoc {
+    use crate::core::types::MajVector;
+    use crate::axioms::predicates::maj_atomp;
+    let obj = state.source_form(obj);
+    match &*obj {
+        Maj::Cons { car, cdr: _ }
+        if !maj_eq(car.clone(), Maj::lit()).to_bool() =>
+            maj_pretty_list(state, obj.clone()),
+        Maj::Vector(MajVector::Any(v)) => {
+            let elements = v.borrow().iter()
+                .map(|elt| (maj_atomp(elt.clone()).to_bool(),
+                            maj_pretty_doc(state, elt.clone())))
+                .collect();
+            MajDoc::Group(Box::new(MajDoc::Concat(vec![
+                MajDoc::Text(String::from("[")),
+                maj_pretty_lines(elements),
+                MajDoc::Text(String::from("]")),
+            ])))
+        },
+        _ => MajDoc::Text(maj_format(state, obj.clone())),
+    }
+}
+
+fn maj_pretty_list(state: &MajState, list: Gc<Maj>) -> MajDoc {
+    use crate::axioms::predicates::{ maj_atomp, maj_consp, maj_symbolp };
+    use crate::evaluator::evaluation::{
+        maj_quotep,
+        maj_unquotep,
+        maj_quasiquotep,
+        maj_unquote_splice_p
+    };
+
+    let prefix =
+        if maj_quotep(list.clone()).to_bool() {
+            Some("'")
+        } else if maj_quasiquotep(list.clone()).to_bool() {
+            Some("`")
+        } else if maj_unquotep(list.clone()).to_bool() {
+            Some(",")
+        } else if maj_unquote_splice_p(list.clone()).to_bool() {
+            Some(",@")
+        } else {
+            None
+        };
+    if let Some(prefix) = prefix {
+        return MajDoc::Concat(vec![
+            MajDoc::Text(String::from(prefix)),
+            maj_pretty_doc(state, maj_car(maj_cdr(list))),
+        ]);
+    }
+
+    let head = maj_car(list.clone());
+    let mut elements = Vec::new();
+    let mut itr = list;
+    while maj_consp(itr.clone()).to_bool() {
+        let elt = maj_car(itr.clone());
+        elements.push((maj_atomp(elt.clone()).to_bool(),
+                       maj_pretty_doc(state, elt)));
+        itr = maj_cdr(itr);
+    }
+    if !maj_nilp(itr.clone()).to_bool() {
+        // The tail of a dotted list is always an atom
+        elements.push((true, MajDoc::Concat(vec![
+            MajDoc::Text(String::from(". ")),
+            maj_pretty_doc(state, itr),
+        ])));
+    }
+
+    let call = maj_symbolp(head.clone()).to_bool() && elements.len() > 1;
+    let rule = state.indent_rule(head);
+    let mut elements = elements.into_iter();
+    let mut parts = vec![MajDoc::Text(String::from("("))];
+    match rule {
+        Some(args) => {
+            let (_, head) = elements.next().unwrap();
+            let header = elements.by_ref().take(args).map(|(_, doc)| doc);
+            let header = maj_pretty_breaks(header.collect());
+            let body = maj_pretty_breaks(elements.map(|(_, doc)| doc).collect());
+            parts.push(MajDoc::Group(Box::new(MajDoc::Concat(vec![
+                head,
+                MajDoc::Nest(4, Box::new(header)),
+            ]))));
+            parts.push(MajDoc::Nest(2, Box::new(body)));
+        },
+        None if call => {
+            let (_, head) = elements.next().unwrap();
+            parts.push(head);
+            parts.push(MajDoc::Text(String::from(" ")));
+            parts.push(maj_pretty_lines(elements.collect()));
+        },
+        None => parts.push(maj_pretty_lines(elements.collect())),
+    }
+    parts.push(MajDoc::Text(String::from(")")));
+    MajDoc::Group(Box::new(MajDoc::Align(Box::new(MajDoc::Concat(parts)))))
+}
+
+fn maj_pretty_breaks(docs: Vec<MajDoc>) -> MajDoc {
+    MajDoc::Concat(docs.into_iter()
+                   .flat_map(|doc| vec![MajDoc::Line, doc])
+                   .collect())
+}
+
+fn maj_pretty_lines(elements: Vec<(bool, MajDoc)>) -> MajDoc {
+    let fill = elements.iter().all(|(atom, _)| *atom);
+    let mut docs = Vec::new();
+    for (_, doc) in elements {
+        if !fill && !docs.is_empty() {
+            docs.push(MajDoc::Line);
+        }
+        docs.push(doc);
+    }
+    MajDoc::Align(Box::new(if fill {
+        MajDoc::Fill(docs)
+    } else {
+        MajDoc::Concat(docs)
+    }))
+}
+
+fn maj_print_right_margin(state: &MajState) -> usize {
+    let margin = state.find_symbol("*print-right-margin*")
+        .map(|sym| state.lookup(Maj::nil(), Gc::new(Maj::Sym(sym))))
+        .and_then(|margin| margin.to_integer());
+    match margin {
+        Some(margin) if margin > 0 => margin as usize,
+        _ => 80,
+    }
+}
+
+pub fn maj_pretty_format(state: &MajState, obj: Gc<Maj>) -> String {
+    maj_pretty_format_margin(state, obj, maj_print_right_margin(state))
+}
+
+pub fn maj_pretty_format_margin(
+    state: &MajState,
+    obj: Gc<Maj>,
+    margin: usize
+) -> String {
+    // Shared structure is printed in a single line, with its labels
+    if !maj_print_labels(state, obj.clone(), true).shared.is_empty() {
+        return maj_format(state, obj);
+    }
+    maj_doc_layout(&maj_pretty_doc(state, obj), margin)
+}
+
+pub fn maj_format_readably(
+    state: &MajState,
+    obj: Gc<Maj>
+) -> Result<String, Gc<Maj>> {
+    let mut labels = maj_print_labels(state, obj.clone(), true);
+    maj_format_readably_labeled(state, obj, &mut labels)
+}
+
+fn maj_format_readably_labeled(
+    state: &MajState,
+    obj: Gc<Maj>,
+    labels: &mut MajPrintLabels
+) -> Result<String, Gc<Maj>> {
+    let obj = state.source_form(obj);
+    Ok(match labels.visit(&obj) {
+        MajLabelUse::Reference(label) => format!("#{}#", label),
+        MajLabelUse::Define(label) =>
+            format!("#{}={}", label,
+                    maj_format_readably_object(state, obj, labels)?),
+        MajLabelUse::Plain => maj_format_readably_object(state, obj, labels)?,
+    })
+}
+
+fn maj_format_readably_object(
+    state: &MajState,
+    obj: Gc<Maj>,
+    labels: &mut MajPrintLabels
+) -> Result<String, Gc<Maj>> {
+    use crate::reader::parser::maj_readable_symbol_p;
+    let readable = match &*obj.clone() {
+        Maj::Sym(_) => {
+            let name = obj.symbol_name(&state);
+            if maj_readable_symbol_p(&name) {
+                Some(name)
+            } else {
+                None
+            }
+        },
+        Maj::Number(num) if maj_readable_number_p(num) =>
+            Some(format!("{}", obj)),
+        Maj::Char(c) => Some(maj_format_char(*c)),
+        Maj::Cons { car: _, cdr: _ } =>
+            return maj_format_list_readably(&state, obj, labels),
+        Maj::Vector(_) =>
+            return maj_format_vector_readably(&state, obj, labels),
+        _ => None,
+    };
+    readable.ok_or_else(|| maj_unreadable_err(obj))
+}
+
+fn maj_readable_number_p(num: &MajNumber) -> bool {
+    match num {
+        MajNumber::Float(num) => num.is_finite(),
+        MajNumber::Complex { real, imag } =>
+            maj_readable_number_p(real) && maj_readable_number_p(imag),
+        _ => true,
+    }
+}
+
+fn maj_unreadable_err(obj: Gc<Maj>) -> Gc<Maj> {
+    use crate::maj_list;
+    use crate::axioms::primitives::maj_err;
+    maj_err(Maj::string("{} cannot be printed readably"),
+            maj_list!(obj))
+}
+
+fn maj_format_list_readably(
+    state: &MajState,
+    list: Gc<Maj>,
+    labels: &mut MajPrintLabels
+) -> Result<String, Gc<Maj>> {
+    use crate::axioms::predicates::{ maj_consp, maj_errorp };
+    use crate::evaluator::evaluation::{
+        maj_quotep,
+        maj_unquotep,
+        maj_quasiquotep,
+        maj_unquote_splice_p
+    };
+
+    let prefix =
+        if maj_quotep(list.clone()).to_bool() {
+            Some("'")
+        } else if maj_quasiquotep(list.clone()).to_bool() {
+            Some("`")
+        } else if maj_unquotep(list.clone()).to_bool() {
+            Some(",")
+        } else if maj_unquote_splice_p(list.clone()).to_bool() {
+            Some(",@")
+        } else {
+            None
+        };
+    let args = maj_cdr(list.clone());
+    if let Some(prefix) = prefix {
+        // Only forms with a single argument come from read macros
+        if maj_consp(args.clone()).to_bool()
+            && maj_nilp(maj_cdr(args.clone())).to_bool()
+            && !labels.sharedp(&args) {
+                return Ok(format!(
+                    "{}{}", prefix,
+                    maj_format_readably_labeled(
+                        state, maj_car(args), labels)?));
+            }
+    }
+
+    if maj_errorp(list.clone()).to_bool()
+        || maj_closurep(list.clone()).to_bool()
+        || maj_primitivep(list.clone()).to_bool()
+        || maj_macrop(list.clone()).to_bool()
+        || maj_conditionp(list.clone()).to_bool()
+        || maj_continuationp(list.clone()).to_bool()
+        || maj_coroutinep(list.clone()).to_bool()
+        || maj_readerp(list.clone()).to_bool()
+        || maj_recordp(list.clone()).to_bool()
+        || maj_genericp(list.clone()).to_bool()
+        || maj_next_method_p(list.clone()).to_bool() {
+            return Err(maj_unreadable_err(list));
+        }
+
+    let mut buffer = String::from("(");
+    let mut itr = list;
+    loop {
+        buffer.push_str(&maj_format_readably_labeled(
+            state, maj_car(itr.clone()), labels)?);
+        let rest = maj_cdr(itr);
+        if maj_nilp(rest.clone()).to_bool() {
+            break;
+        } else if maj_consp(rest.clone()).to_bool()
+            && !labels.sharedp(&rest)
+        {
+            buffer.push(' ');
+            itr = rest;
+        } else {
+            write!(&mut buffer, " . {}",
+                   maj_format_readably_labeled(state, rest, labels)?)
+                .unwrap();
+            break;
+        }
+    }
+    buffer.push(')');
+    Ok(buffer)
+}
+
+fn maj_format_vector_readably(
+    state: &MajState,
+    vector: Gc<Maj>,
+    labels: &mut MajPrintLabels
+) -> Result<String, Gc<Maj>> {
+    use crate::core::types::MajVector;
+    let elements: Vec<Gc<Maj>> =
+        match &*vector {
+            Maj::Vector(MajVector::Char(s)) =>
+                return Ok(maj_format_string(&s.borrow())),
+            Maj::Vector(MajVector::Integer(v)) =>
+                v.borrow().iter().map(|num| Maj::integer(*num)).collect(),
+            Maj::Vector(MajVector::Float(v)) =>
+                v.borrow().iter().map(|num| Maj::float(*num)).collect(),
+            Maj::Vector(MajVector::Any(v)) => v.borrow().clone(),
+            _ => panic!("Vector printing on non-vector object"),
+        };
+    let elements = elements.into_iter()
+        .map(|obj| maj_format_readably_labeled(state, obj, labels))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(format!("#[{}]", elements.join(" ")))
+}
+
+pub fn maj_with_print_objects<T, F>(
+    state: &mut MajState,
+    obj: Gc<Maj>,
+    f: F
+) -> Result<T, Gc<Maj>>
+where F: FnOnce(&mut MajState) -> T {
+    let saved = state.swap_print_objects(HashMap::new());
+    let prepared = maj_prepare_print_objects(state, obj, &mut HashSet::new());
+    let result =
+        if maj_nilp(prepared.clone()).to_bool() {
+            Ok(f(state))
+        } else {
+            Err(prepared)
+        };
+    state.swap_print_objects(saved);
+    result
+}
+
+fn maj_prepare_print_objects(
+    state: &mut MajState,
+    obj: Gc<Maj>,
+    visited: &mut HashSet<usize>
+) -> Gc<Maj> {
+    use crate::maj_list;
+    use crate::core::types::MajVector;
+    use crate::axioms::predicates::{ maj_consp, maj_errorp };
+    use crate::axioms::primitives::maj_type_err;
+    use crate::evaluator::maj_apply;
+    use crate::evaluator::application::maj_applicable_methods;
+
+    // Shared and circular structure is only prepared once
+    if !visited.insert(maj_print_key(&obj)) {
+        return Maj::nil();
+    }
+
+    if maj_recordp(obj.clone()).to_bool() {
+        if state.print_object(obj.clone()).is_some() {
+            return Maj::nil();
+        }
+        let name = Maj::symbol(state, "print-object");
+        let generic = state.lookup(Maj::nil(), name.clone());
+        let applicable = maj_genericp(generic.clone()).to_bool()
+            && maj_applicable_methods(state, name, maj_list!(obj.clone()))
+            .map_or(false, |methods| !methods.is_empty());
+        if applicable {
+            let repr = maj_apply(state, generic, maj_list!(obj.clone()),
+                                 Maj::nil());
+            if maj_errorp(repr.clone()).to_bool() {
+                return repr;
+            }
+            return match repr.stringify() {
+                Some(string) => {
+                    state.set_print_object(obj, string);
+                    Maj::nil()
+                },
+                None => maj_type_err(
+                    Maj::string("print-object returned {}, which is not a string"),
+                    maj_list!(repr)),
+            };
+        }
+        // (lit record type fields . values)
+        return maj_prepare_print_objects(
+            state, maj_cdr(maj_cdr(maj_cdr(maj_cdr(obj)))), visited);
+    }
+
+    if let Maj::Vector(MajVector::Any(v)) = &*obj {
+        let elements = v.borrow().clone();
+        for element in elements {
+            let result = maj_prepare_print_objects(state, element, visited);
+            if !maj_nilp(result.clone()).to_bool() {
+                return result;
+            }
+        }
+        return Maj::nil();
+    }
+
+    if !maj_consp(obj.clone()).to_bool()
+        || maj_eq(maj_car(obj.clone()), Maj::lit()).to_bool() {
+            return Maj::nil();
+        }
+
+    let mut itr = obj;
+    while maj_consp(itr.clone()).to_bool()
+        && !maj_eq(maj_car(itr.clone()), Maj::lit()).to_bool() {
+            let result = maj_prepare_print_objects(
+                state, maj_car(itr.clone()), visited);
+            if !maj_nilp(result.clone()).to_bool() {
+                return result;
+            }
+            itr = maj_cdr(itr);
+            if maj_consp(itr.clone()).to_bool()
+                && !visited.insert(maj_print_key(&itr)) {
+                    return Maj::nil();
+                }
+        }
+    if maj_consp(itr.clone()).to_bool() {
+        // Improper list ending with a literal, which was marked above
+        visited.remove(&maj_print_key(&itr));
+        maj_prepare_print_objects(state, itr, visited)
+    } else {
+        Maj::nil()
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -1,11 +1,56 @@
 use gc::Gc;
+use std::collections::{ HashMap, HashSet };
 use crate::core::{ MajState, Maj };
 use crate::axioms::predicates::maj_errorp;
 use crate::axioms::primitives::maj_err;
+use super::tokenizer::{
+    MajTokenPosition,
+    MajCharSource,
+    MajStringSource,
+    MajLexer,
+    MajReadError,
+    maj_dispatch_token_p,
+    maj_label_definition_p,
+    maj_label_reference_p
+};
+
+struct MajReadOrigin<'a> {
+    file:      Gc<Maj>,
+    positions: &'a [MajTokenPosition],
+}
 
 fn maj_read_one<'a>(
     mut state: &mut MajState,
-    tokens: &'a [String]
+    tokens: &'a [String],
+    origin: Option<&MajReadOrigin>
+) -> Result<(Gc<Maj>, &'a [String]), &'static str> {
+    use crate::axioms::predicates::maj_consp;
+    use crate::maj_list;
+    let skipped = maj_skip_ignored(&mut state, tokens)?;
+    if skipped.is_empty() && !tokens.is_empty() {
+        return Err("Unexpected EOF after ignored form");
+    }
+    let tokens = skipped;
+    let result = maj_read_form(&mut state, tokens, origin);
+    if let (Some(origin), Ok((expr, _))) = (origin, &result) {
+        if maj_consp(expr.clone()).to_bool() {
+            let index = origin.positions.len() - tokens.len();
+            if let Some((line, column)) = origin.positions.get(index) {
+                state.set_location(
+                    expr.clone(),
+                    maj_list!(origin.file.clone(),
+                              Maj::integer(*line),
+                              Maj::integer(*column)));
+            }
+        }
+    }
+    result
+}
+
+fn maj_read_form<'a>(
+    mut state: &mut MajState,
+    tokens: &'a [String],
+    origin: Option<&MajReadOrigin>
 ) -> Result<(Gc<Maj>, &'a [String]), &'static str> {
     if tokens.len() == 0 {
         return Ok((Maj::nil(), tokens));
@@ -15,7 +60,7 @@ fn maj_read_one<'a>(
     let first = tokens.first().unwrap();
     match first.as_ref() {
         "[" => {
-            tokens = &tokens[1..];
+            tokens = maj_skip_ignored(&mut state, &tokens[1..])?;
             // Empty vector
             if let Some(t) = tokens.first() {
                 if t == "]" {
@@ -29,9 +74,9 @@ fn maj_read_one<'a>(
             // Keep collecting to build a non-empty vector
             let mut vector_elts = Vec::new();
             loop {
-                match maj_read_one(&mut state, tokens) {
+                match maj_read_one(&mut state, tokens, origin) {
                     Ok((obj, slice)) => {
-                        tokens = slice;
+                        tokens = maj_skip_ignored(&mut state, slice)?;
                         vector_elts.push(obj);
                     },
                     Err(msg) => {
@@ -55,7 +100,7 @@ fn maj_read_one<'a>(
             }
         },
         "(" => {
-            tokens = &tokens[1..];
+            tokens = maj_skip_ignored(&mut state, &tokens[1..])?;
             // Empty list
             if let Some(t) = tokens.first() {
                 if t == ")" {
@@ -65,9 +110,9 @@ fn maj_read_one<'a>(
             // Keep collecting to build a non-empty list
             let mut list = Vec::new();
             loop {
-                match maj_read_one(&mut state, tokens) {
+                match maj_read_one(&mut state, tokens, origin) {
                     Ok((obj, slice)) => {
-                        tokens = slice;
+                        tokens = maj_skip_ignored(&mut state, slice)?;
                         list.push(obj);
                     },
                     Err(msg) => {
@@ -85,10 +130,12 @@ fn maj_read_one<'a>(
                                 // element, begone
                                 match maj_read_one(
                                     &mut state,
-                                    &tokens[1..]) {
+                                    &tokens[1..],
+                                    origin) {
                                     Ok((obj, slice)) => {
                                         if let Some(e) = list.pop() {
-                                            tokens = slice;
+                                            tokens = maj_skip_ignored(
+                                                &mut state, slice)?;
                                             if tokens.len() == 0 {
                                                 return Err("Unexpected EOF when reading dotted pair");
                                             } else if tokens[0] != ")" {
@@ -116,6 +163,7 @@ fn maj_read_one<'a>(
                 }
             }
         },
+        "#[" => maj_read_vector_literal(&mut state, tokens, origin),
         ")" => Err("Unmatched close parenthesis"),
         "]" => Err("Unmatched close brackets"),
         "." => {
@@ -129,7 +177,7 @@ fn maj_read_one<'a>(
                 return Err("Unmatched quote");
             }
 
-            match maj_read_one(&mut state, &tokens[1..]) {
+            match maj_read_one(&mut state, &tokens[1..], origin) {
                 Ok((obj, slice)) => {
                     Ok((maj_parser_into_list(vec![Maj::quote(), obj]),
                         slice))
@@ -142,7 +190,7 @@ fn maj_read_one<'a>(
                 return Err("Unmatched quasiquote");
             }
 
-            match maj_read_one(&mut state, &tokens[1..]) {
+            match maj_read_one(&mut state, &tokens[1..], origin) {
                 Ok((obj, slice)) => {
                     Ok((maj_parser_into_list(vec![Maj::quasiquote(), obj]),
                         slice))
@@ -155,7 +203,7 @@ fn maj_read_one<'a>(
                 return Err("Unmatched unquote");
             }
 
-            match maj_read_one(&mut state, &tokens[1..]) {
+            match maj_read_one(&mut state, &tokens[1..], origin) {
                 Ok((obj, slice)) => {
                     Ok((maj_parser_into_list(vec![Maj::unquote(), obj]),
                         slice))
@@ -168,7 +216,7 @@ fn maj_read_one<'a>(
                 return Err("Unmatched unquote-splice");
             }
 
-            match maj_read_one(&mut state, &tokens[1..]) {
+            match maj_read_one(&mut state, &tokens[1..], origin) {
                 Ok((obj, slice)) => {
                     Ok((maj_parser_into_list(
                         vec![Maj::unquote_splice(), obj]),
@@ -177,6 +225,24 @@ fn maj_read_one<'a>(
                 Err(msg) => Err(msg),
             }
         },
+        token if maj_label_definition_p(token).is_some() =>
+            maj_read_label_definition(&mut state, tokens, origin),
+        token if maj_label_reference_p(token).is_some() => {
+            let label = maj_label_reference_p(token).unwrap();
+            match state.read_label(label) {
+                Some(obj) => Ok((obj, &tokens[1..])),
+                None => Err("Undefined datum label"),
+            }
+        },
+        token if maj_read_object_p(token).is_some() => {
+            let index = maj_read_object_p(token).unwrap();
+            match state.read_object(index) {
+                Some(obj) => Ok((obj, &tokens[1..])),
+                None => Err("Undefined read object"),
+            }
+        },
+        token if maj_dispatch_token_p(token).is_some() =>
+            Err("Dispatch macros can only be read from a character source"),
         _ => {
             // Symbols
             if let Some(obj) = maj_parse_character(first.as_ref()) {
@@ -192,6 +258,8 @@ fn maj_read_one<'a>(
                 let token: &str = first.as_ref();
                 if (token.len() >= 2) && (&token[0..2] == "#\\") {
                     Err("Unknown character")
+                } else if token.starts_with('"') {
+                    Err("Invalid escape sequence on string constant")
                 } else {
                     // Ordinary symbol
                     Ok((Maj::symbol(&mut state, first), &tokens[1..]))
@@ -297,76 +365,126 @@ fn maj_token_once_p(token: &str, ch: char) -> Option<usize> {
     }
 }
 
+fn maj_parse_code_point(digits: &str) -> Option<char> {
+    if digits.is_empty()
+        || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        None
+    } else {
+        u32::from_str_radix(digits, 16).ok()
+            .and_then(std::char::from_u32)
+    }
+}
+
 fn maj_parse_string(token: &str) -> Option<Gc<Maj>> {
-    if (token.len() < 2)
-        || (token.chars().nth(0).unwrap() != '"')
-        || (token.chars().last().unwrap() != '"') {
-            None
-        } else {
-            let mut buffer = String::new();
-            let mut ignore_next = false;
-            for i in 1..(token.len()-1) {
-                if ignore_next {
-                    ignore_next = false;
-                } else {
-                    match token.chars().nth(i).unwrap() {
-                        '\\' => {
-                            match token.chars().nth(i+1).unwrap() {
-                                'n' => {
-                                    buffer.push('\n');
-                                    ignore_next = true;
-                                },
-                                't' => {
-                                    buffer.push('\t');
-                                    ignore_next = true;
-                                },
-                                c => {
-                                    buffer.push(c);
-                                    ignore_next = true;
-                                },
-                            };
-                        },
-                        c    => buffer.push(c),
+    if !token.starts_with('"') || !token.ends_with('"') || token.len() < 2 {
+        return None;
+    }
+    let mut buffer = String::new();
+    let mut chars = token[1..token.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            buffer.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => buffer.push('\n'),
+            't' => buffer.push('\t'),
+            'r' => buffer.push('\r'),
+            '0' => buffer.push('\0'),
+            'x' => {
+                let mut digits = String::new();
+                loop {
+                    match chars.next()? {
+                        ';' => break,
+                        c => digits.push(c),
                     }
                 }
-            }
-            Some(Maj::string(buffer.as_ref()))
+                buffer.push(maj_parse_code_point(&digits)?);
+            },
+            'u' => {
+                if chars.next()? != '{' {
+                    return None;
+                }
+                let mut digits = String::new();
+                loop {
+                    match chars.next()? {
+                        '}' => break,
+                        c => digits.push(c),
+                    }
+                }
+                buffer.push(maj_parse_code_point(&digits)?);
+            },
+            c => buffer.push(c),
         }
+    }
+    Some(Maj::string(buffer.as_ref()))
 }
 
 fn maj_parse_character(token: &str) -> Option<Gc<Maj>> {
     if (token.len() <= 2) || (&token[0..2] != "#\\") {
-        None
-    } else {
-        let chr = &token[2..];
-        Some(Maj::character(match chr {
-            "␇" | "bel" => '\x07',
-            "newline" => '\n',
-            "tab" => '\t',
-            // Those should not be needed!
-            "space"    => ' ',
-            "lparen"   => '(',
-            "rparen"   => ')',
-            "lbracket" => '[',
-            "rbracket" => ']',
-            c => {
-                if c.len() == 1 {
-                    c.chars().nth(0).unwrap()
-                } else {
-                    return None;
-                }
-            },
-        }))
+        return None;
     }
+    let chr = &token[2..];
+    Some(Maj::character(match chr {
+        "␇" | "bel" | "alarm" => '\x07',
+        "newline" | "linefeed" => '\n',
+        "tab"       => '\t',
+        "space"     => ' ',
+        "return"    => '\r',
+        "escape"    => '\x1b',
+        "nul"       => '\0',
+        "backspace" => '\x08',
+        "delete"    => '\x7f',
+        // Those should not be needed!
+        "lparen"   => '(',
+        "rparen"   => ')',
+        "lbracket" => '[',
+        "rbracket" => ']',
+        c => {
+            let mut chars = c.chars();
+            let first = chars.next().unwrap();
+            if chars.next().is_none() {
+                first
+            } else if c.starts_with('x') {
+                maj_parse_code_point(&c[1..])?
+            } else if c.starts_with("U+") || c.starts_with("u+") {
+                maj_parse_code_point(&c[2..])?
+            } else {
+                return None;
+            }
+        },
+    }))
 }
 
-pub fn maj_parse(
-    mut state: &mut MajState, tokens: Vec<String>
-) -> Result<Gc<Maj>, &str> {
+fn maj_parse_located(
+    state: &mut MajState,
+    tokens: Vec<String>,
+    positions: &[MajTokenPosition],
+    file: &str
+) -> Result<Gc<Maj>, &'static str> {
+    let origin = MajReadOrigin {
+        file: Maj::string(file),
+        positions,
+    };
+    maj_parse_forms(state, tokens, Some(&origin))
+}
+
+fn maj_parse_forms(
+    mut state: &mut MajState,
+    tokens: Vec<String>,
+    origin: Option<&MajReadOrigin>
+) -> Result<Gc<Maj>, &'static str> {
     let mut list = Vec::new();
     let mut tokens = &tokens[..];
-    while tokens.len() > 0 {
-        match maj_read_one(&mut state, tokens) {
+    loop {
+        tokens = maj_skip_ignored(&mut state, tokens)?;
+        if tokens.is_empty() {
+            break;
+        }
+        let labels = state.swap_read_labels(HashMap::new());
+        let result = maj_read_one(&mut state, tokens, origin);
+        state.swap_read_labels(labels);
+        match result {
             Ok((expr, slice)) => {
                 tokens = slice;
                 list.push(expr);
@@ -414,3 +532,544 @@ fn maj_parser_into_vector(elts: Vec<Gc<Maj>>) -> Gc<Maj> {
         Maj::cons(Maj::vector_sym(), expr)
     }
 }
+
+fn maj_skip_ignored<'a>(
+    mut state: &mut MajState,
+    tokens: &'a [String]
+) -> Result<&'a [String], &'static str> {
+    let mut tokens = tokens;
+    loop {
+        match tokens.first().map(|t| t.as_ref()) {
+            Some("#;") => {
+                tokens = maj_skip_datum(&tokens[1..])?;
+            },
+            Some(prefix @ "#+") | Some(prefix @ "#-") => {
+                let (feature, rest) =
+                    maj_read_one(&mut state, &tokens[1..], None)?;
+                let wanted = prefix == "#+";
+                if maj_feature_p(&mut state, feature)? == wanted {
+                    tokens = rest;
+                } else {
+                    tokens = maj_skip_datum(rest)?;
+                }
+            },
+            _ => return Ok(tokens),
+        }
+    }
+}
+
+fn maj_skip_datum(tokens: &[String]) -> Result<&[String], &'static str> {
+    // Discarded forms only have their structure read, so nothing
+    // within them is built or dispatched upon
+    if tokens.is_empty() {
+        return Ok(tokens);
+    }
+    let mut counter = MajDatumCounter::new();
+    for (index, token) in tokens.iter().enumerate() {
+        if counter.depth == 0 && (token == ")" || token == "]") {
+            return Err("Unmatched close parenthesis");
+        }
+        counter.push(token);
+        if counter.complete() {
+            return Ok(&tokens[index + 1..]);
+        }
+    }
+    Err("Unexpected EOF after ignored form")
+}
+
+fn maj_feature_p(
+    mut state: &mut MajState,
+    expr: Gc<Maj>
+) -> Result<bool, &'static str> {
+    use crate::axioms::predicates::{
+        maj_symbolp,
+        maj_consp,
+        maj_nilp,
+        maj_eq
+    };
+    use crate::axioms::primitives::{ maj_car, maj_cdr };
+    if maj_symbolp(expr.clone()).to_bool() {
+        let features = Maj::symbol(&mut state, "*features*");
+        let mut features = state.lookup(Maj::nil(), features);
+        while maj_consp(features.clone()).to_bool() {
+            if maj_eq(maj_car(features.clone()), expr.clone()).to_bool() {
+                return Ok(true);
+            }
+            features = maj_cdr(features);
+        }
+        return Ok(false);
+    }
+
+    if !maj_consp(expr.clone()).to_bool() {
+        return Err("Invalid feature expression");
+    }
+    let operator = maj_car(expr.clone());
+    let mut args = Vec::new();
+    let mut rest = maj_cdr(expr);
+    while maj_consp(rest.clone()).to_bool() {
+        args.push(maj_car(rest.clone()));
+        rest = maj_cdr(rest);
+    }
+    if !maj_nilp(rest).to_bool() {
+        return Err("Invalid feature expression");
+    }
+    let and = Maj::symbol(&mut state, "and");
+    let or  = Maj::symbol(&mut state, "or");
+    let not = Maj::symbol(&mut state, "not");
+    if maj_eq(operator.clone(), and).to_bool() {
+        for arg in args {
+            if !maj_feature_p(&mut state, arg)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    } else if maj_eq(operator.clone(), or).to_bool() {
+        for arg in args {
+            if maj_feature_p(&mut state, arg)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    } else if maj_eq(operator, not).to_bool() && args.len() == 1 {
+        Ok(!maj_feature_p(&mut state, args[0].clone())?)
+    } else {
+        Err("Invalid feature expression")
+    }
+}
+
+fn maj_read_object_p(token: &str) -> Option<usize> {
+    token.strip_prefix("#(")?.strip_suffix(')')?.parse().ok()
+}
+
+fn maj_read_vector_literal<'a>(
+    mut state: &mut MajState,
+    tokens: &'a [String],
+    origin: Option<&MajReadOrigin>
+) -> Result<(Gc<Maj>, &'a [String]), &'static str> {
+    use crate::axioms::primitives::maj_vector;
+    let mut elements = Vec::new();
+    let mut tokens = maj_skip_ignored(&mut state, &tokens[1..])?;
+    loop {
+        match tokens.first().map(|t| t.as_ref()) {
+            Some("]") => break,
+            Some(_) => {
+                let (obj, slice) = maj_read_one(&mut state, tokens, origin)?;
+                elements.push(obj);
+                tokens = maj_skip_ignored(&mut state, slice)?;
+            },
+            None => return Err("Unmatched open bracket"),
+        }
+    }
+    let vector = maj_vector(&mut state, maj_parser_into_list(elements));
+    if maj_errorp(vector.clone()).to_bool() {
+        Err("Invalid vector literal")
+    } else {
+        Ok((vector, &tokens[1..]))
+    }
+}
+
+pub fn maj_readable_symbol_p(name: &str) -> bool {
+    use super::tokenizer::maj_tokenize;
+    let tokens = match maj_tokenize(name) {
+        Ok(tokens) => tokens,
+        Err(_) => return false,
+    };
+    tokens.len() == 1
+        && tokens[0] == name
+        && !name.starts_with('#')
+        && !["(", ")", "[", "]", ".", "'", "`", ",", ",@"].contains(&name)
+        && maj_parse_string(name).is_none()
+        && maj_parse_number(name).is_none()
+}
+
+fn maj_read_label_definition<'a>(
+    mut state: &mut MajState,
+    tokens: &'a [String],
+    origin: Option<&MajReadOrigin>
+) -> Result<(Gc<Maj>, &'a [String]), &'static str> {
+    let label = maj_label_definition_p(&tokens[0]).unwrap();
+    if state.read_label(label).is_some() {
+        return Err("Datum label defined more than once");
+    }
+    if tokens.len() <= 1 {
+        return Err("Unexpected EOF after datum label");
+    }
+    let placeholder = Gc::new(Maj::Sym(state.gen_random_symbol()));
+    state.set_read_label(label, placeholder.clone());
+    let (obj, slice) = maj_read_one(&mut state, &tokens[1..], origin)?;
+    if Gc::ptr_eq(&obj, &placeholder) {
+        return Err("Datum label refers only to itself");
+    }
+    state.set_read_label(label, obj.clone());
+    maj_patch_label(placeholder, obj.clone());
+    Ok((obj, slice))
+}
+
+fn maj_patch_label(placeholder: Gc<Maj>, obj: Gc<Maj>) {
+    use crate::core::types::MajVector;
+    use crate::evaluator::evaluation::maj_replace_pair;
+    let patch = |x: Gc<Maj>| if Gc::ptr_eq(&x, &placeholder) {
+        obj.clone()
+    } else {
+        x
+    };
+    let mut visited = HashSet::new();
+    let mut pending = vec![obj.clone()];
+    while let Some(x) = pending.pop() {
+        if !visited.insert(&*x as *const Maj as usize) {
+            continue;
+        }
+        match &*x {
+            Maj::Cons { car, cdr } => {
+                let (car, cdr) = (car.clone(), cdr.clone());
+                if Gc::ptr_eq(&car, &placeholder)
+                    || Gc::ptr_eq(&cdr, &placeholder) {
+                    maj_replace_pair(x.clone(), patch(car.clone()),
+                                     patch(cdr.clone()));
+                }
+                pending.push(patch(car));
+                pending.push(patch(cdr));
+            },
+            Maj::Vector(MajVector::Any(elts)) => {
+                let mut elts = elts.borrow_mut();
+                for elt in elts.iter_mut() {
+                    *elt = patch(elt.clone());
+                    pending.push(elt.clone());
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+struct MajDatumCounter {
+    depth:   usize,
+    pending: usize,
+}
+
+impl MajDatumCounter {
+    fn new() -> MajDatumCounter {
+        MajDatumCounter {
+            depth:   0,
+            pending: 1,
+        }
+    }
+
+    fn push(&mut self, token: &str) {
+        let required = match token {
+            "(" | "[" | "#[" => {
+                self.depth += 1;
+                None
+            },
+            ")" | "]" => {
+                if self.depth > 0 {
+                    self.depth -= 1;
+                }
+                Some(0)
+            },
+            "'" | "`" | "," | ",@" => Some(1),
+            "#;" | "#+" | "#-" => Some(2),
+            t if maj_label_definition_p(t).is_some()
+                || maj_dispatch_token_p(t).is_some() => Some(1),
+            _ => Some(0),
+        };
+        if self.depth == 0 {
+            if let Some(required) = required {
+                self.pending = self.pending - 1 + required;
+            }
+        }
+    }
+
+    fn complete(&self) -> bool {
+        self.pending == 0
+    }
+}
+
+#[derive(Clone, Copy)]
+enum MajSkipKind {
+    // Datum after #;
+    Discarded,
+    // Feature expression after #+ or #-
+    Feature(bool),
+    // Datum after a feature expression, which lies between the
+    // given tokens, and whether it is discarded once known
+    Conditional(bool, usize, usize, Option<bool>),
+}
+
+struct MajSkip {
+    kind:    MajSkipKind,
+    start:   usize,
+    counter: MajDatumCounter,
+}
+
+impl MajSkip {
+    fn new(kind: MajSkipKind, start: usize) -> MajSkip {
+        MajSkip {
+            kind,
+            start,
+            counter: MajDatumCounter::new(),
+        }
+    }
+}
+
+struct MajDatumTokens {
+    tokens:    Vec<String>,
+    positions: Vec<MajTokenPosition>,
+    objects:   Vec<Gc<Maj>>,
+    counter:   MajDatumCounter,
+    skips:     Vec<MajSkip>,
+}
+
+impl MajDatumTokens {
+    fn new() -> MajDatumTokens {
+        MajDatumTokens {
+            tokens:    Vec::new(),
+            positions: Vec::new(),
+            objects:   Vec::new(),
+            counter:   MajDatumCounter::new(),
+            skips:     Vec::new(),
+        }
+    }
+
+    fn push(&mut self, token: String, position: MajTokenPosition) {
+        self.counter.push(&token);
+        let end = self.tokens.len() + 1;
+        let mut skips = Vec::new();
+        for mut skip in self.skips.drain(..) {
+            skip.counter.push(&token);
+            if !skip.counter.complete() {
+                skips.push(skip);
+            } else if let MajSkipKind::Feature(wanted) = skip.kind {
+                let kind = MajSkipKind::Conditional(wanted, skip.start,
+                                                    end, None);
+                skips.push(MajSkip::new(kind, end));
+            }
+        }
+        match token.as_ref() {
+            "#;" => skips.push(MajSkip::new(MajSkipKind::Discarded, end)),
+            "#+" | "#-" => {
+                let kind = MajSkipKind::Feature(token == "#+");
+                skips.push(MajSkip::new(kind, end));
+            },
+            _ => {},
+        }
+        self.skips = skips;
+        self.tokens.push(token);
+        self.positions.push(position);
+    }
+
+    fn push_object(&mut self, obj: Gc<Maj>, position: MajTokenPosition) {
+        self.push(format!("#({})", self.objects.len()), position);
+        self.objects.push(obj);
+    }
+
+    fn complete(&self) -> bool {
+        self.counter.complete()
+    }
+
+    fn discarding(
+        &mut self,
+        state: &mut MajState
+    ) -> Result<bool, MajReadError> {
+        for skip in self.skips.iter_mut() {
+            let discard = match skip.kind {
+                MajSkipKind::Discarded => true,
+                MajSkipKind::Feature(_) => false,
+                MajSkipKind::Conditional(_, _, _, Some(discard)) => discard,
+                MajSkipKind::Conditional(wanted, start, end, None) => {
+                    let feature = maj_datum_feature_p(
+                        state, &self.tokens[start..end], &self.objects)?;
+                    let discard = feature != wanted;
+                    skip.kind = MajSkipKind::Conditional(wanted, start, end,
+                                                         Some(discard));
+                    discard
+                },
+            };
+            if discard {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+fn maj_datum_feature_p(
+    state: &mut MajState,
+    tokens: &[String],
+    objects: &[Gc<Maj>]
+) -> Result<bool, MajReadError> {
+    let objects = state.swap_read_objects(objects.to_vec());
+    let result = maj_read_one(state, tokens, None)
+        .and_then(|(feature, _)| maj_feature_p(state, feature));
+    state.swap_read_objects(objects);
+    result.map_err(MajReadError::Syntax)
+}
+
+fn maj_datum_tokens<S: MajCharSource>(
+    lexer: &mut MajLexer,
+    source: &mut S,
+    datum: &mut MajDatumTokens
+) -> Result<Option<(char, MajTokenPosition)>, MajReadError> {
+    while !datum.complete() {
+        match lexer.next_token(source)? {
+            Some((token, position)) => match maj_dispatch_token_p(&token) {
+                Some(c) => return Ok(Some((c, position))),
+                None => datum.push(token, position),
+            },
+            None => break,
+        }
+    }
+    Ok(None)
+}
+
+fn maj_parse_datum(
+    state: &mut MajState,
+    tokens: Vec<String>,
+    positions: &[MajTokenPosition],
+    file: Option<&str>
+) -> Result<Option<Gc<Maj>>, MajReadError> {
+    use crate::axioms::predicates::maj_nilp;
+    use crate::axioms::primitives::maj_car;
+    let forms = match file {
+        Some(file) => maj_parse_located(state, tokens, positions, file),
+        None => maj_parse_forms(state, tokens, None),
+    }.map_err(MajReadError::Syntax)?;
+    Ok(if maj_nilp(forms.clone()).to_bool() {
+        None
+    } else {
+        Some(maj_car(forms))
+    })
+}
+
+pub enum MajReaderInput {
+    Text(MajStringSource),
+    Stream(Gc<Maj>),
+}
+
+impl MajReaderInput {
+    pub fn position(&self) -> usize {
+        match self {
+            MajReaderInput::Text(source) => source.position,
+            MajReaderInput::Stream(_) => 0,
+        }
+    }
+}
+
+struct MajStreamSource<'a> {
+    state:  &'a mut MajState,
+    stream: Gc<Maj>,
+}
+
+fn maj_stream_source_char(c: Gc<Maj>) -> Result<Option<char>, Gc<Maj>> {
+    if maj_errorp(c.clone()).to_bool() {
+        Err(c)
+    } else {
+        // The only non-character result is the eof symbol
+        Ok(c.to_char())
+    }
+}
+
+impl MajCharSource for MajStreamSource<'_> {
+    fn peek(&mut self) -> Result<Option<char>, Gc<Maj>> {
+        use crate::axioms::primitives::maj_peek_char;
+        maj_stream_source_char(maj_peek_char(self.state, self.stream.clone()))
+    }
+
+    fn next(&mut self) -> Result<Option<char>, Gc<Maj>> {
+        use crate::axioms::primitives::maj_read_char;
+        maj_stream_source_char(maj_read_char(self.state, self.stream.clone()))
+    }
+}
+
+fn maj_input_tokens(
+    state: &mut MajState,
+    lexer: &mut MajLexer,
+    input: &mut MajReaderInput,
+    datum: &mut MajDatumTokens
+) -> Result<Option<(char, MajTokenPosition)>, MajReadError> {
+    match input {
+        MajReaderInput::Text(source) => maj_datum_tokens(lexer, source, datum),
+        MajReaderInput::Stream(stream) => {
+            let mut source = MajStreamSource {
+                state,
+                stream: stream.clone(),
+            };
+            maj_datum_tokens(lexer, &mut source, datum)
+        },
+    }
+}
+
+fn maj_read_dispatch(
+    state: &mut MajState,
+    lexer: &mut MajLexer,
+    input: &mut MajReaderInput,
+    c: char
+) -> Result<Gc<Maj>, MajReadError> {
+    use crate::evaluator::maj_apply;
+    use crate::maj_list;
+    let function = match state.dispatch_macro(c) {
+        Some(function) => function,
+        None => return Err(
+            MajReadError::Syntax("Unknown dispatch macro character")),
+    };
+    let start = input.position();
+    let tag = Gc::new(Maj::Sym(state.gen_random_symbol()));
+    let reader = maj_list!(Maj::lit(), Maj::reader(), tag);
+    let empty = MajReaderInput::Text(MajStringSource::new("", 0));
+    state.push_reader(reader.clone(), std::mem::replace(input, empty));
+    let result = maj_apply(state, function, maj_list!(reader), Maj::nil());
+    *input = state.pop_reader().unwrap();
+    if let MajReaderInput::Text(source) = input {
+        lexer.skip(source.consumed(start));
+    }
+    if maj_errorp(result.clone()).to_bool() {
+        Err(MajReadError::Source(result))
+    } else {
+        Ok(result)
+    }
+}
+
+pub fn maj_read_datum(
+    state: &mut MajState,
+    lexer: &mut MajLexer,
+    input: &mut MajReaderInput,
+    file: Option<&str>
+) -> Result<Option<Gc<Maj>>, MajReadError> {
+    loop {
+        let mut datum = MajDatumTokens::new();
+        while let Some((c, position)) =
+            maj_input_tokens(state, lexer, input, &mut datum)?
+        {
+            if datum.discarding(state)? {
+                datum.push(format!("#{}", c), position);
+            } else {
+                let obj = maj_read_dispatch(state, lexer, input, c)?;
+                datum.push_object(obj, position);
+            }
+        }
+        if datum.tokens.is_empty() {
+            return Ok(None);
+        }
+        let objects = state.swap_read_objects(datum.objects);
+        let result = maj_parse_datum(state, datum.tokens,
+                                     &datum.positions, file);
+        state.swap_read_objects(objects);
+        if let Some(datum) = result? {
+            return Ok(Some(datum));
+        }
+    }
+}
+
+pub fn maj_read_text(
+    state: &mut MajState,
+    text: &str
+) -> Result<Gc<Maj>, MajReadError> {
+    let mut input = MajReaderInput::Text(MajStringSource::new(text, 0));
+    let mut lexer = MajLexer::new();
+    let mut forms = Vec::new();
+    while let Some(form) = maj_read_datum(state, &mut lexer,
+                                          &mut input, None)? {
+        forms.push(form);
+    }
+    Ok(maj_parser_into_list(forms))
+}
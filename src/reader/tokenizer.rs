@@ -1,224 +1,469 @@
-pub fn maj_tokenize(
-    text: &str
-) -> Result<Vec<String>, (i64, &'static str)> {
-    let mut tokens = Vec::new();
-    let mut buffer = String::new();
-    let mut ignore_count = 0;
-    let mut line = 1;
+use gc::Gc;
+use crate::core::Maj;
+
+pub type MajTokenPosition = (i64, i64);
+
+pub trait MajCharSource {
+    fn peek(&mut self) -> Result<Option<char>, Gc<Maj>>;
+    fn next(&mut self) -> Result<Option<char>, Gc<Maj>>;
+}
+
+pub struct MajStringSource {
+    chars: Vec<char>,
+    pub position: usize,
+}
+
+impl MajStringSource {
+    pub fn new(text: &str, position: usize) -> MajStringSource {
+        MajStringSource {
+            chars: text.chars().collect(),
+            position,
+        }
+    }
+
+    pub fn consumed(&self, start: usize) -> &[char] {
+        &self.chars[start..self.position]
+    }
+}
+
+impl MajCharSource for MajStringSource {
+    fn peek(&mut self) -> Result<Option<char>, Gc<Maj>> {
+        Ok(self.chars.get(self.position).cloned())
+    }
 
-    for (i, c) in text.chars().enumerate() {
-        if ignore_count > 0 {
-            ignore_count -= 1;
+    fn next(&mut self) -> Result<Option<char>, Gc<Maj>> {
+        let c = self.chars.get(self.position).cloned();
+        if c.is_some() {
+            self.position += 1;
+        }
+        Ok(c)
+    }
+}
+
+#[derive(Debug)]
+pub enum MajReadError {
+    Source(Gc<Maj>),
+    Lexical(i64, &'static str),
+    Syntax(&'static str),
+}
+
+pub struct MajLexer {
+    line:   i64,
+    column: i64,
+}
+
+impl MajLexer {
+    pub fn new() -> MajLexer {
+        MajLexer { line: 1, column: 1 }
+    }
+
+    fn peek<S: MajCharSource>(
+        &self,
+        source: &mut S
+    ) -> Result<Option<char>, MajReadError> {
+        source.peek().map_err(MajReadError::Source)
+    }
+
+    fn advance<S: MajCharSource>(
+        &mut self,
+        source: &mut S
+    ) -> Result<Option<char>, MajReadError> {
+        let c = source.next().map_err(MajReadError::Source)?;
+        if let Some(c) = c {
+            self.count(c);
+        }
+        Ok(c)
+    }
+
+    fn count(&mut self, c: char) {
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
         } else {
+            self.column += 1;
+        }
+    }
+
+    pub fn skip(&mut self, consumed: &[char]) {
+        for c in consumed {
+            self.count(*c);
+        }
+    }
+
+    fn lexical_err<T>(&self, msg: &'static str) -> Result<T, MajReadError> {
+        Err(MajReadError::Lexical(self.line, msg))
+    }
+}
+
+impl MajLexer {
+    pub fn next_token<S: MajCharSource>(
+        &mut self,
+        source: &mut S
+    ) -> Result<Option<(String, MajTokenPosition)>, MajReadError> {
+        loop {
+            let here = (self.line, self.column);
+            let c = match self.peek(source)? {
+                Some(c) => c,
+                None => return Ok(None),
+            };
             match c {
+                // Delimiters
+                ' ' | '\n' | '\t' => {
+                    self.advance(source)?;
+                },
                 // Read macros
                 ';' => {
-                    let mut count = 1;
-                    loop {
-                        let c = text.chars().nth(i + count);
-                        match c {
-                            Some('\n') | None => {
-                                break;
-                            },
-                            _ => {
-                                count += 1;
-                            }
+                    while let Some(c) = self.peek(source)? {
+                        if c == '\n' {
+                            break;
                         }
+                        self.advance(source)?;
                     }
-                    ignore_count = count;
                 },
-                '\"' => {
-                    // Keep fetching string
-                    buffer.push('"');
-                    let mut count = 1;
-                    let mut ignore_next = false;
-                    loop {
-                        let c = text.chars().nth(i + count);
-                        if ignore_next {
-                            count += 2;
-                            ignore_next = false;
-                        } else {
-                            match c {
-                                Some('"') => {
-                                    buffer.push('"');
-                                    tokens.push(buffer.clone());
-                                    buffer = String::new();
-                                    ignore_count = count;
-                                    break;
-                                },
-                                Some('\\') => {
-                                    buffer.push('\\');
-                                    if let Some(next) = text.chars().nth(i + count + 1) {
-                                        buffer.push(next);
-                                    } else {
-                                        return Err((line, "Unexpected EOF while reading escaped character on string constant"));
-                                    }
-                                    ignore_next = true;
-                                },
-                                Some(c) => {
-                                    count += 1;
-                                    buffer.push(c);
-                                },
-                                None => {
-                                    return Err((line, "Unexpected EOF while reading string constant"));
-                                },
-                            }
-                        }
-                    }
+                '\'' | '`' | '(' | ')' | '[' | ']' => {
+                    self.advance(source)?;
+                    return Ok(Some((c.to_string(), here)));
+                },
+                ',' => {
+                    self.advance(source)?;
+                    let token = if self.peek(source)? == Some('@') {
+                        self.advance(source)?;
+                        ",@"
+                    } else {
+                        ","
+                    };
+                    return Ok(Some((String::from(token), here)));
+                },
+                '@' => {
+                    // Syntax error: @ alone
+                    return self.lexical_err("'@' should be preceeded by ','");
                 },
                 '#' => {
-                    buffer.push('#');
-                    match text.chars().nth(i + 1) {
-                        Some('\\') => {
-                            buffer.push('\\');
-                            if let Some(c) = text.chars().nth(i + 2) {
-                                buffer.push(c);
-                            } else {
-                                return Err((line, "Unexpected EOF while reading character constant"));
-                            }
-                            // Keep fetching until white space or EOF
-                            let mut count = 2;
-                            loop {
-                                let c = text.chars().nth(i + 1 + count);
-                                match c {
-                                    // Every delimiter on  ):
-                                    // This needs a clever way to never include
-                                    // delimiters.
-                                    Some(' ')  |
-                                    Some('\n') |
-                                    Some('\t') |
-                                    Some(')')  |
-                                    Some('(') |
-                                    Some(']') |
-                                    Some('[') |
-                                    Some('"') |
-                                    None => {
-                                        if buffer.len() > 2 {
-                                            tokens.push(buffer.clone());
-                                            buffer = String::new();
-                                            ignore_count = count;
-                                            break;
-                                        } else {
-                                            return Err((line, "Unexpected end of character constant"));
-                                        }
-                                    },
-                                    Some(c) => {
-                                        buffer.push(c);
-                                        count += 1;
-                                    },
-                                }
-                            }
+                    self.advance(source)?;
+                    match self.peek(source)? {
+                        Some('|') => {
+                            self.advance(source)?;
+                            self.skip_block_comment(source)?;
+                        },
+                        Some('[') => {
+                            self.advance(source)?;
+                            return Ok(Some((String::from("#["), here)));
+                        },
+                        Some(c) if c.is_ascii_digit() => {
+                            let mut buffer = String::from("#");
+                            self.read_label(source, &mut buffer)?;
+                            return Ok(Some((buffer, here)));
+                        },
+                        Some(c) if c != '\\' && maj_dispatch_char_p(c) => {
+                            self.advance(source)?;
+                            return Ok(Some((format!("#{}", c), here)));
+                        },
+                        _ => {
+                            let mut buffer = String::from("#");
+                            self.read_character(source, &mut buffer)?;
+                            return Ok(Some((buffer, here)));
                         },
-                        _ => return Err((line, "Unexpected character while reading character constant")),
-                    }
-                    // Parse character
-                    //unimplemented!("Character tokenization");
-                },
-                '\'' => {
-                    if buffer != "" {
-                        tokens.push(buffer.clone());
-                        buffer = String::new();
                     }
-                    tokens.push(String::from("'"));
                 },
-                '`' => {
-                    if buffer != "" {
-                        tokens.push(buffer.clone());
-                        buffer = String::new();
-                    }
-                    tokens.push(String::from("`"));
-                }
-                ',' => {
-                    if buffer != "" {
-                        tokens.push(buffer.clone());
-                        buffer = String::new();
-                    }
-                    let nextchar = text.chars().nth(i + 1);
-                    tokens.push(String::from(
-                        match nextchar {
-                            Some(c) => {
-                                if c == '@' {
-                                    ignore_count = 1;
-                                    ",@"
-                                } else { "," }
-                            },
-                            None => ",",
-                        }));
+                // Anything else is an atom
+                _ => {
+                    let mut buffer = String::new();
+                    self.read_atom(source, &mut buffer)?;
+                    return Ok(Some((buffer, here)));
                 },
+            }
+        }
+    }
+}
+
+pub fn maj_dispatch_char_p(c: char) -> bool {
+    !c.is_whitespace() && !c.is_ascii_digit() && !"|()[]\"'`,@".contains(c)
+}
+
+pub fn maj_dispatch_token_p(token: &str) -> Option<char> {
+    let mut chars = token.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some('#'), Some(c), None)
+            if c != '\\' && !";+-".contains(c)
+            && maj_dispatch_char_p(c) => Some(c),
+        _ => None,
+    }
+}
+
+fn maj_label_token(token: &str, suffix: char) -> Option<usize> {
+    let mut chars = token.chars();
+    if chars.next() != Some('#') || chars.next_back() != Some(suffix) {
+        return None;
+    }
+    let digits = chars.as_str();
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+pub fn maj_label_definition_p(token: &str) -> Option<usize> {
+    maj_label_token(token, '=')
+}
+
+pub fn maj_label_reference_p(token: &str) -> Option<usize> {
+    maj_label_token(token, '#')
+}
+
+impl MajLexer {
+    fn read_atom<S: MajCharSource>(
+        &mut self,
+        source: &mut S,
+        buffer: &mut String
+    ) -> Result<(), MajReadError> {
+        while let Some(c) = self.peek(source)? {
+            match c {
+                ' ' | '\n' | '\t' | '(' | ')' | '[' | ']' |
+                ';' | '\'' | '`' | ',' => break,
                 '@' => {
-                    // Syntax error: @ alone
-                    return Err((line, "'@' should be preceeded by ','"));
+                    return self.lexical_err(
+                        "'@' should be preceeded by ','");
                 },
-                '(' => {
-                    if buffer != "" {
-                        tokens.push(buffer.clone());
-                        buffer = String::new();
-                    }
-                    tokens.push(String::from("("));
+                '"' => return self.read_string(source, buffer),
+                '#' => {
+                    self.advance(source)?;
+                    buffer.push('#');
+                    return self.read_character(source, buffer);
                 },
-                ')' => {
-                    if buffer != "" {
-                        tokens.push(buffer.clone());
-                        buffer = String::new();
-                    }
-                    tokens.push(String::from(")"));
+                _ => {
+                    self.advance(source)?;
+                    buffer.push(c);
                 },
-                '[' => {
-                    if buffer != "" {
-                        tokens.push(buffer.clone());
-                        buffer = String::new();
-                    }
-                    tokens.push(String::from("["));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MajLexer {
+    fn read_string<S: MajCharSource>(
+        &mut self,
+        source: &mut S,
+        buffer: &mut String
+    ) -> Result<(), MajReadError> {
+        self.advance(source)?;
+        buffer.push('"');
+        loop {
+            match self.advance(source)? {
+                Some('"') => {
+                    buffer.push('"');
+                    return Ok(());
                 },
-                ']' => {
-                    if buffer != "" {
-                        tokens.push(buffer.clone());
-                        buffer = String::new();
+                Some('\\') => {
+                    buffer.push('\\');
+                    match self.advance(source)? {
+                        Some(c) => buffer.push(c),
+                        None => return self.lexical_err(
+                            "Unexpected EOF while reading escaped character on string constant"),
                     }
-                    tokens.push(String::from("]"));
                 },
-                
-                // Delimiters
-                ' ' | '\n' | '\t' => {
-                    if buffer != "" {
-                        tokens.push(buffer.clone());
-                        buffer = String::new();
+                Some(c) => buffer.push(c),
+                None => return self.lexical_err(
+                    "Unexpected EOF while reading string constant"),
+            }
+        }
+    }
+}
+
+impl MajLexer {
+    fn read_character<S: MajCharSource>(
+        &mut self,
+        source: &mut S,
+        buffer: &mut String
+    ) -> Result<(), MajReadError> {
+        match self.peek(source)? {
+            Some('\\') => {},
+            Some(_) => return self.lexical_err(
+                "Unexpected character while reading character constant"),
+            None => return self.lexical_err(
+                "Unexpected EOF while reading character constant"),
+        }
+        self.advance(source)?;
+        buffer.push('\\');
+        match self.advance(source)? {
+            Some(c) => buffer.push(c),
+            None => return self.lexical_err(
+                "Unexpected EOF while reading character constant"),
+        }
+        while let Some(c) = self.peek(source)? {
+            match c {
+                ' ' | '\n' | '\t' | '(' | ')' | '[' | ']' | '"' => break,
+                _ => {
+                    self.advance(source)?;
+                    buffer.push(c);
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MajLexer {
+    fn read_label<S: MajCharSource>(
+        &mut self,
+        source: &mut S,
+        buffer: &mut String
+    ) -> Result<(), MajReadError> {
+        while let Some(c) = self.peek(source)? {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            self.advance(source)?;
+            buffer.push(c);
+        }
+        match self.advance(source)? {
+            Some(c) if c == '=' || c == '#' => {
+                buffer.push(c);
+                Ok(())
+            },
+            None => self.lexical_err(
+                "Unexpected EOF while reading datum label"),
+            _ => self.lexical_err("Expected = or # after datum label"),
+        }
+    }
+}
+
+impl MajLexer {
+    fn skip_block_comment<S: MajCharSource>(
+        &mut self,
+        source: &mut S
+    ) -> Result<(), MajReadError> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.advance(source)? {
+                Some('|') => {
+                    if self.peek(source)? == Some('#') {
+                        self.advance(source)?;
+                        depth -= 1;
                     }
-                    if c == '\n' {
-                        line += 1;
+                },
+                Some('#') => {
+                    if self.peek(source)? == Some('|') {
+                        self.advance(source)?;
+                        depth += 1;
                     }
                 },
-
-                // Anything else is pushed
-                _ => buffer.push(c),
+                Some(_) => {},
+                None => return self.lexical_err(
+                    "Unexpected EOF while reading block comment"),
             }
         }
+        Ok(())
     }
-    if buffer != "" {
-        tokens.push(buffer.clone());
+}
+
+pub fn maj_tokenize_located(
+    text: &str
+) -> Result<(Vec<String>, Vec<MajTokenPosition>), (i64, &'static str)> {
+    let mut source = MajStringSource::new(text, 0);
+    let mut lexer = MajLexer::new();
+    let mut tokens = Vec::new();
+    let mut positions = Vec::new();
+    loop {
+        match lexer.next_token(&mut source) {
+            Ok(Some((token, position))) => {
+                tokens.push(token);
+                positions.push(position);
+            },
+            Ok(None) => return Ok((tokens, positions)),
+            Err(MajReadError::Lexical(line, msg)) => return Err((line, msg)),
+            Err(_) => unreachable!("String sources never fail"),
+        }
     }
-    Ok(tokens)
 }
 
-pub fn maj_tokenize_file<'a>(
-    filename: &str,
-    mut buffer: &'a mut String
+pub fn maj_tokenize(
+    text: &str
 ) -> Result<Vec<String>, (i64, &'static str)> {
+    maj_tokenize_located(text).map(|(tokens, _)| tokens)
+}
+
+pub enum MajInputStatus {
+    Complete,
+    Incomplete,
+    Unbalanced,
+}
+
+pub fn maj_input_status(text: &str, dispatch: &[char]) -> MajInputStatus {
+    let mut source = MajStringSource::new(text, 0);
+    let mut lexer = MajLexer::new();
+    let mut depth = 0;
+    let mut pending: usize = 0;
+    loop {
+        match lexer.next_token(&mut source) {
+            Ok(Some((token, _))) => {
+                match token.as_ref() {
+                    "(" | "[" | "#[" => depth += 1,
+                    ")" | "]" => {
+                        if depth == 0 {
+                            return MajInputStatus::Unbalanced;
+                        }
+                        depth -= 1;
+                        if depth == 0 {
+                            pending = pending.saturating_sub(1);
+                        }
+                    },
+                    "'" | "`" | "," | ",@" | "#;" => {
+                        if depth == 0 {
+                            pending = pending.saturating_sub(1) + 1;
+                        }
+                    },
+                    "#+" | "#-" => {
+                        if depth == 0 {
+                            pending = pending.saturating_sub(1) + 2;
+                        }
+                    },
+                    t if maj_dispatch_token_p(t)
+                        .map_or(false, |c| dispatch.contains(&c))
+                        || maj_label_definition_p(t).is_some() => {
+                        if depth == 0 {
+                            pending = pending.saturating_sub(1) + 1;
+                        }
+                    },
+                    _ => {
+                        if depth == 0 {
+                            pending = pending.saturating_sub(1);
+                        }
+                    },
+                }
+            },
+            Ok(None) => break,
+            Err(MajReadError::Lexical(_, msg))
+                if msg.starts_with("Unexpected EOF") => {
+                return MajInputStatus::Incomplete;
+            },
+            Err(_) => return MajInputStatus::Complete,
+        }
+    }
+    if depth > 0 || pending > 0 {
+        MajInputStatus::Incomplete
+    } else {
+        MajInputStatus::Complete
+    }
+}
+
+pub fn maj_read_file(filename: &str) -> Result<String, &'static str> {
     use std::fs::File;
     use std::io::Read;
+    let mut buffer = String::new();
     match File::open(filename) {
         Ok(mut file) => {
             match file.read_to_string(&mut buffer) {
                 Ok(_) => {
                     // Remove shebang line
                     if buffer.len() >= 2 && &buffer[0..2] == "#!" {
-                        *buffer = buffer.replacen("#!", ";;", 1);
+                        buffer = buffer.replacen("#!", ";;", 1);
                     }
-                    maj_tokenize(buffer.as_ref())
+                    Ok(buffer)
                 },
-                Err(_) => Err((0, "Cannot read file")),
+                Err(_) => Err("Cannot read file"),
             }
         },
-        Err(_) => Err((0, "Cannot open file")),
+        Err(_) => Err("Cannot open file"),
     }
 }
@@ -1,11 +1,17 @@
+extern crate test;
+use test::Bencher;
 use regex::Regex;
 use crate::{ maj_list, maj_dotted_list };
 use crate::printing::{ maj_format, maj_format_raw };
 use crate::core::{ MajState, Maj };
 use crate::axioms::predicates::maj_errorp;
 use crate::evaluator::maj_eval;
-use crate::reader::tokenizer::maj_tokenize;
-use crate::reader::parser::maj_parse;
+use crate::reader::tokenizer::{
+    maj_tokenize,
+    maj_tokenize_located,
+    MajTokenPosition
+};
+use crate::reader::parser::maj_read_text;
 use crate::axioms::primitives::{ maj_car, maj_macroexpand_1 };
 
 const RE_IN_STREAM:  &'static str =
@@ -72,7 +78,7 @@ macro_rules! test_parser {
         for n in 0..tokens.len() {
             assert_eq!(tokens[n], expected_tokens[n]);
         }
-        let parsed = maj_parse(&mut $state, tokens).unwrap();
+        let parsed = maj_read_text(&mut $state, $expr).unwrap();
         let fmt    = maj_format_raw(&$state, parsed, false);
         assert_eq!(fmt, $y);
     }
@@ -86,8 +92,8 @@ macro_rules! multi_parser_test {
 
 macro_rules! test_parser_fail {
     ($state:ident, $expr:tt) => {
-        let tokens = maj_tokenize($expr).unwrap();
-        assert!(maj_parse(&mut $state, tokens).is_err());
+        assert!(maj_tokenize($expr).is_ok());
+        assert!(maj_read_text(&mut $state, $expr).is_err());
     }
 }
 
@@ -99,8 +105,7 @@ macro_rules! multi_parser_fail_test {
 
 macro_rules! test_eval {
     ($state:ident, $expr:tt, $y:tt) => {
-        let tokens = maj_tokenize($expr).unwrap();
-        let parsed = maj_parse(&mut $state, tokens).unwrap();
+        let parsed = maj_read_text(&mut $state, $expr).unwrap();
         let result = maj_eval(
             &mut $state,
             Maj::cons(Maj::do_sym(), parsed),
@@ -119,8 +124,7 @@ macro_rules! multi_eval_test {
 
 macro_rules! test_eval_fail {
     ($state:ident, $expr:tt) => {
-        let tokens = maj_tokenize($expr).unwrap();
-        let parsed = maj_parse(&mut $state, tokens).unwrap();
+        let parsed = maj_read_text(&mut $state, $expr).unwrap();
         let result = maj_eval(
             &mut $state,
             Maj::cons(Maj::do_sym(), parsed),
@@ -137,8 +141,7 @@ macro_rules! multi_eval_fail_test {
 
 macro_rules! test_macroexpand_1 {
     ($state:ident, $expr:tt, $y:tt) => {
-        let tokens = maj_tokenize($expr).unwrap();
-        let parsed = maj_parse(&mut $state, tokens).unwrap();
+        let parsed = maj_read_text(&mut $state, $expr).unwrap();
         let parsed = maj_car(parsed);
         let (expanded, worked) =
             maj_macroexpand_1(&mut $state, parsed, Maj::nil());
@@ -643,9 +646,300 @@ fn formatter_error() {
 }
 
 #[test]
-#[ignore]
+fn formatter_readably() {
+    use crate::printing::maj_format_readably;
+    let mut state = MajState::new();
+    let cases = vec![
+        ("'(a \"b\\n\" #\\space 1/2 -3.5 2J3)",
+         "(a \"b\\n\" #\\space 1/2 -3.5 2J3)"),
+        ("''a", "'a"),
+        ("'`(a ,b ,@c)", "`(a ,b ,@c)"),
+        ("'(quote a b)", "(quote a b)"),
+        ("'(1 . 2)", "(1 . 2)"),
+        ("[1 2 3]", "#[1 2 3]"),
+        ("(vector 1 'a \"s\")", "#[1 a \"s\"]"),
+        ("(vector)", "#[]"),
+        (":key", ":key"),
+    ];
+    for (expr, expected) in cases {
+        let parsed = maj_read_text(&mut state, expr).unwrap();
+        let result = maj_eval(&mut state, maj_car(parsed), Maj::nil());
+        assert_eq!(maj_format_readably(&state, result).ok(),
+                   Some(String::from(expected)));
+    }
+
+    let unreadable = vec![
+        "(fn (x) x)",
+        "car",
+        "*stdout*",
+        "(gensym)",
+        "(intern \"a b\")",
+        "(intern \"12\")",
+        "(intern \"\")",
+        "(list 1 (fn () nil))",
+        "(vector (gensym))",
+    ];
+    for expr in unreadable {
+        let parsed = maj_read_text(&mut state, expr).unwrap();
+        let result = maj_eval(&mut state, maj_car(parsed), Maj::nil());
+        assert!(maj_format_readably(&state, result).is_err(),
+                "Printing {} readably", expr);
+    }
+    assert!(maj_format_readably(&state, Maj::float(std::f64::INFINITY))
+            .is_err());
+
+    multi_eval_fail_test!(
+        state;
+        "(let ((*print-readably* t)) (write (fn (x) x) *stdout*))";
+        "(let ((*print-readably* t)) (write (gensym) *stdout*))";
+    );
+}
+
+fn random_datum<R: rand::Rng>(
+    state: &mut MajState,
+    rng: &mut R,
+    depth: u32
+) -> gc::Gc<Maj> {
+    use crate::axioms::utils::simplify_frac_raw;
+    use crate::axioms::primitives::maj_vector;
+    use crate::reader::parser::maj_readable_symbol_p;
+    let choices = if depth == 0 { 7 } else { 10 };
+    match rng.gen_range(0, choices) {
+        0 => Maj::integer(rng.gen()),
+        1 => {
+            let mut num = f64::from_bits(rng.gen());
+            while !num.is_finite() {
+                num = f64::from_bits(rng.gen());
+            }
+            Maj::float(num)
+        },
+        2 => {
+            let (numer, denom) = simplify_frac_raw(
+                rng.gen_range(-1000, 1000),
+                rng.gen_range(1, 1000));
+            if rng.gen_bool(0.5) {
+                Maj::fraction(numer, denom)
+            } else {
+                Maj::complex(Maj::fraction(numer, denom),
+                             Maj::float(rng.gen_range(-10.0, 10.0)))
+            }
+        },
+        3 => Maj::character(random_char(rng)),
+        4 => {
+            let length = rng.gen_range(0, 8);
+            let text: String = (0..length).map(|_| random_char(rng)).collect();
+            Maj::string(&text)
+        },
+        5 => {
+            let alphabet: Vec<char> = "ab1-.:*#;λ'\"".chars().collect();
+            let length = rng.gen_range(1, 5);
+            let name: String = (0..length)
+                .map(|_| alphabet[rng.gen_range(0, alphabet.len())])
+                .collect();
+            if !maj_readable_symbol_p(&name) {
+                return Maj::symbol(state, "a");
+            }
+            Maj::symbol(state, &name)
+        },
+        6 => Maj::nil(),
+        7 => {
+            let length = rng.gen_range(1, 5);
+            let mut list = if rng.gen_bool(0.3) {
+                random_datum(state, rng, 0)
+            } else {
+                Maj::nil()
+            };
+            for _ in 0..length {
+                list = Maj::cons(random_datum(state, rng, depth - 1), list);
+            }
+            list
+        },
+        8 => {
+            let prefix = ["quote", "quasiquote", "unquote", "unquote-splice"]
+                [rng.gen_range(0, 4)];
+            let prefix = Maj::symbol(state, prefix);
+            let datum = random_datum(state, rng, depth - 1);
+            if rng.gen_bool(0.8) {
+                maj_list!(prefix, datum)
+            } else {
+                maj_list!(prefix, datum, Maj::nil())
+            }
+        },
+        _ => {
+            let length = rng.gen_range(0, 4);
+            let mut elements = Maj::nil();
+            for _ in 0..length {
+                elements = Maj::cons(random_datum(state, rng, depth - 1),
+                                     elements);
+            }
+            maj_vector(state, elements)
+        },
+    }
+}
+
+fn random_char<R: rand::Rng>(rng: &mut R) -> char {
+    let special = " \n\t\r\0\x07\x7f()[]\";#\\'`,@|␇";
+    if rng.gen_bool(0.3) {
+        let special: Vec<char> = special.chars().collect();
+        special[rng.gen_range(0, special.len())]
+    } else {
+        std::char::from_u32(rng.gen_range(0, 0x3000)).unwrap()
+    }
+}
+
+fn maj_datum_equal(x: gc::Gc<Maj>, y: gc::Gc<Maj>) -> bool {
+    use crate::core::types::{ MajNumber, MajVector };
+    fn number_equal(x: &MajNumber, y: &MajNumber) -> bool {
+        match (x, y) {
+            (MajNumber::Integer(a), MajNumber::Integer(b)) => a == b,
+            (MajNumber::Float(a), MajNumber::Float(b)) =>
+                a.to_bits() == b.to_bits(),
+            (MajNumber::Fraction(an, ad), MajNumber::Fraction(bn, bd)) =>
+                (an, ad) == (bn, bd),
+            (MajNumber::Complex { real: ar, imag: ai },
+             MajNumber::Complex { real: br, imag: bi }) =>
+                number_equal(ar, br) && number_equal(ai, bi),
+            _ => false,
+        }
+    }
+    match (&*x, &*y) {
+        (Maj::Sym(a), Maj::Sym(b)) => a == b,
+        (Maj::Char(a), Maj::Char(b)) => a == b,
+        (Maj::Number(a), Maj::Number(b)) => number_equal(a, b),
+        (Maj::Cons { car: xa, cdr: xd }, Maj::Cons { car: ya, cdr: yd }) =>
+            maj_datum_equal(xa.clone(), ya.clone())
+            && maj_datum_equal(xd.clone(), yd.clone()),
+        (Maj::Vector(a), Maj::Vector(b)) => match (a, b) {
+            (MajVector::Integer(a), MajVector::Integer(b)) =>
+                *a.borrow() == *b.borrow(),
+            (MajVector::Float(a), MajVector::Float(b)) =>
+                a.borrow().iter().map(|n| n.to_bits())
+                .eq(b.borrow().iter().map(|n| n.to_bits())),
+            (MajVector::Char(a), MajVector::Char(b)) =>
+                *a.borrow() == *b.borrow(),
+            (MajVector::Any(a), MajVector::Any(b)) =>
+                a.borrow().len() == b.borrow().len()
+                && a.borrow().iter().zip(b.borrow().iter())
+                .all(|(a, b)| maj_datum_equal(a.clone(), b.clone())),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+#[test]
+fn formatter_readably_round_trip() {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::printing::maj_format_readably;
+    use crate::axioms::primitives::maj_cdr;
+    use crate::axioms::predicates::maj_nilp;
+    let mut state = MajState::new();
+    let mut rng = StdRng::seed_from_u64(0x6d616a);
+    for _ in 0..1000 {
+        let datum = random_datum(&mut state, &mut rng, 3);
+        let printed = maj_format_readably(&state, datum.clone()).unwrap();
+        let parsed = maj_read_text(&mut state, &printed).unwrap();
+        assert!(maj_nilp(maj_cdr(parsed.clone())).to_bool(),
+                "Reading back {}", printed);
+        assert!(maj_datum_equal(datum, maj_car(parsed)),
+                "Reading back {}", printed);
+    }
+}
+
+#[test]
+fn formatter_circular() {
+    use crate::printing::maj_format_readably;
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(let ((x (list 1 2))) (set-cdr (cdr x) x) x)", "#1=(1 2 . #1#)");
+        ("(let ((x (list 1 2))) (set-car x x) x)", "#1=(#1# 2)");
+        ("(let ((x (list 'a))) (list x x))", "((a) (a))");
+        ("(let ((x (list 'a)))
+            (let ((*print-circle* t))
+              (format \"{}\" (list x x))))",
+         "\"(#1=(a) #1#)\"");
+        ("(let ((x (list 'a))) (format \"{}\" (list x x)))", "\"((a) (a))\"");
+    );
+
+    multi_eval_fail_test!(
+        state;
+        "(let ((x (list 1 2))) (set-cdr (cdr x) x) (length x))";
+        "(let ((x (list 1 2 3))) (set-cdr (cdr (cdr x)) (cdr x)) (length x))";
+        "(let ((x (list 1 2))) (set-car (cdr x) x) (depth x))";
+    );
+
+    let texts = vec!["#1=(a #[b #1#] . #1#)", "#1=(quote . #1#)", "#1=(a '#1#)"];
+    for text in texts {
+        let parsed = maj_car(maj_read_text(&mut state, text).unwrap());
+        let printed = maj_format_readably(&state, parsed).unwrap();
+        let reparsed = maj_car(maj_read_text(&mut state, &printed).unwrap());
+        assert_eq!(maj_format_readably(&state, reparsed).unwrap(), printed);
+    }
+}
+
+#[test]
 fn formatter_prettyprinting() {
-    unimplemented!();
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::printing::maj_pretty_format_margin;
+    fn pretty(state: &mut MajState, expr: &str, margin: usize) -> String {
+        let parsed = maj_read_text(state, expr).unwrap();
+        let result = maj_eval(state, maj_car(parsed), Maj::nil());
+        maj_pretty_format_margin(state, result, margin)
+    }
+
+    let mut state = MajState::new();
+    let fact = "'(defn fact (n) (if (< n 2) 1 (* n (fact (1- n)))))";
+    let cases = vec![
+        (fact, 80, "(defn fact (n) (if (< n 2) 1 (* n (fact (1- n)))))"),
+        (fact, 30, "(defn fact (n)\n  (if (< n 2)\n    1\n    (* n (fact (1- n)))))"),
+        ("'(let ((a 1) (b 2)) (+ a b))", 20,
+         "(let ((a 1) (b 2))\n  (+ a b))"),
+        ("'(def square (fn (x) (* x x)))", 20,
+         "(def square\n  (fn (x) (* x x)))"),
+        ("'(cond ((= x 1) 'one) ((= x 2) 'two) (t 'many))", 30,
+         "(cond\n  ((= x 1) 'one)\n  ((= x 2) 'two)\n  (t 'many))"),
+        ("'(while (< i 10) (print \"{}\" i) (set i (1+ i)))", 25,
+         "(while (< i 10)\n  (print \"{}\" i)\n  (set i (1+ i)))"),
+        ("'(my-form x (a) (b))", 10,
+         "(my-form x\n         (a)\n         (b))"),
+        ("'(1 2 3 4 5 6 7 8 9 10 11 12 13 14 15)", 20,
+         "(1 2 3 4 5 6 7 8 9\n 10 11 12 13 14 15)"),
+        ("'#[(a b) (c d) (e f)]", 10, "[(a b)\n (c d)\n (e f)]"),
+        ("'(a b . c)", 5, "(a b\n   . c)"),
+    ];
+    for (expr, margin, expected) in cases {
+        assert_eq!(pretty(&mut state, expr, margin), expected);
+    }
+
+    multi_eval_test!(
+        state;
+        ("(set-pretty-indent 'my-form 1)", "1");
+    );
+    assert_eq!(pretty(&mut state, "'(my-form x (a) (b))", 10),
+               "(my-form x\n  (a)\n  (b))");
+    multi_eval_test!(
+        state;
+        ("(set-pretty-indent 'my-form nil)", "nil");
+    );
+    assert_eq!(pretty(&mut state, "'(my-form x (a) (b))", 10),
+               "(my-form x\n         (a)\n         (b))");
+
+    multi_eval_fail_test!(
+        state;
+        "(set-pretty-indent 1 2)";
+        "(set-pretty-indent 'my-form -1)";
+        "(set-pretty-indent 'my-form 'a)";
+    );
+
+    let mut rng = StdRng::seed_from_u64(0x6d616a);
+    for _ in 0..1000 {
+        let datum = random_datum(&mut state, &mut rng, 3);
+        assert_eq!(maj_pretty_format_margin(&state, datum.clone(), 10000),
+                   maj_format(&state, datum));
+    }
 }
 
 #[test]
@@ -943,6 +1237,18 @@ fn predicates_proper_list_p() {
             maj_dotted_list!(a, b, c, d)),
          false);
     );
+    multi_eval_test!(
+        state;
+        ("(proper-list-p '#1=(1 . #1#))", "nil");
+        ("(proper-list-p '#1=(1 2 . #1#))", "nil");
+        ("(proper-list-p '(1 . #1=(2 3 . #1#)))", "nil");
+        ("(proper-list-p '(#1=(a) #1#))", "t");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(apply + '#1=(1 . #1#))";
+        "(apply + '(1 . #1=(2 3 . #1#)))";
+    );
 }
 
 #[test]
@@ -1175,6 +1481,44 @@ fn predicates_errorp() {
     );
 }
 
+#[test]
+fn predicates_conditionp() {
+    use crate::axioms::predicates::maj_conditionp;
+    use crate::axioms::primitives::{
+        maj_err,
+        maj_typed_err,
+        maj_error_to_condition
+    };
+    let mut state = MajState::new();
+    let kind = Maj::symbol(&mut state, "my-error");
+    multi_boolean_test!(
+        (maj_conditionp(maj_error_to_condition(
+            maj_err(Maj::string("Some error"),
+                    Maj::nil()))), true);
+        (maj_conditionp(maj_error_to_condition(
+            maj_typed_err(kind, Maj::string("Some error"),
+                          Maj::nil()))), true);
+        (maj_conditionp(maj_err(Maj::string("Some error"),
+                                Maj::nil())), false);
+        (maj_conditionp(Maj::integer(2)), false);
+    );
+}
+
+#[test]
+fn predicates_continuationp() {
+    use crate::axioms::predicates::maj_continuationp;
+    let mut state = MajState::new();
+    let tag = Maj::gensym(&mut state);
+    multi_boolean_test!(
+        (maj_continuationp(
+            maj_list!(Maj::lit(), Maj::continuation(), tag)), true);
+        (maj_continuationp(
+            maj_list!(Maj::lit(), Maj::closure(), Maj::nil(),
+                      Maj::nil(), Maj::nil())), false);
+        (maj_continuationp(Maj::integer(2)), false);
+    );
+}
+
 #[test]
 fn predicates_zerop() {
     use crate::axioms::predicates::maj_zerop;
@@ -1189,6 +1533,23 @@ fn predicates_zerop() {
         maj_zerop(&mut state, Maj::nil(), Maj::t()));
 }
 
+#[test]
+fn predicates_keywordp() {
+    use crate::axioms::predicates::maj_keywordp;
+    let mut state = MajState::new();
+    let keyword = Maj::symbol(&mut state, ":name");
+    let colon = Maj::symbol(&mut state, ":");
+    let symbol = Maj::symbol(&mut state, "name");
+    let gensym = Maj::gensym(&mut state);
+    multi_boolean_test!(
+        (maj_keywordp(&state, keyword), true);
+        (maj_keywordp(&state, colon), false);
+        (maj_keywordp(&state, symbol), false);
+        (maj_keywordp(&state, gensym), false);
+        (maj_keywordp(&state, Maj::string(":name")), false);
+    );
+}
+
 #[test]
 fn primitives_cons() {
     use crate::axioms::primitives::{ maj_cons, maj_err };
@@ -1446,6 +1807,40 @@ fn primitives_get_environment() {
     // TODO: Add application (sum-test)
 }
 
+#[test]
+fn primitives_eval() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(eval '(+ 1 2))", "3");
+        ("(eval (list 'list 1 ''a))", "(1 a)");
+        ("(eval '(let ((z 2)) (* z z)))", "4");
+        ("(eval '(def evaled 42))", "evaled");
+        ("evaled", "42");
+        ("(eval 'evaled (get-environment 'global))", "42");
+        ("(let ((x 5) (y 6))
+            (eval '(+ x y) (get-environment 'lexical)))",
+         "11");
+        ("(let ((x 1))
+            (eval '(set x 10) (get-environment 'lexical))
+            x)",
+         "10");
+        ("(eval (read-from-string \"(cons 1 2)\"))", "(1 . 2)");
+        ("(handler-case (eval '(car 1))
+            (type-error (c) (error-form c)))",
+         "(car 1)");
+    );
+    multi_eval_fail_test!(
+        state;
+        // The caller's lexical environment is not used by default
+        "(let ((x 1)) (eval 'x))";
+        "(eval '(car 1))";
+        "(eval 1 2)";
+        "(eval 1 '(1 2))";
+        "(eval 1 nil nil)";
+    );
+}
+
 #[test]
 fn primitives_coin() {
     use crate::axioms::primitives::maj_coin;
@@ -1696,15 +2091,68 @@ fn primitives_nth() {
 }
 
 #[test]
-#[ignore]
 fn primitives_macroexpand_1() {
-    unimplemented!();
+    let mut state = MajState::new();
+    multi_macroexpand_1_test!(
+        state;
+        ("(when a b)", "(if a (do b) nil)");
+        ("(let* ((a 1) (b a)) b)",
+         "(let ((a 1)) (let* ((b a)) b))");
+        ("5", "5");
+    );
+    multi_eval_test!(
+        state;
+        ("(macroexpand-1 (quote (defn f (x) x)))",
+         "(def f (fn (x) x))");
+    );
 }
 
 #[test]
-#[ignore]
 fn primitives_macroexpand() {
-    unimplemented!();
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(macroexpand (quote (let* ((a 1) (b a)) b)))",
+         "((fn (a) (let* ((b a)) b)) 1)");
+        ("(macroexpand (quote (cond (a 1) (t 2))))",
+         "(if a (do 1) (cond (t 2)))");
+        ("(macroexpand (quote (car x)))", "(car x)");
+        ("(macroexpand (quote x))", "x");
+        ("(macroexpand 5)", "5");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(macroexpand)";
+        "(macroexpand (quote (when)))";
+    );
+}
+
+#[test]
+fn primitives_macroexpand_all() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(macroexpand-all (quote (let* ((a 1) (b a)) b)))",
+         "((fn (a) ((fn (b) (do b)) a)) 1)");
+        ("(macroexpand-all (quote (cond (a 1) (t 2))))",
+         "(if a (do 1) (if t (do 2) nil))");
+        ("(macroexpand-all (quote (fn (x) (when x (quote (when 1))))))",
+         "(fn (x) (if x (do (quote (when 1))) nil))");
+        ("(macroexpand-all (quote (defn g (when) (when 1))))",
+         "(def g (fn (when) (when 1)))");
+        ("(macroexpand-all (quote (letrec ((when (x) x)) (when 1))))",
+         "(letrec ((when (x) x)) (when 1))");
+        ("(macroexpand-all
+            (quote (match x
+                     ((when y) (when y 1))
+                     (_ (unless x 2)))))",
+         "(match x ((when y) (when y 1)) (_ (if (not x) (do 2) nil)))");
+        ("(macroexpand-all
+            (quote (handler-case (when a b)
+                     (error (e) (unless e 1)))))",
+         "(handler-case (if a (do b) nil) (error (e) (if (not e) (do 1) nil)))");
+        ("(macroexpand-all 5)", "5");
+    );
 }
 
 #[test]
@@ -1730,7 +2178,7 @@ fn primitives_gensym() {
     let mut state = MajState::new();
     let n = 100;
     for _ in 0..n {
-        test_regex!(state, maj_gensym(&mut state), r"^:G[0-9]*$");
+        test_regex!(state, maj_gensym(&mut state), r"^#:G[0-9]*$");
     }
 }
 
@@ -2525,9 +2973,55 @@ fn primitives_write() {
 }
 
 #[test]
-#[ignore]
 fn primitives_read() {
-    unimplemented!();
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(multiple-value-list (read-from-string \"(a b) c\"))",
+         "((a b) 5)");
+        ("(multiple-value-list (read-from-string \"(a b) c\" 5))",
+         "(c 7)");
+        ("(multiple-value-bind (x i) (read-from-string \"(a b) c\" 7)
+            (list (eq x *eof*) i))",
+         "(t 7)");
+        ("(multiple-value-bind (x i) (read-from-string \"  ; only a comment\")
+            (list (eq x *eof*) i))",
+         "(t 18)");
+        // The end of input is distinct from any datum
+        ("(read-from-string \"eof\")", "eof");
+        ("(eq (read-from-string \"eof\") *eof*)", "nil");
+        ("(read-from-string \"'x\")", "(quote x)");
+        ("(read-from-string \"#\\\\a\")", "#\\a");
+        ("(read-from-string \"(1 . 2)\")", "(1 . 2)");
+        // The datum is not evaluated
+        ("(read-from-string \"(car '(1 2))\")", "(car (quote (1 2)))");
+        ("(read-all \"1 2/3 (x . y) \\\"str\\\"\")", "(1 2/3 (x . y) \"str\")");
+        ("(read-all \"\")", "nil");
+        // Streams are consumed one datum at a time
+        ("(def *data* (open-stream 'in \"test-read.maj\"))", "*data*");
+        ("(read *data*)", "(name \"data\")");
+        ("(read-char *data*)", "#\\space");
+        ("(read *data*)", "(values 1 2 3)");
+        // Text in data files is decoded as UTF-8
+        ("(read *data*)", "(título \"ação\" #\\é)");
+        ("(read-all *data*)", "(last sym)");
+        ("(eq (read *data*) *eof*)", "t");
+        ("(close-stream *data*)", "t");
+        ("(with-open-stream (s 'in \"test-read.maj\") (length (read-all s)))",
+         "5");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(read-from-string \"(a b\")";
+        "(read-from-string \")\")";
+        "(read-from-string 'a)";
+        "(read-from-string \"a\" 2)";
+        "(read-from-string \"a\" 0 1)";
+        "(read-all \"(a\")";
+        "(read *stdout*)";
+        "(read 2/3)";
+        "(read *data*)";
+    );
 }
 
 #[test]
@@ -2700,13 +3194,14 @@ fn environments_dynamic_bindings() {
     let mut state = MajState::new();
     let my_function = Maj::symbol(&mut state, "my-function");
     let def = Maj::symbol(&mut state, "def");
+    let defvar = Maj::symbol(&mut state, "defvar");
     let my_value = Maj::symbol(&mut state, "*my-value*");
     let fnsym = sym_from_raw(MajRawSym::Fn);
     
     multi_eval_ast_test!(
         state;
-        // (def *my-value* 5)
-        (maj_list!(def.clone(), my_value.clone(),
+        // (defvar *my-value* 5)
+        (maj_list!(defvar.clone(), my_value.clone(),
                    Maj::integer(5)),
          Maj::nil(),
          "*my-value*");
@@ -2732,10 +3227,76 @@ fn environments_dynamic_bindings() {
          "6");
         // (let ((*my-value* 7))
         //   (my-function))
+        (maj_list!(
+            Maj::symbol(&mut state, "let"),
+            maj_list!(maj_list!(my_value.clone(), Maj::integer(7))),
+            maj_list!(my_function.clone())),
+         Maj::nil(),
+         "7");
+        // *my-value*, restored
+        (my_value.clone(), Maj::nil(), "5");
+        // (my-function)
+        // context: ((*my-value* . 8)), ignored for special variables
         (maj_list!(my_function.clone()),
          maj_list!(Maj::cons(my_value.clone(),
-                             Maj::integer(7))),
-         "7");
+                             Maj::integer(8))),
+         "5");
+    );
+}
+
+#[test]
+fn environments_special_variables() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(defvar *depth* 0)", "*depth*");
+        ("(defvar *depth* 10)", "*depth*");
+        ("*depth*", "0");
+        ("(defparameter *depth* 1)", "*depth*");
+        ("*depth*", "1");
+        ("(defvar *unbound*)", "*unbound*");
+        ("(defn depth () *depth*)", "depth");
+        ("(let ((*depth* 2)) (depth))", "2");
+        ("(depth)", "1");
+        ("(defn nested (*depth*) (if (< *depth* 3) (nested (1+ *depth*)) (depth)))",
+         "nested");
+        ("(nested 0)", "3");
+        ("*depth*", "1");
+        ("(let ((*depth* 5)) (set *depth* 6) (depth))", "6");
+        ("*depth*", "1");
+        ("(handler-case (let ((*depth* 7)) (car 1)) (error () (depth)))", "1");
+        ("(catch 'out (let ((*depth* 8)) (throw 'out (depth))))", "8");
+        ("*depth*", "1");
+        ("(def log nil)", "log");
+        ("(handler-case
+            (let ((*depth* 9))
+              (unwind-protect (car 1)
+                (set log (depth))))
+            (error () (list log (depth))))",
+         "(9 1)");
+        ("(call/cc (fn (k) (let ((*depth* 10)) (k (depth)))))", "10");
+        ("(depth)", "1");
+        ("(def capture (let ((*depth* 11)) (fn () *depth*)))", "capture");
+        ("(capture)", "1");
+        ("(let ((*depth* 12)) (let ((x 0)) ((fn (y) (depth)) x)))", "12");
+        ("(defn bump (*depth*) (list (depth) ((fn (x) (depth)) 0)))", "bump");
+        ("(bump 13)", "(13 13)");
+        ("(defn pair (*depth* x) (list (depth) x))", "pair");
+        ("(def curried (pair 14))", "curried");
+        ("(curried 'a)", "(14 a)");
+        ("(depth)", "1");
+        ("(defn opair (*depth* &optional (y 'none)) (list (depth) y))",
+         "opair");
+        ("((opair 15 &))", "(15 none)");
+        ("(defn peek () secret)", "peek");
+        ("(handler-case (let ((secret 1)) (peek)) (error () 'lexical))",
+         "lexical");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(defvar 1 2)";
+        "(defvar)";
+        "(defparameter *depth*)";
     );
 }
 
@@ -2793,14 +3354,14 @@ fn environment_mutability_set() {
         // (mutate-x) altering x globally
         (maj_list!(mutate_x.clone()), Maj::nil(), "11");
         
-        // (mutate-x) altering x dynamically
+        // (mutate-x) ignoring the caller's lexical x
         // context: ((x . 12))
         (maj_list!(mutate_x.clone()),
          maj_list!(Maj::cons(x.clone(), Maj::integer(12))),
-         "14");
+         "13");
 
-        // x, unchanged
-        (x.clone(), Maj::nil(), "11");
+        // x, altered globally
+        (x.clone(), Maj::nil(), "13");
 
         // (defn mutate-x-local () (set x (+ x 2)) x)
         // context: ((x . 12))
@@ -2820,13 +3381,13 @@ fn environment_mutability_set() {
         (maj_list!(mutate_x_local.clone()), Maj::nil(), "14");
         
         // x, unchanged
-        (x.clone(), Maj::nil(), "11");
+        (x.clone(), Maj::nil(), "13");
 
         // (mutate-x-local) changing captured environment
         (maj_list!(mutate_x_local.clone()), Maj::nil(), "16");
 
         // x, unchanged
-        (x.clone(), Maj::nil(), "11");
+        (x.clone(), Maj::nil(), "13");
     );
 }
 
@@ -2881,7 +3442,7 @@ fn environment_mutability_set_car_set_cdr() {
          Maj::nil(),
          "replace-x-elts");
 
-        // (replace-x-elts 20 90)
+        // (replace-x-elts 20 90), ignoring the caller's lexical x
         // context: ((x . (9 . 10)))
         (maj_list!(
             replace_x_elts.clone(),
@@ -2892,7 +3453,7 @@ fn environment_mutability_set_car_set_cdr() {
          "(20 . 90)");
 
         // x
-        (x.clone(), Maj::nil(), "(5 . 6)");
+        (x.clone(), Maj::nil(), "(20 . 90)");
 
         // (replace-x-elts 30 45)
         (maj_list!(replace_x_elts.clone(),
@@ -2984,10 +3545,159 @@ fn reader_strings() {
         state;
         ("\"Hello\\nworld\"",
          vec!["\"Hello\\nworld\""],
-         "(\"Hello\nworld\")");
+         "(\"Hello\\nworld\")");
         ("\"Hello\\tworld\"",
         vec!["\"Hello\\tworld\""],
-        "(\"Hello	world\")");
+        "(\"Hello\\tworld\")");
+        ("\"a\\\"b\\\\c\\rd\\0\"",
+         vec!["\"a\\\"b\\\\c\\rd\\0\""],
+         "(\"a\\\"b\\\\c\\rd\\0\")");
+        ("\"\\x41;\\u{e9}\\u{1F600}\\q\"",
+         vec!["\"\\x41;\\u{e9}\\u{1F600}\\q\""],
+         "(\"Aé😀q\")");
+        ("\"\\x7;\\x1b;ação\"",
+         vec!["\"\\x7;\\x1b;ação\""],
+         "(\"\\x7;\\x1b;ação\")");
+    );
+    multi_parser_fail_test!(
+        state;
+        "\"\\x41\"";
+        "\"\\xZZ;\"";
+        "\"\\x;\"";
+        "\"\\u41\"";
+        "\"\\u{D800}\"";
+        "\"\\u{110000}\"";
+    );
+}
+
+#[test]
+fn reader_characters() {
+    let mut state = MajState::new();
+    multi_parser_test!(
+        state;
+        ("#\\a #\\( #\\⍣", vec!["#\\a", "#\\(", "#\\⍣"],
+         "(#\\a #\\( #\\⍣)");
+        ("#\\space #\\newline #\\tab #\\return #\\escape",
+         vec!["#\\space", "#\\newline", "#\\tab",
+              "#\\return", "#\\escape"],
+         "(#\\space #\\newline #\\tab #\\return #\\escape)");
+        ("#\\nul #\\backspace #\\delete #\\bel #\\␇",
+         vec!["#\\nul", "#\\backspace", "#\\delete",
+              "#\\bel", "#\\␇"],
+         "(#\\nul #\\backspace #\\delete #\\␇ #\\␇)");
+        ("#\\x #\\x41 #\\U+00E9 #\\u+1F600 #\\x1",
+         vec!["#\\x", "#\\x41", "#\\U+00E9", "#\\u+1F600", "#\\x1"],
+         "(#\\x #\\A #\\é #\\😀 #\\x1)");
+    );
+    multi_parser_fail_test!(
+        state;
+        "#\\xyz";
+        "#\\U+";
+        "#\\U+D800";
+        "#\\unknown";
+    );
+}
+
+#[test]
+fn reader_printer_round_trip() {
+    use crate::printing::{ maj_format_char, maj_format_string };
+    let texts = vec!["", "simples", "a\"b\\c", "\n\t\r\0",
+                     "\x07\x1b\x7f\u{85}", "ação ⍣ 😀"];
+    for text in texts {
+        let printed = maj_format_string(text);
+        let mut state = MajState::new();
+        let parsed = maj_car(maj_read_text(&mut state, &printed).unwrap());
+        assert_eq!(parsed.stringify().unwrap(), text,
+                   "Reading back {}", printed);
+    }
+    for code in (0..0x250).chain(0x2400..0x2430) {
+        let c = std::char::from_u32(code).unwrap();
+        let printed = maj_format_char(c);
+        let mut state = MajState::new();
+        let parsed = maj_car(maj_read_text(&mut state, &printed).unwrap());
+        assert_eq!(parsed.to_char(), Some(c), "Reading back {}", printed);
+    }
+}
+
+#[test]
+fn reader_vector_literals() {
+    let mut state = MajState::new();
+    multi_parser_test!(
+        state;
+        ("#[]", vec!["#[", "]"], "([])");
+        ("#[1 2 3]", vec!["#[", "1", "2", "3", "]"], "([1 2 3])");
+        ("#[1.5 2.0]", vec!["#[", "1.5", "2.0", "]"], "([1.5 2.0])");
+        ("#[#\\a #\\b]", vec!["#[", "#\\a", "#\\b", "]"], "(\"ab\")");
+        ("#[a (b c) #;d 'e]",
+         vec!["#[", "a", "(", "b", "c", ")", "#;", "d", "'", "e", "]"],
+         "([a (b c) (quote e)])");
+        ("#[#[1] [2]]",
+         vec!["#[", "#[", "1", "]", "[", "2", "]", "]"],
+         "([[1] (vector 2)])");
+    );
+
+    multi_parser_fail_test!(
+        state;
+        "#[1 2";
+        "#[1 2)";
+        "#[1 . 2]";
+    );
+
+    multi_eval_test!(
+        state;
+        ("#[1 2 3]", "[1 2 3]");
+        ("(vec-type #[1 2 3])", "integer");
+        ("(vec-type '#[a 1])", "any");
+        ("(let ((x 1)) #[x])", "[x]");
+    );
+}
+
+#[test]
+fn reader_datum_labels() {
+    let mut state = MajState::new();
+    multi_parser_test!(
+        state;
+        ("#1=(a . #1#)",
+         vec!["#1=", "(", "a", ".", "#1#", ")"],
+         "(#1=(a . #1#))");
+        ("(#1=(x) #1#)",
+         vec!["(", "#1=", "(", "x", ")", "#1#", ")"],
+         "(((x) (x)))");
+        ("#0=#[a #0#]",
+         vec!["#0=", "#[", "a", "#0#", "]"],
+         "(#1=[a #1#])");
+        ("#1=(a #2=(b . #2#) . #1#)",
+         vec!["#1=", "(", "a", "#2=", "(", "b", ".", "#2#", ")",
+              ".", "#1#", ")"],
+         "(#1=(a #2=(b . #2#) . #1#))");
+        ("#1=(a) #1=(b)",
+         vec!["#1=", "(", "a", ")", "#1=", "(", "b", ")"],
+         "((a) (b))");
+    );
+
+    multi_parser_fail_test!(
+        state;
+        "#1#";
+        "(#1=a #1=b)";
+        "#1=#1#";
+        "#1=";
+        "(#1=a) #1#";
+    );
+
+    assert!(maj_tokenize("#1").is_err());
+    assert!(maj_tokenize("#1a").is_err());
+
+    multi_eval_test!(
+        state;
+        ("'#1=(a . #1#)", "#1=(a . #1#)");
+        ("(let ((x '#1=(a b . #1#))) (eq x (cdr (cdr x))))", "t");
+    );
+    // Circular code is rejected as invalid syntax
+    multi_eval_fail_test!(
+        state;
+        "#1=(if . #1#)";
+        "#1=(def x . #1#)";
+        "(eval '#1=(fn (x) . #1#))";
     );
 }
 
@@ -3024,6 +3734,153 @@ fn reader_comments() {
     );
 }
 
+#[test]
+fn reader_block_and_datum_comments() {
+    let mut state = MajState::new();
+    multi_parser_test!(
+        state;
+        ("#| Comentário |#", vec![], "nil");
+        ("#| Comentários #| aninhados |# continuam
+          em várias linhas |# (+ 1 2)",
+         vec!["(", "+", "1", "2", ")"],
+         "((+ 1 2))");
+        ("(a #| b |# c)", vec!["(", "a", "c", ")"], "((a c))");
+        ("(a #;(b c) d)",
+         vec!["(", "a", "#;", "(", "b", "c", ")", "d", ")"],
+         "((a d))");
+        ("(a #;b)", vec!["(", "a", "#;", "b", ")"], "((a))");
+        ("#;a #;b c", vec!["#;", "a", "#;", "b", "c"], "(c)");
+        ("#;#;a b c", vec!["#;", "#;", "a", "b", "c"], "(c)");
+        ("[1 #;2 3]", vec!["[", "1", "#;", "2", "3", "]"],
+         "((vector 1 3))");
+        ("(a . #;b c)", vec!["(", "a", ".", "#;", "b", "c", ")"],
+         "((a . c))");
+        ("'#;a b", vec!["'", "#;", "a", "b"], "((quote b))");
+    );
+    multi_parser_fail_test!(
+        state;
+        "'#;a";
+        "(a #;)";
+    );
+    assert!(maj_tokenize("#| Sem fim #| |#").is_err());
+}
+
+#[test]
+fn reader_feature_conditionals() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(list #+majestic 1 #-majestic 2)", "(1)");
+        ("#+(and majestic (not no-such-feature)) 'yes", "yes");
+        ("(list #+(or no-such-feature) 1 #-(or) 2)", "(2)");
+        ("(list #+no-such-feature #;1 2 3)", "(3)");
+        ("(list #-no-such-feature #;1 2 3)", "(2 3)");
+        ("(def *features* (cons 'custom *features*))", "*features*");
+        ("#+custom 'on", "on");
+        ("(read-all \"#;a b #+custom c #-custom d\")", "(b c)");
+    );
+    multi_parser_fail_test!(
+        state;
+        "#+(xor a b) c";
+        "#+1 a";
+        "#+(not a b) c";
+    );
+}
+
+#[test]
+fn reader_dispatch_macros() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(functionp
+           (set-dispatch-macro #\\d (fn (r) (list '* 2 (read r)))))",
+         "t");
+        ("#d 21", "42");
+        ("'#d(+ 1 2)", "(* 2 (+ 1 2))");
+        ("(list #d 1 #d #d 1)", "(2 4)");
+        ("(read-from-string \"#d x\")", "(* 2 x)");
+        ("(multiple-value-list (read-from-string \"#d x y\"))",
+         "((* 2 x) 4)");
+        ("(functionp
+           (set-dispatch-macro #\\h
+             (fn (r)
+               (list 'quote
+                     (map (fn (p) (cons (car p) (cadr p)))
+                          (read r))))))",
+         "t");
+        ("#h((a 1) (b 2))", "((a . 1) (b . 2))");
+        // Dispatch macros consume characters themselves
+        ("(functionp
+           (set-dispatch-macro #\\r
+             (fn (r)
+               (let ((s []))
+                 (read-char r)
+                 (until (or (equal (peek-char r) #\\\")
+                            (eq (peek-char r) 'eof))
+                   (vec-push (read-char r) s))
+                 (if (eq (read-char r) 'eof)
+                     (error 'raw-string-error \"Unterminated raw string\")
+                     (vec-coerce 'char s))))))",
+         "t");
+        ("(vec-length #r\"C:\\\")", "3");
+        ("(equal #r\"a\\nb\" \"a\\\\nb\")", "t");
+        ("(functionp
+           (set-dispatch-macro #\\{
+             (fn (r)
+               (let ((s []))
+                 (until (equal (peek-char r) #\\})
+                   (vec-push (read-char r) s))
+                 (read-char r)
+                 (list 'quote (read-all (vec-coerce 'char s)))))))",
+         "t");
+        ("#{a b (c d)}", "(a b (c d))");
+        ("(list #{1 2} 3)", "((1 2) 3)");
+        // Readers are only valid while their macro is applied
+        ("(def saved nil)", "saved");
+        ("(functionp
+           (set-dispatch-macro #\\s (fn (r) (set saved r) (read r))))",
+         "t");
+        ("#s 1", "1");
+        ("(readerp saved)", "t");
+        // Errors raised by dispatch macros are propagated
+        ("(handler-case (read-from-string \"#r\\\"abc\")
+            (raw-string-error (c) (error-message c)))",
+         "\"Unterminated raw string\"");
+        ("(set-dispatch-macro #\\d nil)", "nil");
+        // Discarded forms never apply dispatch macros
+        ("(list #-majestic #q(1 2) 3)", "(3)");
+        ("(list #;#q(1 2) 3)", "(3)");
+        ("(def dispatched 0)", "dispatched");
+        ("(functionp
+           (set-dispatch-macro #\\c
+             (fn (r) (set dispatched (+ dispatched 1)) (read r))))",
+         "t");
+        ("(list #+no-such-feature #c 1 #c 2)", "(2)");
+        ("dispatched", "1");
+        ("(functionp (set-dispatch-macro #\\e (fn (r) (car (read r)))))",
+         "t");
+        ("(handler-case (read-from-string \"#e 1\")
+            (type-error (c) (error-type c)))",
+         "type-error");
+    );
+    multi_parser_fail_test!(
+        state;
+        "#d 1";
+        "#d";
+        "#e 1";
+        "#e";
+    );
+    multi_eval_fail_test!(
+        state;
+        "(read-char saved)";
+        "(read saved)";
+        "(set-dispatch-macro #\\; car)";
+        "(set-dispatch-macro #\\( car)";
+        "(set-dispatch-macro 'a car)";
+        "(set-dispatch-macro #\\q 1)";
+    );
+}
+
 #[test]
 fn reader_quote() {
     let mut state = MajState::new();
@@ -3073,6 +3930,354 @@ fn reader_vector_syntax() {
     );
 }
 
+#[test]
+fn reader_token_positions() {
+    let (tokens, positions) =
+        maj_tokenize_located("(a ; comment\n  (b \"c\nd\") 'e)\n#\\x")
+        .unwrap();
+    assert_eq!(tokens,
+               vec!["(", "a", "(", "b", "\"c\nd\"", ")",
+                    "'", "e", ")", "#\\x"]);
+    assert_eq!(positions,
+               vec![(1, 1), (1, 2), (2, 3), (2, 4), (2, 6), (3, 3),
+                    (3, 5), (3, 6), (3, 7), (4, 1)]);
+}
+
+fn maj_tokenize_legacy(
+    text: &str
+) -> Result<(Vec<String>, Vec<MajTokenPosition>), (i64, &'static str)> {
+    let mut tokens = Vec::new();
+    let mut buffer = String::new();
+    let mut ignore_count = 0;
+    let mut line = 1;
+    let mut line_start = 0;
+    let mut start = (1, 1);
+
+    for (i, c) in text.chars().enumerate() {
+        if ignore_count > 0 {
+            ignore_count -= 1;
+        } else {
+            let here = (line, (i - line_start + 1) as i64);
+            match c {
+                // Read macros
+                ';' => {
+                    let mut count = 1;
+                    loop {
+                        let c = text.chars().nth(i + count);
+                        match c {
+                            Some('\n') | None => {
+                                break;
+                            },
+                            _ => {
+                                count += 1;
+                            }
+                        }
+                    }
+                    // Leave the newline for the delimiters
+                    ignore_count = count - 1;
+                },
+                '\"' => {
+                    // Keep fetching string
+                    if buffer == "" {
+                        start = here;
+                    }
+                    buffer.push('"');
+                    let mut count = 1;
+                    let mut ignore_next = false;
+                    loop {
+                        let c = text.chars().nth(i + count);
+                        if ignore_next {
+                            count += 2;
+                            ignore_next = false;
+                        } else {
+                            match c {
+                                Some('"') => {
+                                    buffer.push('"');
+                                    tokens.push((buffer.clone(), start));
+                                    buffer = String::new();
+                                    ignore_count = count;
+                                    break;
+                                },
+                                Some('\\') => {
+                                    buffer.push('\\');
+                                    if let Some(next) = text.chars().nth(i + count + 1) {
+                                        buffer.push(next);
+                                    } else {
+                                        return Err((line, "Unexpected EOF while reading escaped character on string constant"));
+                                    }
+                                    ignore_next = true;
+                                },
+                                Some(c) => {
+                                    if c == '\n' {
+                                        line += 1;
+                                        line_start = i + count + 1;
+                                    }
+                                    count += 1;
+                                    buffer.push(c);
+                                },
+                                None => {
+                                    return Err((line, "Unexpected EOF while reading string constant"));
+                                },
+                            }
+                        }
+                    }
+                },
+                '#' => {
+                    if buffer == "" {
+                        start = here;
+                    }
+                    buffer.push('#');
+                    match text.chars().nth(i + 1) {
+                        Some('\\') => {
+                            buffer.push('\\');
+                            if let Some(c) = text.chars().nth(i + 2) {
+                                buffer.push(c);
+                            } else {
+                                return Err((line, "Unexpected EOF while reading character constant"));
+                            }
+                            // Keep fetching until white space or EOF
+                            let mut count = 2;
+                            loop {
+                                let c = text.chars().nth(i + 1 + count);
+                                match c {
+                                    // Every delimiter on  ):
+                                    // This needs a clever way to never include
+                                    // delimiters.
+                                    Some(' ')  |
+                                    Some('\n') |
+                                    Some('\t') |
+                                    Some(')')  |
+                                    Some('(') |
+                                    Some(']') |
+                                    Some('[') |
+                                    Some('"') |
+                                    None => {
+                                        if buffer.len() > 2 {
+                                            tokens.push((buffer.clone(), start));
+                                            buffer = String::new();
+                                            ignore_count = count;
+                                            break;
+                                        } else {
+                                            return Err((line, "Unexpected end of character constant"));
+                                        }
+                                    },
+                                    Some(c) => {
+                                        buffer.push(c);
+                                        count += 1;
+                                    },
+                                }
+                            }
+                        },
+                        _ => return Err((line, "Unexpected character while reading character constant")),
+                    }
+                    // Parse character
+                    //unimplemented!("Character tokenization");
+                },
+                '\'' => {
+                    if buffer != "" {
+                        tokens.push((buffer.clone(), start));
+                        buffer = String::new();
+                    }
+                    tokens.push((String::from("'"), here));
+                },
+                '`' => {
+                    if buffer != "" {
+                        tokens.push((buffer.clone(), start));
+                        buffer = String::new();
+                    }
+                    tokens.push((String::from("`"), here));
+                }
+                ',' => {
+                    if buffer != "" {
+                        tokens.push((buffer.clone(), start));
+                        buffer = String::new();
+                    }
+                    let nextchar = text.chars().nth(i + 1);
+                    tokens.push((String::from(
+                        match nextchar {
+                            Some(c) => {
+                                if c == '@' {
+                                    ignore_count = 1;
+                                    ",@"
+                                } else { "," }
+                            },
+                            None => ",",
+                        }), here));
+                },
+                '@' => {
+                    // Syntax error: @ alone
+                    return Err((line, "'@' should be preceeded by ','"));
+                },
+                '(' => {
+                    if buffer != "" {
+                        tokens.push((buffer.clone(), start));
+                        buffer = String::new();
+                    }
+                    tokens.push((String::from("("), here));
+                },
+                ')' => {
+                    if buffer != "" {
+                        tokens.push((buffer.clone(), start));
+                        buffer = String::new();
+                    }
+                    tokens.push((String::from(")"), here));
+                },
+                '[' => {
+                    if buffer != "" {
+                        tokens.push((buffer.clone(), start));
+                        buffer = String::new();
+                    }
+                    tokens.push((String::from("["), here));
+                },
+                ']' => {
+                    if buffer != "" {
+                        tokens.push((buffer.clone(), start));
+                        buffer = String::new();
+                    }
+                    tokens.push((String::from("]"), here));
+                },
+                
+                // Delimiters
+                ' ' | '\n' | '\t' => {
+                    if buffer != "" {
+                        tokens.push((buffer.clone(), start));
+                        buffer = String::new();
+                    }
+                    if c == '\n' {
+                        line += 1;
+                        line_start = i + 1;
+                    }
+                },
+
+                // Anything else is pushed
+                _ => {
+                    if buffer == "" {
+                        start = here;
+                    }
+                    buffer.push(c);
+                },
+            }
+        }
+    }
+    if buffer != "" {
+        tokens.push((buffer.clone(), start));
+    }
+    Ok(tokens.into_iter().unzip())
+}
+
+#[test]
+fn reader_tokenizer_regression() {
+    let mut texts: Vec<String> = vec![
+        "",
+        "  \t\n  ",
+        "(def x 5)",
+        "(a ; comment\n  (b \"c\nd\") 'e)\n#\\x",
+        "; only a comment",
+        "(a ;c)\n b)",
+        "'(1 2 . 3) `(a ,b ,@c) ,,@d",
+        "[1 2 'a 3 (+ 2 5)]",
+        "1/2 3J2/5 -4.5 +7 1.0e10",
+        "\"Hello, \\\"world\\\"!\" \"a\\\\b\" \"\"",
+        "(print \"{}\" #\\( #\\) #\\[ #\\] #\\space #\\newline)",
+        "#\\a) #\\ab\"c\" #\\\"",
+        "foo#\\a bar",
+        "abc\"def\"ghi",
+        "(λ (x) '⍣ #\\⍣ \"ação\")",
+        "a,b'c`d",
+        "@",
+        "a@b",
+        "(a . @b)",
+        "\"abc",
+        "\"abc\\",
+        "(x \"multi\nline",
+        "#",
+        "# a",
+        "#\\",
+        "(a #(b))",
+    ].into_iter().map(String::from).collect();
+
+    let mut files: Vec<_> = std::fs::read_dir("examples").unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    files.sort();
+    for file in files {
+        texts.push(std::fs::read_to_string(file).unwrap());
+    }
+
+    for text in texts {
+        assert_eq!(maj_tokenize_located(&text),
+                   maj_tokenize_legacy(&text),
+                   "Tokenizing {:?}", text);
+    }
+}
+
+fn maj_large_data() -> (String, usize) {
+    let mut text = String::from("(def data '(");
+    let mut entries = 0;
+    while text.len() < 300_000 {
+        text.push_str(&format!("(entry {} \"name-{}\" ({}.5 {}/3) #\\a)\n",
+                               entries, entries, entries, entries + 1));
+        entries += 1;
+    }
+    text.push_str("))\n");
+    (text, entries)
+}
+
+#[test]
+fn reader_large_input() {
+    let (text, entries) = maj_large_data();
+    let (tokens, _) = maj_tokenize_located(&text).unwrap();
+    // Five tokens open the data, two close it, and each entry has ten
+    assert_eq!(tokens.len(), 7 + 10 * entries);
+
+    let mut state = MajState::new();
+    let forms = maj_read_text(&mut state, &text).unwrap();
+    let result = maj_eval(&mut state, maj_car(forms), Maj::nil());
+    assert!(!maj_errorp(result).to_bool());
+    let length = maj_read_text(&mut state, "(length data)").unwrap();
+    let length = maj_eval(&mut state, maj_car(length), Maj::nil());
+    assert_eq!(length.to_integer(), Some(entries as i64));
+}
+
+#[bench]
+fn benchmark_load_large_file(b: &mut Bencher) {
+    let (text, _) = maj_large_data();
+    let path = std::env::temp_dir().join("majestic-large-data.maj");
+    std::fs::write(&path, text).unwrap();
+    let mut state = MajState::new();
+    let load = format!("(load {:?})", path.to_str().unwrap());
+    let load = maj_car(maj_read_text(&mut state, &load).unwrap());
+    b.iter(|| {
+        let result = maj_eval(&mut state, load.clone(), Maj::nil());
+        assert!(!maj_errorp(result).to_bool());
+    });
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn reader_input_status() {
+    use crate::reader::tokenizer::{ maj_input_status, MajInputStatus };
+    let complete = vec!["", "(a b)", "'x", "#\\(", "\"(\"", "(a ; (\n b)",
+                        "\"abc\\\" def\"", "@", "#d 1", "#z", "(a #z)",
+                        "#1a", "#a"];
+    let incomplete = vec!["(a", "[1 2", "'", "(a `", "\"abc", "(a \"b)",
+                          "#\\", "(a ,@", "#d", "(a #d", "#", "#1", "#| a",
+                          "(a #z"];
+    let unbalanced = vec![")", "(a))", "]"];
+    for text in complete {
+        assert!(matches!(maj_input_status(text, &['d']), MajInputStatus::Complete),
+                "{:?} should be complete", text);
+    }
+    for text in incomplete {
+        assert!(matches!(maj_input_status(text, &['d']), MajInputStatus::Incomplete),
+                "{:?} should be incomplete", text);
+    }
+    for text in unbalanced {
+        assert!(matches!(maj_input_status(text, &['d']), MajInputStatus::Unbalanced),
+                "{:?} should be unbalanced", text);
+    }
+}
+
 #[test]
 fn evaluator_quote() {
     let mut state = MajState::new();
@@ -3084,6 +4289,40 @@ fn evaluator_quote() {
     );
 }
 
+#[test]
+fn evaluator_keywords() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        (":name", ":name");
+        ("':name", ":name");
+        ("(list :a 1 :b 2)", "(:a 1 :b 2)");
+        ("(eq :a ':a)", "t");
+        ("(keywordp :a)", "t");
+        ("(keywordp 'a)", "nil");
+        ("(keywordp (gensym))", "nil");
+        ("(let ((g (gensym))) (eq g (intern (name g))))", "nil");
+        ("(defsyntax kw-list () ((_ x) (list :key x)))", "kw-list");
+        ("(kw-list 1)", "(:key 1)");
+        ("(closurep (eval '(fn (x) x) (get-environment :global)))", "t");
+        ("((fn (&key (a :none)) a))", ":none");
+        ("((fn (&optional (a :none)) a))", ":none");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(get-environment :nowhere)";
+        "(open-stream :sideways \"file.txt\")";
+        "(fn (:a) 1)";
+        "(fn (a . :rest) 1)";
+        "(fn ((a :b)) a)";
+        "(fn (&key (:a 1)) 1)";
+        "(mac (:a) 1)";
+        "(let ((:a 1)) :a)";
+        "(def :a 1)";
+        "(defvar :b)";
+    );
+}
+
 #[test]
 fn evaluator_def() {
     let mut state = MajState::new();
@@ -3391,6 +4630,478 @@ fn evaluator_unwind_protect() {
     );
 }
 
+#[test]
+fn evaluator_catch_throw() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(catch 'done (throw 'done 5) 10)", "5");
+        ("(catch 'done 10)", "10");
+        ("(catch 'outer
+            (catch 'inner (throw 'outer 1))
+            2)",
+         "1");
+        ("(def cleaned nil)", "cleaned");
+        ("(catch 'done
+            (unwind-protect (throw 'done 'thrown)
+              (set cleaned t)))",
+         "thrown");
+        ("cleaned", "t");
+        ("(catch 'done
+            (handler-case (throw 'done 'passed)
+              (error () 'caught)))",
+         "passed");
+        ("(catch 'done (and (throw 'done 1) 2))", "1");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(throw 'nowhere 1)";
+        "(catch 'done (throw 'other 1))";
+    );
+}
+
+#[test]
+fn evaluator_handler_case() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(handler-case (+ 1 2) (error () 'caught))", "3");
+        ("(handler-case (car 1) (error () 'caught))", "caught");
+        ("(handler-case (err \"Untyped\") (error () 'untyped))",
+         "untyped");
+        ("(handler-case (error 'my-error \"Something {}\" 1)
+            (other-error () 'other)
+            (my-error (c) (list 'mine (conditionp c))))",
+         "(mine t)");
+        ("(handler-case (error 'my-error \"Something {}\" 1)
+            (my-error (c)
+              (list (error-type c)
+                    (error-message c)
+                    (error-irritants c))))",
+         "(my-error \"Something 1\" (1))");
+        ("(handler-case
+              (handler-case (error 'inner \"Inner\")
+                (other () 'other))
+            (inner () 'outer))",
+         "outer");
+        ("(handler-case
+              (handler-case (error 'inner \"Inner\")
+                (inner (c) (signal c)))
+            (inner () 'signaled))",
+         "signaled");
+        ("(def cleaned nil)", "cleaned");
+        ("(handler-case
+              (unwind-protect (car 1)
+                (set cleaned t))
+            (error () cleaned))",
+         "t");
+        ("(ignore-errors (car 1))", "nil");
+        ("(ignore-errors 1 2)", "2");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(handler-case (error 'my-error \"Oops\") (other () 'no))";
+        "(error 'my-error \"Oops\")";
+        "(signal 'my-error \"Oops\")";
+        "(error \"Oops\" \"Oops\")";
+    );
+}
+
+#[test]
+fn evaluator_call_cc() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(call/cc (fn (k) 1))", "1");
+        ("(call/cc (fn (k) (k 2) 3))", "2");
+        ("(+ 1 (call/cc (fn (k) (+ 10 (k 2)))))", "3");
+        ("(call/cc (fn (k) (k)))", "nil");
+        ("(call/cc (fn (outer)
+            (call/cc (fn (inner) (outer 1)))
+            2))",
+         "1");
+        ("(defn find-first (p lst)
+            (call/cc
+             (fn (return)
+               (mapc (fn (x) (when (p x) (return x))) lst)
+               nil)))",
+         "find-first");
+        ("(find-first (fn (x) (> x 2)) '(1 2 3 4))", "3");
+        ("(find-first (fn (x) (> x 5)) '(1 2 3 4))", "nil");
+        ("(def cleaned nil)", "cleaned");
+        ("(call/cc (fn (k)
+            (unwind-protect (k 5)
+              (set cleaned t))))",
+         "5");
+        ("cleaned", "t");
+        ("(handler-case (call/cc (fn (k) (k 1)))
+            (error () 'caught))",
+         "1");
+        ("(def saved (call/cc (fn (k) k)))", "saved");
+        ("(continuationp saved)", "t");
+        ("(functionp saved)", "t");
+        ("(handler-case (saved 1) (error () 'dead))", "dead");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(saved 1)";
+        "(call/cc (fn (k) (k 1 2)))";
+        "(call/cc 1)";
+    );
+}
+
+#[test]
+fn evaluator_coroutines() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(def co (generator (yield 1) (yield 2) 3))", "co");
+        ("(coroutinep co)", "t");
+        ("(coroutine-status co)", "suspended");
+        ("(list (resume co) (resume co))", "(1 2)");
+        ("(list (resume co) (coroutine-status co))", "(3 dead)");
+        // Arguments start the coroutine, and later become yield values
+        ("(def acc (make-coroutine
+                    (fn (start)
+                      (let ((total start))
+                        (while t
+                          (set total (+ total (yield total))))))))",
+         "acc");
+        ("(list (resume acc 10) (resume acc 5) (resume acc 7))",
+         "(10 15 22)");
+        ("(def echo (generator (list (yield 1) (yield 2))))", "echo");
+        ("(list (resume echo) (resume echo 'a) (resume echo 'b))",
+         "(1 2 (a b))");
+        ("(resume (generator (cond ((yield 1) 2) (t 3))))", "1");
+        ("(resume (generator (and t (or nil (yield 'deep)))))", "deep");
+        // Generators are consumed with for
+        ("(defn count-to (n)
+            (generator
+              (let ((i 0))
+                (while (< i n)
+                  (set i (1+ i))
+                  (yield i)))))",
+         "count-to");
+        ("(let ((acc nil))
+            (for (x (count-to 4)) (set acc (cons x acc)))
+            acc)",
+         "(4 3 2 1)");
+        ("(for (x (count-to 0)) (err \"unreachable\"))", "nil");
+        // Nesting
+        ("(defn squares (gen) (generator (for (x gen) (yield (* x x)))))",
+         "squares");
+        ("(let ((acc nil))
+            (for (x (squares (squares (count-to 3))))
+              (set acc (cons x acc)))
+            acc)",
+         "(81 16 1)");
+        ("(defn zip-gen (a b)
+            (generator
+              (for (x a)
+                (yield (list x (resume b))))))",
+         "zip-gen");
+        ("(let ((acc nil))
+            (for (p (zip-gen (count-to 2) (count-to 5)))
+              (set acc (cons p acc)))
+            acc)",
+         "((2 2) (1 1))");
+        // Errors end the coroutine
+        ("(def bad (generator (yield 1) (car 1) (yield 2)))", "bad");
+        ("(resume bad)", "1");
+        ("(handler-case (resume bad) (type-error (c) (error-form c)))",
+         "(car 1)");
+        ("(coroutine-status bad)", "dead");
+        ("(handler-case (for (x (generator (yield 1) (err \"boom\")))
+                           x)
+            (error (c) (error-message c)))",
+         "\"boom\"");
+        // Loops may yield, break and continue
+        ("(defn collect (gen)
+            (let ((acc nil))
+              (for (x gen) (set acc (cons x acc)))
+              (reverse acc)))",
+         "collect");
+        ("(collect (generator (dotimes (i 5) (yield i))))", "(0 1 2 3 4)");
+        ("(collect (generator
+                     (dolist (x '(1 2 3 4))
+                       (when (= x 2) (continue))
+                       (yield (* x 10)))))",
+         "(10 30 40)");
+        ("(collect (generator (dovec (c \"ab\") (yield c))))", "(#\\a #\\b)");
+        ("(collect (generator
+                     (loop for x in '(1 2 3 4 5)
+                           do (when (= x 4) (break))
+                              (yield x))))",
+         "(1 2 3)");
+        ("(def looped (generator (yield (dotimes (i 3) (yield i) (break 'out)))))",
+         "looped");
+        ("(list (resume looped) (resume looped) (coroutine-status looped))",
+         "(0 out suspended)");
+        ("(resume (generator (catch 'tag (yield 1) 2)))", "1");
+        ("(let ((co (generator (catch 'tag (yield 1) (throw 'tag 'thrown)))))
+            (list (resume co) (resume co)))",
+         "(1 thrown)");
+        // Yielding through unwind-protect and handler-case
+        ("(collect (generator (unwind-protect (yield 1) (yield 2))))",
+         "(1 2)");
+        ("(def cleaned nil)", "cleaned");
+        ("(def guarded (generator
+                         (unwind-protect (do (yield 1) (car 1))
+                           (set cleaned t))))",
+         "guarded");
+        ("(list (resume guarded) cleaned)", "(1 nil)");
+        ("(handler-case (resume guarded) (type-error (c) cleaned))", "t");
+        ("(collect (generator
+                     (handler-case (do (yield 1) (car 1))
+                       (type-error (c) (yield (error-form c))))))",
+         "(1 (car 1))");
+        ("(let ((co (generator
+                      (catch 'tag
+                        (handler-case (do (yield 1) (throw 'tag 'out))
+                          (error () 'handled))))))
+            (list (resume co) (resume co)))",
+         "(1 out)");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(resume bad)";
+        "(resume co)";
+        "(yield 1)";
+        "(resume 1)";
+        "(make-coroutine car)";
+        "(for (x '(1 2)) x)";
+        "(resume (generator (mapc (fn (x) (yield x)) '(1 2))))";
+        "(resume (generator (letrec ((f () 1)) (yield (f)))))";
+        "(resume (generator (multiple-value-bind (a b) (values 1 2) (yield a))))";
+        "(resume (generator (apply list (yield 1) nil)))";
+        "(def self (generator (resume self))) (resume self)";
+        "(def started (generator (yield 1))) (resume started) (resume started 1 2)";
+    );
+}
+
+#[test]
+fn evaluator_coroutines_release() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        // Finished coroutines leave the table at once
+        ("(def done (generator (yield 1) 2))", "done");
+        ("(list (resume done) (resume done))", "(1 2)");
+        // Abandoned coroutines go away with their objects
+        ("(let ((i 0))
+            (while (< i 200)
+              (resume (generator (yield i) (yield i)))
+              (set i (1+ i))))",
+         "i");
+    );
+    gc::force_collect();
+    assert_eq!(state.live_coroutines(), 0);
+    multi_eval_test!(
+        state;
+        ("(def kept (generator (yield 1) (yield 2)))", "kept");
+        ("(resume kept)", "1");
+    );
+    gc::force_collect();
+    assert_eq!(state.live_coroutines(), 1);
+    multi_eval_test!(
+        state;
+        ("(resume kept)", "2");
+        ("(coroutine-status kept)", "suspended");
+    );
+}
+
+#[test]
+fn evaluator_multiple_values() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(values 1 2)", "1");
+        ("(values)", "nil");
+        ("(+ 1 (values 2 3))", "3");
+        ("(multiple-value-list (values 1 2 3))", "(1 2 3)");
+        ("(multiple-value-list (values))", "nil");
+        ("(multiple-value-list (values nil t))", "(nil t)");
+        ("(multiple-value-list 5)", "(5)");
+        ("(multiple-value-list (do (values 1 2) 3))", "(3)");
+        ("(multiple-value-list (list (values 1 2)))", "((1))");
+        ("(multiple-value-list (if t (values 1 2) 3))", "(1 2)");
+        ("(def x 5)", "x");
+        ("(multiple-value-list (do (values x 10) x))", "(5)");
+        ("(multiple-value-list (let ((y (values 1 2))) y))", "(1)");
+        ("(multiple-value-list ((fn (y) y) (values x 2)))", "(5)");
+        ("(multiple-value-list (car (list (values x 2))))", "(5)");
+        ("(multiple-value-list (or (values x 2) 3))", "(5)");
+        ("(multiple-value-list (or nil (values x 2)))", "(5 2)");
+        ("(multiple-value-list ((fn () (values 1 2))))", "(1 2)");
+        ("(defn split-first (lst) (values (car lst) (cdr lst)))",
+         "split-first");
+        ("(split-first '(1 2 3))", "1");
+        ("(multiple-value-bind (head tail) (split-first '(1 2 3))
+            (list head tail))",
+         "(1 (2 3))");
+        ("(multiple-value-bind (a b c) (values 1 2) (list a b c))",
+         "(1 2 nil)");
+        ("(multiple-value-bind (a) (values 1 2) a)", "1");
+        ("(multiple-value-bind (a b) 5 (list a b))", "(5 nil)");
+        ("(multiple-value-bind () (values 1 2))", "nil");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(multiple-value-list)";
+        "(multiple-value-list (values 1) 2)";
+        "(multiple-value-list (car 1))";
+        "(multiple-value-bind (a 1) (values 1 2) a)";
+        "(multiple-value-bind a (values 1 2) a)";
+    );
+}
+
+#[test]
+fn evaluator_match() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(match 5 (1 'one) (5 'five))", "five");
+        ("(match \"b\" (\"a\" 1) (\"b\" 2))", "2");
+        ("(match nil ((a . b) 'cons) (nil 'empty))", "empty");
+        ("(match 'foo ('bar 1) ('foo 2))", "2");
+        ("(match '(1 2) ('(1 2) 'same))", "same");
+        ("(match 3 (x (* x x)))", "9");
+        ("(match '(1 2) ((_ b) b))", "2");
+        ("(match '(1 2 3) ((a b) 'two) ((a . rest) (list a rest)))",
+         "(1 (2 3))");
+        ("(match '(1 (2 3)) ((a (b c)) (+ a b c)))", "6");
+        ("(match '(1 . 2) ((a . 2) a))", "1");
+        ("(match '(add 1 2) (('sub x y) (- x y)) (('add x y) (+ x y)))", "3");
+        ("(match 3 ((? stringp) 'string) ((? numberp x) (* x 2)))", "6");
+        ("(match '(1 \"a\") (((? numberp n) (? stringp s)) (list s n)))",
+         "(\"a\" 1)");
+        ("(match [1 2] ([a b] (+ a b)))", "3");
+        ("(match [1 2 3] ([a b] 'two) (_ 'other))", "other");
+        ("(match '(1 2) ([a b] 'vector) ((a b) 'list))", "list");
+        ("(match 4 (x when (> x 5) 'big) (x 'small))", "small");
+        ("(match 9 (x when (> x 5) 'big) (x 'small))", "big");
+        ("(match 1 (a))", "nil");
+        ("(match 5 (:foo 'yes) (_ 'no))", "no");
+        ("(match :foo (:foo 'yes))", "yes");
+        ("(match '(:add 1 2) ((:sub x y) (- x y)) ((:add x y) (+ x y)))",
+         "3");
+        ("(defn simplify (expr)
+            (match expr
+              (('+ 0 x) (simplify x))
+              (('+ x 0) (simplify x))
+              (('* 1 x) (simplify x))
+              ((op a b) (list op (simplify a) (simplify b)))
+              (x x)))",
+         "simplify");
+        ("(simplify '(* 1 (+ x 0)))", "x");
+        ("(simplify '(+ (* 1 y) z))", "(+ y z)");
+        ("(handler-case (match 7 (1 'one))
+            (match-error (e) (error-type e)))",
+         "match-error");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(match 7 (1 'one))";
+        "(match)";
+        "(match 1 2)";
+        "(match 1 (x when))";
+        "(match 1 ((? car) 'never))";
+        "(match 1 ((? numberp x y) x))";
+    );
+}
+
+#[test]
+fn evaluator_error_context() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(handler-case (car 1) (error (c) (error-type c)))",
+         "type-error");
+        ("(handler-case undefined-variable
+            (error (c) (error-type c)))",
+         "unbound-variable");
+        ("(handler-case ((fn (x) x) 1 2)
+            (error (c) (error-type c)))",
+         "arity-error");
+        ("(handler-case (open-stream 'in \"/nonexistent/file.txt\")
+            (error (c) (error-type c)))",
+         "io-error");
+        ("(handler-case (err \"Untyped\")
+            (error (c) (error-type c)))",
+         "error");
+        ("(handler-case (car 1) (error (c) (error-form c)))",
+         "(car 1)");
+        ("(handler-case (car 1) (error (c) (error-message c)))",
+         "\"1 is not a cons cell\"");
+        ("(defn my-car (x) (car x))", "my-car");
+        ("(handler-case (my-car 1) (error (c) (error-form c)))",
+         "(car x)");
+        ("(handler-case (my-car 1) (error (c) (error-stack c)))",
+         "((car x) (my-car 1))");
+        ("(handler-case (my-car 1)
+            (type-error (c) (error-irritants c)))",
+         "(1)");
+        ("(handler-case
+              (handler-case (my-car 1)
+                (type-error (c) (signal c)))
+            (error (c) (error-form c)))",
+         "(car x)");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(error-type 1)";
+        "(error-message 'not-a-condition)";
+    );
+}
+
+#[test]
+fn evaluator_error_location() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(load \"test-locations.maj\")", "location-car");
+        ("(handler-case (location-car 1)
+            (error (c) (error-location c)))",
+         "(\"test-locations.maj\" 3 3)");
+        ("(handler-case (location-car 1)
+            (error (c) (error-form c)))",
+         "(car x)");
+        ("(handler-case (car 1) (error (c) (error-location c)))",
+         "nil");
+        ("(handler-case (load \"test-locations-fail.maj\")
+            (error (c) (error-location c)))",
+         "(\"test-locations-fail.maj\" 3 3)");
+        ("(handler-case (load \"test-locations-fail.maj\")
+            (error (c) (error-message c)))",
+         "\"On evaluation of file test-locations-fail.maj:3:3: not-a-list is not a cons cell\"");
+    );
+}
+
+#[test]
+fn evaluator_backtrace() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(backtrace)", "nil");
+        ("(defn bt-car (x) (car x))", "bt-car");
+        ("(defn bt-here () (backtrace))", "bt-here");
+        ("(bt-here)", "((bt-here nil nil))");
+        ("(handler-case (bt-car 1) (error (c) (backtrace c)))",
+         "((car (1) nil) (bt-car (1) nil))");
+        ("(handler-case ((fn (x) (car x)) 1)
+            (error (c) (closurep (car (cadr (backtrace c))))))",
+         "t");
+        ("(load \"test-locations.maj\")", "location-car");
+        ("(handler-case (location-car 1) (error (c) (backtrace c)))",
+         "((car (1) (\"test-locations.maj\" 3 3)) (location-car (1) nil))");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(backtrace 1)";
+        "(handler-case (car 1) (error (c) (backtrace c c)))";
+    );
+}
+
 #[test]
 #[ignore]
 fn evaluator_closure_application_default() {
@@ -3404,9 +5115,63 @@ fn evaluator_closure_application_partial() {
 }
 
 #[test]
-#[ignore]
 fn evaluator_closure_application_optional_args() {
-    unimplemented!();
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("((fn (a &optional b (c 10)) (list a b c)) 1)", "(1 nil 10)");
+        ("((fn (a &optional b (c 10)) (list a b c)) 1 2 3)", "(1 2 3)");
+        ("((fn (a &optional (b (* a 2))) (list a b)) 4)", "(4 8)");
+        ("((fn (&optional a) a))", "nil");
+        ("((fn (&optional a . rest) (list a rest)) 1 2 3)", "(1 (2 3))");
+        ("(closurep ((fn (a b &optional (c 3)) (list a b c)) 1))", "t");
+        ("(((fn (a b &optional (c 3)) (list a b c)) 1) 2)", "(1 2 3)");
+        ("(closurep ((fn (a &optional (b 3)) (list a b)) 1 &))", "t");
+        ("(((fn (a &optional (b 3)) (list a b)) 1 &) 9)", "(1 9)");
+        ("(((fn (a &optional (b 3)) (list a b)) 1 &))", "(1 3)");
+        ("(defn pad (x &optional (n 2)) (list x n))", "pad");
+        ("(pad 'a)", "(a 2)");
+        ("(pad 'a 5)", "(a 5)");
+        ("(defmac inc (x &optional (by 1)) `(+ ,x ,by))", "inc");
+        ("(inc 5)", "6");
+        ("(inc 5 10)", "15");
+        ("(letfn ((f (x &optional (y x)) (* x y))) (f 3))", "9");
+    );
+    multi_eval_fail_test!(
+        state;
+        "((fn (a &optional b) b))";
+        "((fn (&optional a) a) 1 2)";
+        "((fn (a &optional (b (car 1))) b) 1)";
+        "((fn (&optional 1) nil))";
+    );
+}
+
+#[test]
+fn evaluator_closure_application_keyword_args() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("((fn (a &key b (c 10)) (list a b c)) 1)", "(1 nil 10)");
+        ("((fn (a &key b (c 10)) (list a b c)) 1 :c 3 :b 2)", "(1 2 3)");
+        ("((fn (&key (a 1) (b (+ a 1))) (list a b)) :a 5)", "(5 6)");
+        ("((fn (a &optional (b 2) &key (c 3)) (list a b c)) 1)", "(1 2 3)");
+        ("((fn (a &optional (b 2) &key (c 3)) (list a b c)) 1 4 :c 5)",
+         "(1 4 5)");
+        ("((fn (&key a . opts) (list a opts)) :b 2 :a 1)",
+         "(1 (:b 2 :a 1))");
+        ("(closurep ((fn (a &key b) (list a b)) 1 &))", "t");
+        ("(((fn (a &key b) (list a b)) 1 &) :b 2)", "(1 2)");
+        ("(defn make-point (&key (x 0) (y 0)) (list x y))", "make-point");
+        ("(make-point :y 3)", "(0 3)");
+        ("(letfn ((f (x &key (y 1)) (+ x y))) (f 2 :y 5))", "7");
+    );
+    multi_eval_fail_test!(
+        state;
+        "((fn (&key a) a) ':b 2)";
+        "((fn (&key a) a) ':a)";
+        "((fn (&key a &optional b) a))";
+        "((fn (a &key b) b) 1 2 &)";
+    );
 }
 
 #[test]
@@ -3544,6 +5309,188 @@ fn evaluator_primitives_application() {
     unimplemented!();
 }
 
+#[test]
+fn macros_preexpansion() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        // Closure bodies are expanded once, on creation
+        ("(defn f (x) (when x (list x)))", "f");
+        ("f", "(lit closure nil (x) ((if x (do (list x)) nil)))");
+        ("(f 1)", "(1)");
+        ("(f nil)", "nil");
+        // Macros defined later are still expanded when applied
+        ("(defn g () (later 2))", "g");
+        ("(defmac later (x) (quasiquote (list (unquote x) (unquote x))))",
+         "later");
+        ("(g)", "(2 2)");
+        // Local bindings shadow macros
+        ("(defn h (when) (when 5))", "h");
+        ("(h 1+)", "6");
+        ("(letrec ((unless (x) (* x 2))) (unless 3))", "6");
+        // Quoted data is never expanded
+        ("(defn q () (quote (when a b)))", "q");
+        ("(q)", "(when a b)");
+        // Nested closures created in a loop
+        ("(defn counter (n)
+            (let ((fs nil))
+              (while (> n 0)
+                (set fs (cons (fn () (unless nil n)) fs))
+                (set n (1- n)))
+              (map (fn (f) (f)) fs)))",
+         "counter");
+        ("(counter 3)", "(0 0 0)");
+        // Expansion errors only show up when the form is evaluated
+        ("(defmac broken (x) (car x))", "broken");
+        ("(defn b (x) (if x (broken 1) (quote ok)))", "b");
+        ("(b nil)", "ok");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(b t)";
+    );
+}
+
+#[test]
+fn macros_weak_tables() {
+    use crate::core::state::MajWeakTable;
+    let mut table = MajWeakTable::new();
+    let kept = Maj::cons(Maj::t(), Maj::nil());
+    table.insert(&kept, 0);
+    for i in 1..1000 {
+        let temporary = Maj::cons(Maj::integer(i), Maj::nil());
+        table.insert(&temporary, i);
+    }
+    gc::force_collect();
+    assert_eq!(table.live(), 1);
+    assert_eq!(table.get(&kept), Some(&0));
+    // New objects never inherit the entries of collected ones
+    for i in 1..1000 {
+        let fresh = Maj::cons(Maj::integer(i), Maj::nil());
+        assert!(table.get(&fresh).is_none());
+    }
+}
+
+#[test]
+fn macros_compiled_calls() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        // Compiled calls keep their original form
+        ("(defn cc-car (x) (car x))", "cc-car");
+        ("cc-car", "(lit closure nil (x) ((car x)))");
+        ("(cc-car (quote (1 2)))", "1");
+        ("(handler-case (cc-car 1) (error (c) (error-form c)))",
+         "(car x)");
+        ("(handler-case (cc-car 1) (error (c) (backtrace c)))",
+         "((car (1) nil) (cc-car (1) nil))");
+        // Partial application still curries
+        ("(defn cc-cons (x) (cons x))", "cc-cons");
+        ("((cc-cons 1) 2)", "(1 . 2)");
+        // Local bindings are never compiled
+        ("(let ((car cdr)) (car (quote (1 2))))", "(2)");
+        ("(defn cc-local (car) (car (quote (1 2))))", "cc-local");
+        ("(cc-local cdr)", "(2)");
+        // macroexpand-all does not compile
+        ("(symbolp (car (macroexpand-all (quote (car x)))))", "t");
+        // Inline functions are fixed at compile time
+        ("(defn cc-sq (x) (* x x))", "cc-sq");
+        ("(defn cc-late (x) (cc-sq x))", "cc-late");
+        ("(declaim (inline cc-sq))", "nil");
+        ("(defn cc-early (x) (cc-sq x))", "cc-early");
+        ("(def cc-sq (fn (x) (+ x x)))", "cc-sq");
+        ("(cc-late 3)", "6");
+        ("(cc-early 3)", "9");
+        // Primitives declared notinline follow redefinitions
+        ("(declaim (notinline car))", "nil");
+        ("(defn cc-head (x) (car x))", "cc-head");
+        ("(def car cdr)", "car");
+        ("(cc-head (quote (1 2)))", "(2)");
+        // Calls compiled before a primitive is redefined follow it too
+        ("(cc-car (quote (1 2)))", "(2)");
+        ("(defn cc-length (x) (length x))", "cc-length");
+        ("(cc-length (quote (1 2)))", "2");
+        ("(let ((length 0)) (set length 1) length)", "1");
+        ("(cc-length (quote (1 2)))", "2");
+        ("(set length (fn (x) 'redefined))", "length");
+        ("(cc-length (quote (1 2)))", "redefined");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(proclaim (quote inline))";
+        "(proclaim (quote (frobnicate cc-sq)))";
+        "(proclaim (quote (inline 1)))";
+    );
+}
+
+#[test]
+fn macros_syntax_rules() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(defsyntax swap! ()
+            ((_ a b) (let ((tmp a)) (set a b) (set b tmp))))",
+         "swap!");
+        // Symbols bound by the expansion never capture user variables
+        ("(let ((tmp 1) (other 2))
+            (swap! tmp other)
+            (list tmp other))",
+         "(2 1)");
+        // Ellipses and recursive rules
+        ("(defsyntax my-or ()
+            ((_) nil)
+            ((_ e) e)
+            ((_ e rest ...) (let ((x e)) (if x x (my-or rest ...)))))",
+         "my-or");
+        ("(my-or)", "nil");
+        ("(my-or nil nil 3)", "3");
+        ("(let ((x 5)) (my-or nil x))", "5");
+        // Local bindings never capture global functions
+        ("(defsyntax pairs () ((_ x ...) (list (cons x x) ...)))", "pairs");
+        ("(let ((list 'oops) (cons 'oops)) (pairs 1 2))",
+         "((1 . 1) (2 . 2))");
+        ("(defn f (list) (pairs list 2))", "f");
+        ("(f 7)", "((7 . 7) (2 . 2))");
+        // Global references are resolved when evaluated
+        ("(defn helper (x) (* x 2))", "helper");
+        ("(defsyntax twice () ((_ x) (helper x)))", "twice");
+        ("(defn use-twice (helper) (twice helper))", "use-twice");
+        ("(use-twice 3)", "6");
+        ("(defn helper (x) (* x 3))", "helper");
+        ("(use-twice 3)", "9");
+        // Literals
+        ("(defsyntax my-if (then else)
+            ((_ c then a else b) (if c a b)))",
+         "my-if");
+        ("(my-if t then 1 else 2)", "1");
+        ("(my-if nil then 1 else 2)", "2");
+        // Binding forms introduced by templates
+        ("(defsyntax first-or ()
+            ((_ e d) (match e ((x . _) x) (_ d))))",
+         "first-or");
+        ("(let ((x 10)) (first-or nil x))", "10");
+        ("(first-or '(1 2) 0)", "1");
+        ("(defsyntax while2 ()
+            ((_ c body ...)
+             (letrec ((loop () (when c body ... (loop)))) (loop))))",
+         "while2");
+        ("(let ((i 0) (loop 10))
+            (while2 (< i 3) (set i (1+ i)))
+            (list i loop))",
+         "(3 10)");
+        ("(macroexpand-1 '(pairs))", "(list)");
+        ("(defsyntax bad () ((_ x ...) x))", "bad");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(my-if nil foo 1 else 2)";
+        "(bad 1 2)";
+        "(syntax-rules (1) ((_ x) x))";
+        "(syntax-rules () (x x))";
+        "(syntax-rules)";
+    );
+}
+
 #[test]
 fn macros_cond() {
     let mut state = MajState::new();
@@ -3736,3 +5683,174 @@ fn macros_with_open_stream() {
          "#\\H");
     }
 }
+
+#[test]
+fn macros_generic_functions() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(defgeneric gf-kind (x) (quote thing))", "gf-kind");
+        ("(genericp gf-kind)", "t");
+        ("(functionp gf-kind)", "t");
+        ("gf-kind", "(lit generic gf-kind)");
+        ("(defmethod gf-kind ((x integer)) (list (quote integer) (call-next-method)))",
+         "gf-kind");
+        ("(defmethod gf-kind ((x float)) (quote float))", "gf-kind");
+        ("(defmethod gf-kind ((x number)) (list (quote number) (call-next-method)))",
+         "gf-kind");
+        ("(gf-kind 1)", "(integer (number thing))");
+        ("(gf-kind 1.5)", "float");
+        ("(gf-kind 1/2)", "(number thing)");
+        ("(gf-kind (quote (1 2)))", "thing");
+        // Redefining a method replaces it
+        ("(defmethod gf-kind ((x float)) (quote real))", "gf-kind");
+        ("(gf-kind 1.5)", "real");
+        // Dispatch on more than one argument
+        ("(defgeneric gf-join (a b))", "gf-join");
+        ("(defmethod gf-join ((a integer) (b vector)) (quote iv))", "gf-join");
+        ("(defmethod gf-join ((a vector) b) (quote vt))", "gf-join");
+        ("(defmethod gf-join ((a list) (b list)) (quote ll))", "gf-join");
+        ("(gf-join 1 \"a\")", "iv");
+        ("(gf-join [1] 2)", "vt");
+        ("(gf-join nil (quote (1)))", "ll");
+        // call-next-method may receive new arguments
+        ("(defgeneric gf-scale (x))", "gf-scale");
+        ("(defmethod gf-scale ((x number)) (* x 10))", "gf-scale");
+        ("(defmethod gf-scale ((x integer)) (call-next-method (1+ x)))",
+         "gf-scale");
+        ("(gf-scale 1)", "20");
+        // Methods create their generic function
+        ("(defmethod gf-auto ((x symbol)) x)", "gf-auto");
+        ("(gf-auto (quote a))", "a");
+        // Redefinition keeps methods; without methods, arity may change
+        ("(defgeneric gf-kind (y))", "gf-kind");
+        ("(gf-kind 1)", "(integer (number thing))");
+        ("(defgeneric gf-empty (x))", "gf-empty");
+        ("(defgeneric gf-empty (x y))", "gf-empty");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(gf-join 1 1)";
+        "(gf-scale (quote a))";
+        "(defmethod gf-join ((a integer)) a)";
+        "(defgeneric gf-join (a))";
+        "(defgeneric gf-kind (x y))";
+        "(defmethod gf-auto ((x symbol)) (call-next-method))
+         (gf-auto (quote a))";
+    );
+}
+
+#[test]
+fn macros_records() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(defrecord point x y)", "point");
+        ("(def p (make-point 1 2))", "p");
+        ("p", "(lit record point (x y) 1 2)");
+        ("(recordp p)", "t");
+        ("(type p)", "point");
+        ("(list (point-p p) (point-p 1))", "(t nil)");
+        ("(list (point-x p) (point-y p))", "(1 2)");
+        ("(record-set p (quote x) 5)", "5");
+        ("(point-x p)", "5");
+        ("(defrecord empty)", "empty");
+        ("(make-empty)", "(lit record empty nil)");
+        // Records may be dispatched upon
+        ("(defmethod rec-area ((p point)) (* (point-x p) (point-y p)))",
+         "rec-area");
+        ("(defmethod rec-area ((r record)) 0)", "rec-area");
+        ("(list (rec-area p) (rec-area (make-empty)))", "(10 0)");
+        // Records are printed through print-object
+        ("(defmethod print-object ((p point)) (format \"<{},{}>\" (point-x p) (point-y p)))",
+         "print-object");
+        ("(format \"{}\" p)", "\"<5,2>\"");
+        ("(format \"{}\" (list p [p]))", "\"(<5,2> [<5,2>])\"");
+        ("(format \"{}\" (make-empty))", "\"#<record empty>\"");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(make-point 1 2 3)";
+        "(make-record (quote point) (quote (x y)) 1)";
+        "(record-get p (quote z))";
+        "(record-get 1 (quote x))";
+        "(defmethod print-object ((e empty)) 1)
+         (format \"{}\" (make-empty))";
+    );
+}
+
+#[test]
+fn macros_iteration() {
+    let mut state = MajState::new();
+    multi_eval_test!(
+        state;
+        ("(let ((acc nil)) (dotimes (i 4) (set acc (cons i acc))) acc)",
+         "(3 2 1 0)");
+        ("(dotimes (i 3 (list 'done i)) i)", "(done 3)");
+        ("(dotimes (i 0) (err \"unreachable\"))", "nil");
+        ("(let ((acc nil))
+            (dolist (x '(1 2 3) (reverse acc))
+              (set acc (cons (* x 10) acc))))",
+         "(10 20 30)");
+        ("(let ((acc nil)) (dovec (x [1 2 3] acc) (set acc (cons x acc))))",
+         "(3 2 1)");
+        ("(let ((n 0)) (dovec (c \"abc\") (set n (1+ n))) n)", "3");
+        // Early exits
+        ("(dotimes (i 10) (when (= i 4) (break (* i i))))", "16");
+        ("(dolist (x '(1 2 3)) (break))", "nil");
+        ("(let ((acc nil))
+            (dolist (x '(1 2 3 4 5) (reverse acc))
+              (when (= x 3) (continue))
+              (set acc (cons x acc))))",
+         "(1 2 4 5)");
+        ("(let ((acc nil))
+            (dotimes (i 3)
+              (dotimes (j 3)
+                (when (> j i) (break))
+                (set acc (cons (list i j) acc))))
+            (length acc))",
+         "6");
+        ("(let ((log nil))
+            (list (dotimes (i 5)
+                    (unwind-protect
+                      (when (= i 2) (break 'out))
+                      (set log (cons i log))))
+                  log))",
+         "(out (2 1 0))");
+        // Structured loop
+        ("(loop for x in '(1 2 3) collect (* x x))", "(1 4 9)");
+        ("(loop for x across [3 9 2] maximize x)", "9");
+        ("(loop for x in '(5 3 8) minimize x)", "3");
+        ("(loop for i from 1 to 10 when (> i 5) sum i)", "40");
+        ("(loop for i from 10 downto 1 by 3 collect i)", "(10 7 4 1)");
+        ("(loop for i from 0 below 3 for x in '(a b c d) collect (list i x))",
+         "((0 a) (1 b) (2 c))");
+        ("(loop repeat 3 collect 'x)", "(x x x)");
+        ("(loop for x in '(1 2 3 4 5) while (< x 4) collect x)", "(1 2 3)");
+        ("(loop for x in '(1 2 3 4 5) until (> x 2) collect x)", "(1 2)");
+        ("(loop for x in '(1 2 3) unless (= x 2) collect x)", "(1 3)");
+        ("(loop for x in '(1 2 3 4) do (when (= x 2) (continue)) collect x)",
+         "(1 3 4)");
+        ("(loop for x in '(1 2 3) collect x do (when (= x 2) (break)))",
+         "(1 2)");
+        ("(loop for i from 1 do (when (> i 3) (break (list 'big i))))",
+         "(big 4)");
+        ("(loop for x in '(1 2))", "nil");
+        ("(loop for x in nil sum x)", "0");
+        ("(let ((i 0)) (loop (set i (1+ i)) (when (> i 5) (break i))))",
+         "6");
+    );
+    multi_eval_fail_test!(
+        state;
+        "(break)";
+        "(continue)";
+        "(loop for x in '(1 2) collect x sum x)";
+        "(loop for 1 in '(1))";
+        "(loop for x on '(1))";
+        "(loop for x in)";
+        "(loop frobnicate)";
+        "(loop when t)";
+        "(loop for x in '(1) when t for y in '(2))";
+        "(dolist (x '(1 2)) (car x))";
+    );
+}